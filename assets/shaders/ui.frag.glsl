@@ -1,10 +1,14 @@
 precision mediump float;
 varying vec2 vUV;
 varying vec2 vViewport;
-uniform vec4 uColor;
+varying vec4 vColor;
 uniform float uTime;
 uniform int uEffectMode;
 uniform float uProgress;
+uniform vec4 uTint;
+uniform float uGlobalAlpha;
+uniform sampler2D uTexture;
+uniform int uUseTexture;      // 1 while drawing textured quads
 
 // Noise function for turbulence
 float noise(vec2 p) {
@@ -39,14 +43,14 @@ vec2 vortex(vec2 p, float t) {
 
 void main() {
   if (uEffectMode == 0) {
-    gl_FragColor = uColor;
+    gl_FragColor = uUseTexture == 1 ? texture2D(uTexture, vUV) * vColor : vColor;
   } else if (uEffectMode == 1) {  // Reveal: mathematical wave interference
     vec2 p = vUV * 2.0 - 1.0;
     float t = uTime * 2.0;
     float angle = length(p) * 6.2832 + t;
     float wave = sin(angle * 8.0) * 0.5 + 0.5;
     wave *= sin(p.x * 20.0 + t * 1.5) * sin(p.y * 20.0 + t * 1.7);
-    gl_FragColor = vec4(uColor.rgb, uColor.a * wave);
+    gl_FragColor = vec4(vColor.rgb, vColor.a * wave);
   } else if (uEffectMode == 2) {  // ULTRA RADICAL PLASMA CHAOS
     // Correct for aspect ratio to prevent distortion
     vec2 p = vUV * 2.0 - 1.0;
@@ -184,6 +188,7 @@ void main() {
         alpha = (1.0 - uProgress) * 5.0; // Quick fade out
     }
 
-    gl_FragColor = vec4(col, alpha);
+    gl_FragColor = vec4(col, alpha) * uTint;
   }
+  gl_FragColor.a *= uGlobalAlpha;
 }
\ No newline at end of file
@@ -1,9 +1,14 @@
-use crate::config::Config;
-use crate::features::pomodoro::PomodoroMode;
-use crate::gfx::{anim::{Timeline, lerp}, draw::DrawContext, math::{Color, Rect, Vec2}};
-use crate::wayland::ActiveSurface;
+use crate::config::{ClockFace, Config, SecondsStyle};
+use crate::features::{alarm::Alarm, battery::Battery, clock::{self, Clock, ColorMode}, countdown::Countdown, pomodoro::{Pomodoro, PomodoroMode}, sysmon::SysMon, Feature};
+use crate::keybindings::{Action, Keybindings, Modifiers};
+use crate::state::State;
+use crate::stats::Stats;
+use crate::gfx::{anim::{Easing, Timeline, lerp}, draw::DrawContext, math::{Color, Rect, Vec2}};
+use crate::wayland::{window_manager::WindowId, ActiveSurface};
 use anyhow::Result;
-use log::info;
+use log::{info, warn};
+use std::any::Any;
+use std::time::Instant;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum UiMode {
@@ -18,10 +23,51 @@ pub enum UiEvent {
     PointerEnter { pos: Vec2 },
     PointerLeave,
     PointerMove { pos: Vec2 },
-    PointerDown { pos: Vec2, button: u32 },
+    PointerDown { pos: Vec2, button: u32, surface: Option<ActiveSurface> },
     PointerUp,
-    Scroll { delta: f32, surface: Option<ActiveSurface> },
-    Key(u32),
+    /// First touch point going down; moves and lifts arrive as PointerMove/PointerUp
+    TouchDown { pos: Vec2, surface: Option<ActiveSurface> },
+    /// The compositor took the touch sequence over; drop the gesture
+    TouchCancel,
+    Scroll { delta: f32, surface: Option<ActiveSurface>, modifiers: Modifiers },
+    Key { keysym: u32, modifiers: Modifiers },
+}
+
+/// Pointer travel before a left press on the clock turns into a drag
+const DRAG_THRESHOLD: f32 = 4.0;
+
+/// Holding a touch this long without dragging starts or stops the pomodoro
+const LONG_PRESS_SECONDS: f32 = 0.5;
+
+/// Brightness change per shift+scroll step on the clock
+const BRIGHTNESS_STEP: f32 = 0.1;
+
+/// Shift+scrolling stops here so the digits never vanish entirely
+const MIN_BRIGHTNESS: f32 = 0.1;
+
+/// Quiet time after a color mode, seconds or brightness change before it's saved
+const STATE_SAVE_DELAY: f32 = 2.0;
+
+/// A touch in progress: a tap on release, or a long-press once `hold` completes
+#[derive(Debug, Clone)]
+pub struct TouchPress {
+    hold: Timeline,
+    long_pressed: bool,
+}
+
+/// Left-button gesture on the clock; a click unless it moves far enough to drag
+#[derive(Debug, Clone, Copy)]
+pub struct ClockDrag {
+    /// Surface-local press position; the surface follows the pointer so
+    /// every motion is measured against it
+    press: Vec2,
+    /// Margins the surface sits at for the motion events still coming in.
+    /// Catches up with `margins` once main applies them, so events queued
+    /// before the surface moved don't count the same offset twice
+    placed: [f32; 2],
+    /// Horizontal and vertical margin being dragged, kept fractional
+    margins: [f32; 2],
+    dragging: bool,
 }
 
 pub struct App {
@@ -33,41 +79,168 @@ pub struct App {
     pub expand_timeline: Timeline,
     pub hover: bool,
     pub last_frame_time: f32,
+    /// Seconds since `epoch`, for animation timing. Things that must line up
+    /// with the wall clock's seconds (colon blink, heartbeat, seconds bar)
+    /// use its sub-second fraction instead, see `Clock::tick`.
     pub time: f32,
+    /// Monotonic origin of `time`, taken at startup
+    epoch: Instant,
 
     // Click detection
     pub last_click_time: f32,
     pub click_count: u32,
 
-    // Pomodoro
-    pub pomodoro: crate::features::pomodoro::Pomodoro,
+    /// Clock, pomodoro and any optional features, updated and rendered uniformly
+    pub features: Vec<Box<dyn Feature>>,
+    /// Logical size of the output the clock is on
     pub screen_size: Option<[u32; 2]>,
 
+    // Dragging the clock around; main applies the margins when flagged
+    pub drag: Option<ClockDrag>,
+    pub margins_changed: bool,
+    /// Last pointer position, for routing scrolls over an inline timer
+    pointer: Option<Vec2>,
+    pub touch: Option<TouchPress>,
+
     // Clock settings
     pub show_seconds: bool,
-    pub color_mode: u8,
+    pub color_mode: ColorMode,
+    pub brightness: f32,
+    /// Starts at `clock.blink_colon`; clicking a colon flips it for this
+    /// session without touching the config
+    pub blink_colon: bool,
+    /// Hide the clock while a pomodoro runs, see `toggle_focus`
+    pub focus: bool,
+    /// Starts at `clock.face`, see `toggle_face`
+    pub face: ClockFace,
+    /// When the clock settings last changed without being saved, see `State`
+    state_changed_at: Option<f32>,
+
+    keybindings: Keybindings,
+    /// Set by the quit binding; main ends the loop
+    pub quit_requested: bool,
+    /// Input or a clock tick since the last frame, see `take_redraw`
+    redraw: bool,
 }
 
 impl App {
-    pub fn new(config: Config) -> Self {
+    /// `state` overrides the config's starting color mode and seconds;
+    /// `stats` are the pomodoro counts so far
+    pub fn new(config: Config, state: State, stats: Stats) -> Self {
         let logical_size = [config.collapsed_size.width, config.collapsed_size.height];
-        Self {
+        let color_mode = ColorMode::from_config(state.color_mode.as_deref().unwrap_or(&config.clock.color_mode));
+        let brightness = state.brightness.unwrap_or(config.clock.brightness).clamp(0.0, 1.0);
+        let blink_colon = config.clock.blink_colon;
+        let focus = state.focus.unwrap_or(config.pomodoro.hide_clock_while_counting);
+        let face = state.face.unwrap_or(config.clock.face);
+
+        let mut pomodoro = Pomodoro::new(&config.pomodoro, stats);
+        pomodoro.set_effects(&config.effects);
+        let mut features: Vec<Box<dyn Feature>> = vec![
+            Box::new(Clock::new(&config, color_mode)),
+            Box::new(pomodoro),
+        ];
+        if let Some(name) = config.world_clock.timezone.as_deref() {
+            match time_tz::timezones::get_by_name(name) {
+                Some(tz) => features.push(Box::new(Clock::world(&config, color_mode, tz))),
+                None => warn!("Unknown world_clock.timezone '{}', world clock disabled", name),
+            }
+        }
+        if config.battery.enabled {
+            features.push(Box::new(Battery::new(&config)));
+        }
+        if config.sysmon.enabled {
+            features.push(Box::new(SysMon::new(&config.sysmon)));
+        }
+        if let Some(countdown) = Countdown::new(&config) {
+            features.push(Box::new(countdown));
+        }
+        let alarm = Alarm::new(&config.alarm);
+        if alarm.is_enabled() {
+            features.push(Box::new(alarm));
+        }
+        for feature in &features {
+            info!("Registered feature: {}", feature.name());
+        }
+
+        let keybindings = Keybindings::from_config(&config.keybindings);
+
+        let mut app = Self {
             config,
             mode: UiMode::Collapsed,
             scale: 1.0,
             logical_size,
             buffer_size: logical_size,
-            expand_timeline: Timeline::new(0.15), // 150ms animation
+            // Springy overshoot; runs a little past 150ms while it settles
+            expand_timeline: Timeline::with_easing(0.15, Easing::Spring { stiffness: 400.0, damping: 24.0 }),
             hover: false,
             last_frame_time: 0.0,
             time: 0.0,
+            epoch: Instant::now(),
             last_click_time: 0.0,
             click_count: 0,
-            pomodoro: crate::features::pomodoro::Pomodoro::new(),
+            features,
             screen_size: None,
-            show_seconds: true,
-            color_mode: 0,
+            drag: None,
+            margins_changed: false,
+            pointer: None,
+            touch: None,
+            show_seconds: state.show_seconds.unwrap_or(true),
+            color_mode,
+            brightness,
+            blink_colon,
+            focus,
+            face,
+            state_changed_at: None,
+            keybindings,
+            quit_requested: false,
+            redraw: true,
+        };
+        let style = app.seconds_style();
+        for clock in app.clocks_mut().filter(|c| !c.is_world_clock()) {
+            clock.set_seconds_style(style);
+        }
+        let brightness = app.brightness;
+        for clock in app.clocks_mut() {
+            clock.set_brightness(brightness);
+            clock.set_face(face);
         }
+        app
+    }
+
+    pub fn feature<T: Feature>(&self) -> Option<&T> {
+        self.features.iter().find_map(|f| (f.as_ref() as &dyn Any).downcast_ref::<T>())
+    }
+
+    pub fn feature_mut<T: Feature>(&mut self) -> Option<&mut T> {
+        self.features.iter_mut().find_map(|f| (f.as_mut() as &mut dyn Any).downcast_mut::<T>())
+    }
+
+    fn clocks_mut(&mut self) -> impl Iterator<Item = &mut Clock> {
+        self.features.iter_mut().filter_map(|f| (f.as_mut() as &mut dyn Any).downcast_mut::<Clock>())
+    }
+
+    pub fn pomodoro(&self) -> &Pomodoro {
+        self.feature().expect("pomodoro feature is always registered")
+    }
+
+    pub fn pomodoro_mut(&mut self) -> &mut Pomodoro {
+        self.feature_mut().expect("pomodoro feature is always registered")
+    }
+
+    /// Whether any feature currently draws into `window`
+    pub fn wants_window(&self, window: WindowId) -> bool {
+        self.features.iter().any(|f| f.window() == Some(window))
+    }
+
+    /// Preferred size of `window`, from the first feature drawing into it
+    pub fn window_size(&self, window: WindowId) -> Option<[u32; 2]> {
+        self.features.iter()
+            .find(|f| f.window() == Some(window))
+            .map(|f| {
+                let (width, height) = f.desired_expanded_size();
+                [width, height]
+            })
     }
 
     pub fn set_screen_size(&mut self, size: [u32; 2]) {
@@ -75,87 +248,464 @@ impl App {
     }
 
     pub fn start_pomodoro(&mut self) {
-        self.pomodoro.start(self.time);
+        let now = self.time;
+        self.pomodoro_mut().start(now);
     }
 
-    pub fn toggle_expand(&mut self) {
-        match self.mode {
-            UiMode::Collapsed => {
-                self.mode = UiMode::Expanding;
-                self.expand_timeline.start(self.time);
+    /// Cycle the pomodoro duration from the keyboard, briefly showing the
+    /// timer so the new value is visible even when idle
+    fn adjust_duration(&mut self, delta: f32) {
+        let now = self.time;
+        let pomodoro = self.pomodoro_mut();
+        pomodoro.cycle_duration(delta);
+        pomodoro.flash_duration(now);
+    }
+
+    pub fn toggle_pomodoro(&mut self) {
+        if matches!(self.pomodoro().mode, PomodoroMode::Idle) {
+            info!("Starting pomodoro");
+            self.start_pomodoro();
+        } else if matches!(self.pomodoro().mode, PomodoroMode::Counting { .. } | PomodoroMode::Paused { .. } | PomodoroMode::Stopwatch { .. }) {
+            // If already running, stop the timer (go back to idle)
+            info!("Stopping pomodoro");
+            self.pomodoro_mut().stop();
+        }
+    }
+
+    pub fn toggle_seconds(&mut self) {
+        self.show_seconds = !self.show_seconds;
+        info!("Toggled seconds display: {}", self.show_seconds);
+        self.apply_seconds_style();
+        self.state_changed_at = Some(self.time);
+    }
+
+    pub fn toggle_blink_colon(&mut self) {
+        self.blink_colon = !self.blink_colon;
+        let blink = self.blink_colon;
+        for clock in self.clocks_mut() {
+            clock.set_blink_colon(blink);
+        }
+        info!("Colon blinking: {}", blink);
+    }
+
+    /// Whether a surface-local `pos` on the clock window is over a colon
+    fn clock_colon_at(&self, pos: Vec2) -> bool {
+        let [width, height] = self.clock_size();
+        let viewport = Rect::new(0.0, 0.0, width as f32, height as f32);
+        self.feature::<Clock>().is_some_and(|clock| clock.colon_contains(viewport, pos))
+    }
+
+    fn apply_seconds_style(&mut self) {
+        let style = self.seconds_style();
+        for clock in self.clocks_mut().filter(|c| !c.is_world_clock()) {
+            clock.set_seconds_style(style);
+        }
+    }
+
+    pub fn cycle_color_mode(&mut self, forward: bool) {
+        self.color_mode = if forward { self.color_mode.next() } else { self.color_mode.prev() };
+        let (mode, now) = (self.color_mode, self.time);
+        for clock in self.clocks_mut() {
+            clock.set_color_mode(mode, now);
+        }
+        info!("Changed color mode to: {}", self.color_mode.name());
+        self.state_changed_at = Some(self.time);
+    }
+
+    /// Step the digit brightness up or down, keeping them visible
+    pub fn adjust_brightness(&mut self, delta: f32) {
+        self.brightness = (self.brightness + delta).clamp(MIN_BRIGHTNESS, 1.0);
+        let brightness = self.brightness;
+        for clock in self.clocks_mut() {
+            clock.set_brightness(brightness);
+        }
+        info!("Brightness now {:.1}", self.brightness);
+        self.state_changed_at = Some(self.time);
+    }
+
+    /// Write the color mode, seconds toggle, brightness, focus and face to the
+    /// state file if they changed since the last save
+    pub fn save_state(&mut self) {
+        if self.state_changed_at.take().is_none() {
+            return;
+        }
+        let state = State {
+            color_mode: Some(self.color_mode.name().to_string()),
+            show_seconds: Some(self.show_seconds),
+            brightness: Some(self.brightness),
+            // Left to the config unless the key overrode it
+            focus: (self.focus != self.config.pomodoro.hide_clock_while_counting).then_some(self.focus),
+            face: (self.face != self.config.clock.face).then_some(self.face),
+        };
+        if let Err(e) = state.save() {
+            warn!("Failed to save state: {}", e);
+        }
+    }
+
+    /// Switch focus mode, which hides the clock while a pomodoro runs.
+    /// Remembered in `State`, the config is left alone
+    pub fn toggle_focus(&mut self) {
+        self.focus = !self.focus;
+        info!("Focus mode: {}", self.focus);
+        self.state_changed_at = Some(self.time);
+    }
+
+    /// Whether focus mode has the clock hidden right now
+    pub fn clock_hidden(&self) -> bool {
+        self.focus
+            && matches!(self.pomodoro().mode, PomodoroMode::Counting { .. } | PomodoroMode::Paused { .. })
+    }
+
+    /// Switch between the digital readout and the analog dial.
+    /// Remembered in `State`, the config is left alone
+    pub fn toggle_face(&mut self) {
+        self.face = match self.face {
+            ClockFace::Digital => ClockFace::Analog,
+            ClockFace::Analog => ClockFace::Digital,
+        };
+        let face = self.face;
+        for clock in self.clocks_mut() {
+            clock.set_face(face);
+        }
+        info!("Switched clock face to {:?}", face);
+        self.state_changed_at = Some(self.time);
+    }
+
+    /// Adopt an edited config file. Theme colors, color mode, brightness,
+    /// effects, the pomodoro task, margins and fps_cap apply immediately;
+    /// everything else on the next start.
+    /// The running config then lags the file on those other keys, so it is
+    /// never written back whole; `Config::save_margins` patches the file
+    pub fn reload_config(&mut self, config: Config) {
+        let now = self.time;
+        for clock in self.clocks_mut() {
+            clock.set_theme(&config.theme);
+        }
+        if let Some(battery) = self.feature_mut::<Battery>() {
+            battery.set_theme(&config.theme);
+        }
+        if let Some(countdown) = self.feature_mut::<Countdown>() {
+            countdown.set_theme(&config.theme);
+        }
+
+        // Only an edit to the configured mode overrides the scrolled-to one
+        let color_mode = ColorMode::from_config(&config.clock.color_mode);
+        if config.clock.color_mode != self.config.clock.color_mode && color_mode != self.color_mode {
+            self.color_mode = color_mode;
+            for clock in self.clocks_mut() {
+                clock.set_color_mode(color_mode, now);
+            }
+            self.state_changed_at = Some(now);
+        }
+        if config.clock.brightness != self.config.clock.brightness {
+            self.brightness = config.clock.brightness;
+            for clock in self.clocks_mut() {
+                clock.set_brightness(config.clock.brightness);
             }
-            UiMode::Expanded => {
-                self.mode = UiMode::Collapsing;
-                self.expand_timeline.start(self.time);
+            self.state_changed_at = Some(now);
+        }
+
+        if config.effects != self.config.effects {
+            self.pomodoro_mut().set_effects(&config.effects);
+            if let Some(countdown) = self.feature_mut::<Countdown>() {
+                countdown.set_effects(&config.effects);
             }
-            _ => {}
+        }
+        if config.pomodoro.task != self.config.pomodoro.task {
+            self.pomodoro_mut().set_task(config.pomodoro.task.as_deref());
+        }
+
+        if config.margins != self.config.margins {
+            self.margins_changed = true;
+        }
+
+        // Only those keys reach the running config. Features copied the rest
+        // at startup, and sizes read from here have to keep agreeing with them
+        let live = &mut self.config;
+        live.theme = config.theme;
+        live.clock.color_mode = config.clock.color_mode;
+        live.clock.brightness = config.clock.brightness;
+        live.effects = config.effects;
+        live.pomodoro.task = config.pomodoro.task;
+        live.margins = config.margins;
+        live.fps_cap = config.fps_cap;
+        // A file that failed to load at startup can be saved to once it loads
+        live.path = config.path;
+        info!("Config reloaded");
+    }
+
+    fn begin_drag(&mut self, pos: Vec2) {
+        let anchor = self.config.position.anchor;
+        let margins = &self.config.margins;
+        let horizontal = if anchor.is_left() { margins.left } else { margins.right };
+        let vertical = if anchor.is_top() { margins.top } else { margins.bottom };
+        let margins = [horizontal as f32, vertical as f32];
+        self.drag = Some(ClockDrag {
+            press: pos,
+            placed: margins,
+            margins,
+            dragging: false,
+        });
+    }
+
+    fn update_drag(&mut self, pos: Vec2) {
+        let Some(mut drag) = self.drag else { return };
+        let (dx, dy) = (pos.x - drag.press.x, pos.y - drag.press.y);
+        if !drag.dragging && dx.hypot(dy) < DRAG_THRESHOLD {
+            return;
+        }
+        drag.dragging = true;
+
+        // Margins grow away from the anchored edges. Clamp so the whole clock
+        // stays on screen.
+        let anchor = self.config.position.anchor;
+        let size = self.get_current_size();
+        let screen = self.screen_size.unwrap_or([1920, 1080]);
+        let max = [
+            screen[0].saturating_sub(size[0]) as f32,
+            screen[1].saturating_sub(size[1]) as f32,
+        ];
+        drag.margins[0] = (drag.placed[0] + if anchor.is_left() { dx } else { -dx }).clamp(0.0, max[0]);
+        drag.margins[1] = (drag.placed[1] + if anchor.is_top() { dy } else { -dy }).clamp(0.0, max[1]);
+
+        let [horizontal, vertical] = drag.margins.map(|m| m.round() as u32);
+        let margins = &mut self.config.margins;
+        // Centered axes ignore their margins, so leave them be
+        if !anchor.centers_x() {
+            if anchor.is_left() { margins.left = horizontal } else { margins.right = horizontal }
+        }
+        if !anchor.centers_y() {
+            if anchor.is_top() { margins.top = vertical } else { margins.bottom = vertical }
+        }
+
+        self.drag = Some(drag);
+        self.margins_changed = true;
+    }
+
+    /// Whether the clock's margins need applying; call once they're sent to
+    /// the compositor, a drag measures later motion from there
+    pub fn take_margins_changed(&mut self) -> bool {
+        if let Some(drag) = &mut self.drag {
+            drag.placed = drag.margins;
+        }
+        std::mem::take(&mut self.margins_changed)
+    }
+
+    /// The configured seconds style, or `Hidden` while seconds are toggled
+    /// off; an expanded clock always shows the seconds digits
+    pub fn seconds_style(&self) -> SecondsStyle {
+        if matches!(self.mode, UiMode::Expanding | UiMode::Expanded) {
+            SecondsStyle::Digits
+        } else if self.show_seconds {
+            self.config.clock.seconds_style
+        } else {
+            SecondsStyle::Hidden
+        }
+    }
+
+    pub fn toggle_expand(&mut self) {
+        self.set_expanded(matches!(self.mode, UiMode::Collapsed | UiMode::Collapsing));
+    }
+
+    /// Start growing or shrinking the clock, turning around mid-way if needed
+    pub fn set_expanded(&mut self, expanded: bool) {
+        let mode = match (expanded, self.mode) {
+            (true, UiMode::Collapsed | UiMode::Collapsing) => UiMode::Expanding,
+            (false, UiMode::Expanded | UiMode::Expanding) => UiMode::Collapsing,
+            _ => return,
+        };
+        self.mode = mode;
+        self.expand_timeline.start(self.time);
+        self.apply_seconds_style();
+    }
+
+    /// 0 collapsed to 1 expanded; the spring may overshoot a little
+    fn expand_progress(&self) -> f32 {
+        match self.mode {
+            UiMode::Collapsed => 0.0,
+            UiMode::Expanded => 1.0,
+            UiMode::Expanding => self.expand_timeline.eased_progress(),
+            UiMode::Collapsing => 1.0 - self.expand_timeline.eased_progress(),
         }
     }
 
     pub fn handle_event(&mut self, event: UiEvent) {
+        self.redraw = true;
+        match event {
+            UiEvent::PointerEnter { pos } | UiEvent::PointerMove { pos } => self.pointer = Some(pos),
+            UiEvent::PointerLeave => self.pointer = None,
+            _ => {}
+        }
+        let event = self.retarget_inline_timer(event);
+
+        // Features get first pick
+        for feature in &mut self.features {
+            if feature.handle_event(event.clone()) {
+                return;
+            }
+        }
+
         match event {
             UiEvent::PointerEnter { .. } => {
                 self.hover = true;
+                if self.config.expand_on_hover {
+                    self.set_expanded(true);
+                }
             }
             UiEvent::PointerLeave => {
                 self.hover = false;
+                if self.config.expand_on_hover {
+                    self.set_expanded(false);
+                }
             }
-            UiEvent::PointerDown { button, .. } => {
+            UiEvent::PointerDown { pos, button, surface } => {
                 // Right click (BTN_RIGHT = 0x111) starts/stops Pomodoro timer
                 if button == 0x111 {
-                    info!("Right click detected! Button: {:#x}, Mode: {:?}", button, self.pomodoro.mode);
-                    if matches!(self.pomodoro.mode, PomodoroMode::Idle) {
-                        info!("Starting pomodoro from right click");
-                        self.start_pomodoro();
-                    } else if matches!(self.pomodoro.mode, PomodoroMode::Counting { .. }) {
-                        // If already running, stop the timer (go back to idle)
-                        info!("Stopping pomodoro from right click");
-                        self.pomodoro.stop();
-                    }
+                    info!("Right click detected! Button: {:#x}, Mode: {:?}", button, self.pomodoro().mode);
+                    self.toggle_pomodoro();
+                    return;
+                }
+
+                // Middle click (BTN_MIDDLE = 0x112) pauses/resumes a running timer
+                if button == 0x112 {
+                    let now = self.time;
+                    self.pomodoro_mut().toggle_pause(now);
                     return;
                 }
 
-                // Left click (BTN_LEFT = 0x110) toggles seconds display
+                // Left click (BTN_LEFT = 0x110) toggles seconds display, or
+                // drags the clock if the pointer moves before release
                 if button == 0x110 {
-                    self.show_seconds = !self.show_seconds;
-                    info!("Toggled seconds display: {}", self.show_seconds);
+                    if surface == Some(ActiveSurface::Clock) {
+                        self.begin_drag(pos);
+                    } else {
+                        self.toggle_seconds();
+                    }
                 }
             }
-            UiEvent::Scroll { delta, surface } => {
-                info!("Scroll event: delta={}, surface={:?}", delta, surface);
-                // Handle scroll based on which surface we're over
-                match surface {
-                    Some(ActiveSurface::Clock) => {
-                        // Cycle through color modes on clock surface
-                        const NUM_MODES: u8 = 11; // Total number of color modes
-                        if delta > 0.0 {
-                            self.color_mode = (self.color_mode + 1) % NUM_MODES;
-                        } else if delta < 0.0 {
-                            self.color_mode = if self.color_mode == 0 {
-                                NUM_MODES - 1
-                            } else {
-                                self.color_mode - 1
-                            };
+            UiEvent::TouchDown { pos, surface } => {
+                let mut hold = Timeline::with_easing(LONG_PRESS_SECONDS, Easing::Linear);
+                hold.start(self.time);
+                self.touch = Some(TouchPress { hold, long_pressed: false });
+                // Touches on the clock can drag it, like the left button
+                if surface == Some(ActiveSurface::Clock) {
+                    self.begin_drag(pos);
+                }
+            }
+            UiEvent::TouchCancel => {
+                self.touch = None;
+                self.drag = None;
+            }
+            UiEvent::PointerMove { pos } => {
+                self.update_drag(pos);
+            }
+            UiEvent::PointerUp => {
+                // A long-press already acted; the lift shouldn't also tap
+                let touch = self.touch.take();
+                let tap = !touch.as_ref().is_some_and(|t| t.long_pressed);
+                if let Some(drag) = self.drag.take() {
+                    if drag.dragging {
+                        info!("Clock moved, margins now {:?}", self.config.margins);
+                        if let Err(e) = self.config.save_margins() {
+                            log::warn!("Failed to save clock position: {}", e);
                         }
-                        info!("Changed color mode to: {}", self.color_mode);
+                    } else if tap && self.clock_colon_at(drag.press) {
+                        self.toggle_blink_colon();
+                    } else if tap {
+                        self.toggle_seconds();
                     }
-                    Some(ActiveSurface::Timer) => {
-                        // Cycle through timer durations on timer surface
-                        self.pomodoro.cycle_duration(delta);
-                    }
-                    _ => {} // Ignore scroll on other surfaces or no surface
+                } else if touch.is_some() && tap {
+                    self.toggle_seconds();
                 }
             }
-            UiEvent::Key(_key) => {
-                // No key handling needed anymore since we removed expand mode
+            UiEvent::Scroll { delta, surface, modifiers } => {
+                info!("Scroll event: delta={}, surface={:?}", delta, surface);
+                // Cycle through color modes on the clock surface, or dim it with
+                // shift held; other surfaces ignore scroll
+                if surface == Some(ActiveSurface::Clock) && delta != 0.0 {
+                    if modifiers.shift {
+                        self.adjust_brightness(delta.signum() * BRIGHTNESS_STEP);
+                    } else {
+                        self.cycle_color_mode(delta > 0.0);
+                    }
+                }
             }
-            _ => {}
+            UiEvent::Key { keysym, modifiers } => match self.keybindings.lookup(modifiers, keysym) {
+                Some(Action::ToggleSeconds) => self.toggle_seconds(),
+                Some(Action::StartPomodoro) => self.toggle_pomodoro(),
+                Some(Action::Pause) => {
+                    let now = self.time;
+                    self.pomodoro_mut().toggle_pause(now);
+                }
+                Some(Action::CycleColor) => self.cycle_color_mode(true),
+                Some(Action::ToggleFace) => self.toggle_face(),
+                Some(Action::ToggleStopwatch) => {
+                    let now = self.time;
+                    self.pomodoro_mut().toggle_stopwatch(now);
+                }
+                Some(Action::LongerDuration) => self.adjust_duration(1.0),
+                Some(Action::ShorterDuration) => self.adjust_duration(-1.0),
+                Some(Action::ToggleFocus) => self.toggle_focus(),
+                Some(Action::Quit) => {
+                    info!("Quit requested");
+                    self.quit_requested = true;
+                }
+                None => {}
+            },
         }
     }
 
+    /// Advance `time` to the monotonic clock and update. Summing frame times
+    /// instead would slowly drift from real time.
+    pub fn update_to_now(&mut self) {
+        let now = self.epoch.elapsed().as_secs_f32();
+        self.update(now - self.time);
+    }
+
+    /// Advance `time` by `dt` seconds; headless renders drive time this way
     pub fn update(&mut self, dt: f32) {
         self.time += dt;
 
+        for feature in &mut self.features {
+            feature.update(dt, self.time);
+        }
+
+        // Every clock's flag is taken so none carries over to the next frame
+        let ticked = self.clocks_mut().fold(false, |ticked, clock| clock.take_second_changed() | ticked);
+        self.redraw |= ticked;
+
+        // Debounced so scrolling through the color modes writes once
+        if self.state_changed_at.is_some_and(|at| self.time - at >= STATE_SAVE_DELAY) {
+            self.save_state();
+        }
+
+        // A quiet completion celebrates on the clock instead of fullscreen
+        let celebration = self.pomodoro().clock_pulse_progress()
+            .or_else(|| self.feature::<Countdown>().and_then(Countdown::clock_pulse_progress));
+        for clock in self.clocks_mut().filter(|c| !c.is_world_clock()) {
+            clock.set_celebration(celebration);
+        }
+
+        if self.feature_mut::<Alarm>().is_some_and(|alarm| alarm.take_fired()) {
+            let now = self.time;
+            for clock in self.clocks_mut().filter(|c| !c.is_world_clock()) {
+                clock.pulse(now);
+            }
+        }
+
+        // A touch held still long enough acts like a right click
+        let dragging = self.drag.is_some_and(|d| d.dragging);
+        let long_press = self.touch.as_mut().is_some_and(|touch| {
+            touch.hold.update(self.time);
+            let fire = !touch.long_pressed && !dragging && touch.hold.is_complete();
+            touch.long_pressed |= fire;
+            fire
+        });
+        if long_press {
+            info!("Long-press detected, Mode: {:?}", self.pomodoro().mode);
+            self.toggle_pomodoro();
+        }
+
         // Reset click count
         if self.time - self.last_click_time > 0.5 {
             self.click_count = 0;
@@ -181,18 +731,102 @@ impl App {
         }
     }
 
+    /// Whether the next frame needs drawing. With animations off and nothing
+    /// in motion that is only after input or when a clock's second rolls
+    /// over, so an idle clock redraws once a second.
+    pub fn take_redraw(&mut self) -> bool {
+        let busy = self.config.animations_enabled
+            || !matches!(self.pomodoro().mode, PomodoroMode::Idle)
+            || self.wants_window(WindowId::Timer)
+            || self.wants_window(WindowId::Plasma)
+            || self.drag.is_some()
+            || self.touch.is_some()
+            || matches!(self.mode, UiMode::Expanding | UiMode::Collapsing)
+            || self.clocks_mut().any(|clock| clock.is_animating());
+        std::mem::take(&mut self.redraw) || busy
+    }
+
+    /// With `pomodoro.inline`, input over the timer's strip of the clock
+    /// surface goes to the timer as if it had a window of its own
+    fn retarget_inline_timer(&self, event: UiEvent) -> UiEvent {
+        let Some(timer) = self.inline_timer_rect() else { return event };
+        let timer_surface = Some(ActiveSurface::Timer);
+        match event {
+            UiEvent::PointerDown { pos, button, surface: Some(ActiveSurface::Clock) } if timer.contains(pos) => {
+                UiEvent::PointerDown { pos, button, surface: timer_surface }
+            }
+            UiEvent::TouchDown { pos, surface: Some(ActiveSurface::Clock) } if timer.contains(pos) => {
+                UiEvent::TouchDown { pos, surface: timer_surface }
+            }
+            UiEvent::Scroll { delta, surface: Some(ActiveSurface::Clock), modifiers }
+                if self.pointer.is_some_and(|pos| timer.contains(pos)) =>
+            {
+                UiEvent::Scroll { delta, surface: timer_surface, modifiers }
+            }
+            event => event,
+        }
+    }
+
+    /// Size of the timer drawn into the clock surface, while there is one
+    fn inline_timer_size(&self) -> Option<[u32; 2]> {
+        if !self.config.pomodoro.inline {
+            return None;
+        }
+        self.window_size(WindowId::Timer)
+    }
+
+    /// Where the inline timer sits in the clock surface: along the bottom,
+    /// on the side the clock is anchored to
+    fn inline_timer_rect(&self) -> Option<Rect> {
+        let [width, height] = self.inline_timer_size()?;
+        let [surface_width, surface_height] = self.get_current_size();
+        let x = if self.config.position.anchor.is_left() { 0 } else { surface_width - width };
+        Some(Rect::new(x as f32, (surface_height - height) as f32, width as f32, height as f32))
+    }
+
+    /// The clock surface: the clock, plus an inline timer under it. Hidden
+    /// in focus mode, leaving just the inline timer or a single pixel.
     pub fn get_current_size(&self) -> [u32; 2] {
-        // Calculate width based on whether seconds are shown
-        // Keep height constant at 60
-        let width = if self.show_seconds {
-            220  // Width with seconds (6 digits + 2 colons)
-        } else {
-            150  // Width without seconds (4 digits + 1 colon)
-        };
-        [width, 60]
+        if self.clock_hidden() {
+            return self.inline_timer_size().unwrap_or([1, 1]);
+        }
+        let [width, height] = self.clock_size();
+        match self.inline_timer_size() {
+            Some([timer_width, timer_height]) => [width.max(timer_width), height + timer_height],
+            None => [width, height],
+        }
     }
 
-    pub fn render(&self, _draw: &mut DrawContext) {}
+    /// The clock's own size, whatever else shares or hides its surface
+    pub fn clock_size(&self) -> [u32; 2] {
+        // Height eases from collapsed_size.height toward expanded_size.height;
+        // the width fits the face, wider with seconds
+        let collapsed_height = self.config.collapsed_size.height;
+        let expanded_height = self.config.expanded_size.height.max(collapsed_height) as f32;
+        let height = lerp(collapsed_height as f32, expanded_height, self.expand_progress()).round().max(1.0) as u32;
+        let show_seconds = self.seconds_style() == SecondsStyle::Digits;
+        let spec = clock::LayoutSpec::new(&self.config.clock.layout, show_seconds);
+        [clock::clock_width(&self.config.clock.style, &spec, height), height]
+    }
+
+    /// Draw every feature that targets `window`
+    pub fn render(&self, window: WindowId, draw: &mut DrawContext, viewport: Rect) {
+        let inline_timer = if window == WindowId::Clock { self.inline_timer_rect() } else { None };
+        let features_viewport = match inline_timer {
+            Some(timer) => Rect::new(viewport.x, viewport.y, viewport.width, viewport.height - timer.height),
+            None => viewport,
+        };
+        let hidden = window == WindowId::Clock && self.clock_hidden();
+        for feature in self.features.iter().filter(|f| f.window() == Some(window) && !hidden) {
+            feature.render(draw, features_viewport);
+        }
+
+        if let Some(timer) = inline_timer {
+            draw.set_region(timer);
+            self.render(WindowId::Timer, draw, Rect::new(0.0, 0.0, timer.width, timer.height));
+            draw.set_region(viewport);
+        }
+    }
 
     fn render_clock(&self, draw: &mut DrawContext) {
         let text_color = Color::rgba(255, 255, 255, 255);
@@ -212,4 +846,70 @@ impl App {
             (self.logical_size[1] as f32 * scale) as u32,
         ];
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Anchor;
+
+    fn pointer_down(app: &mut App, x: f32, y: f32) {
+        let pos = Vec2::new(x, y);
+        app.handle_event(UiEvent::PointerDown { pos, button: 0x110, surface: Some(ActiveSurface::Clock) });
+    }
+
+    fn pointer_move(app: &mut App, x: f32, y: f32) {
+        app.handle_event(UiEvent::PointerMove { pos: Vec2::new(x, y) });
+    }
+
+    #[test]
+    fn queued_moves_drag_by_the_latest_offset_only() {
+        let mut config = Config::default();
+        config.position.anchor = Anchor::TopLeft;
+        config.margins.left = 100;
+        config.margins.top = 100;
+        let mut app = App::new(config, State::default(), Stats::default());
+        app.set_screen_size([1920, 1080]);
+
+        // Three motions reach us before the surface has moved at all
+        pointer_down(&mut app, 10.0, 10.0);
+        pointer_move(&mut app, 30.0, 10.0);
+        pointer_move(&mut app, 50.0, 20.0);
+        pointer_move(&mut app, 60.0, 25.0);
+        assert_eq!((app.config.margins.left, app.config.margins.top), (150, 115));
+
+        // Once applied, positions are relative to the moved surface
+        assert!(app.take_margins_changed());
+        pointer_move(&mut app, 15.0, 5.0);
+        assert_eq!((app.config.margins.left, app.config.margins.top), (155, 110));
+        pointer_move(&mut app, 12.0, 10.0);
+        assert_eq!((app.config.margins.left, app.config.margins.top), (152, 115));
+    }
+
+    #[test]
+    fn reload_leaves_next_start_keys_alone() {
+        let mut app = App::new(Config::default(), State::default(), Stats::default());
+        let size = app.get_current_size();
+
+        let mut edited = Config::default();
+        edited.position.anchor = Anchor::BottomLeft;
+        edited.expanded_size.width += 100;
+        edited.clock.face = ClockFace::Analog;
+        edited.pomodoro.inline = true;
+        edited.pomodoro.hide_clock_while_counting = true;
+        edited.inhibit_idle_during_pomodoro = false;
+        edited.fps_cap = 5;
+        app.reload_config(edited);
+
+        let defaults = Config::default();
+        assert_eq!(app.config.position.anchor, defaults.position.anchor);
+        assert_eq!(app.config.expanded_size.width, defaults.expanded_size.width);
+        assert_eq!(app.config.clock.face, defaults.clock.face);
+        assert!(!app.config.pomodoro.inline);
+        assert!(!app.config.pomodoro.hide_clock_while_counting);
+        assert!(!app.focus);
+        assert!(app.config.inhibit_idle_during_pomodoro);
+        assert_eq!(app.get_current_size(), size);
+        // A live key still goes through
+        assert_eq!(app.config.fps_cap, 5);
+    }
+}
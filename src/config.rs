@@ -1,8 +1,18 @@
-use anyhow::Result;
+use crate::error::{CornaError, Result};
+use crate::gfx::draw::MAX_EFFECT_MODE;
+use crate::gfx::math::Color;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, SystemTime};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
+    /// Connector name of the output to show on (e.g. "DP-1"); first output if unset
+    #[serde(default)]
+    pub output: Option<String>,
+
     #[serde(default = "default_position")]
     pub position: Position,
 
@@ -12,34 +22,104 @@ pub struct Config {
     #[serde(default = "default_size")]
     pub collapsed_size: Size,
 
+    /// With `expand_on_hover`, the clock grows to this height (its width
+    /// follows the digits) and shows seconds
     #[serde(default = "default_expanded_size")]
     pub expanded_size: Size,
 
+    /// Grow the clock while the pointer is over it
+    #[serde(default)]
+    pub expand_on_hover: bool,
+
     #[serde(default = "default_theme")]
     pub theme: Theme,
 
+    /// Frame rate limit when vsync is off
     #[serde(default = "default_fps_cap")]
     pub fps_cap: u32,
 
+    /// Let buffer swaps wait for the compositor instead of sleeping to fps_cap
+    #[serde(default = "default_vsync")]
+    pub vsync: bool,
+
     #[serde(default)]
     pub animations_enabled: bool,
+
+    /// Feather the stepped edges of seven-segment digits (a few more draw ops)
+    #[serde(default)]
+    pub antialias: bool,
+
+    #[serde(default)]
+    pub clock: ClockConfig,
+
+    #[serde(default)]
+    pub pomodoro: PomodoroConfig,
+
+    #[serde(default)]
+    pub world_clock: WorldClockConfig,
+
+    #[serde(default)]
+    pub battery: BatteryConfig,
+
+    #[serde(default)]
+    pub sysmon: SysMonConfig,
+
+    #[serde(default)]
+    pub alarm: AlarmConfig,
+
+    #[serde(default)]
+    pub countdown: CountdownConfig,
+
+    #[serde(default)]
+    pub scroll: ScrollConfig,
+
+    #[serde(default)]
+    pub effects: EffectsConfig,
+
+    /// Action name ("toggle_seconds", "start_pomodoro", "pause", "cycle_color",
+    /// "quit") to key combo such as "space" or "ctrl+p"
+    #[serde(default = "crate::keybindings::default_keybindings")]
+    pub keybindings: BTreeMap<String, String>,
+
+    /// Keep the screen from dimming or blanking while a pomodoro counts down
+    #[serde(default = "default_inhibit_idle")]
+    pub inhibit_idle_during_pomodoro: bool,
+
+    /// Open the clock as a regular window when the compositor lacks layer-shell
+    #[serde(default)]
+    pub allow_xdg_fallback: bool,
+
+    /// File this config was loaded from, or will be created at; None for the
+    /// fallback default used when the file failed to load, which is never
+    /// written back
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Position {
     pub anchor: Anchor,
+    /// Passed to the clock's layer surface: 0 floats over other windows,
+    /// a positive value reserves that many pixels, -1 also ignores other
+    /// surfaces' reserved space. Strict layer-shell compositors treat a
+    /// positive zone on a corner-anchored surface as 0.
     pub exclusive_zone: i32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum Anchor {
     TopLeft,
     TopRight,
     BottomLeft,
     BottomRight,
+    /// Centered horizontally along the top edge
+    TopCenter,
+    /// Centered horizontally along the bottom edge
+    BottomCenter,
+    Center,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Margins {
     pub top: u32,
     pub right: u32,
@@ -47,6 +127,41 @@ pub struct Margins {
     pub left: u32,
 }
 
+impl Anchor {
+    pub fn is_top(self) -> bool {
+        matches!(self, Anchor::TopLeft | Anchor::TopRight | Anchor::TopCenter)
+    }
+
+    pub fn is_left(self) -> bool {
+        matches!(self, Anchor::TopLeft | Anchor::BottomLeft)
+    }
+
+    /// Not pinned to the left or right edge
+    pub fn centers_x(self) -> bool {
+        matches!(self, Anchor::TopCenter | Anchor::BottomCenter | Anchor::Center)
+    }
+
+    /// Not pinned to the top or bottom edge
+    pub fn centers_y(self) -> bool {
+        self == Anchor::Center
+    }
+}
+
+impl Margins {
+    /// Layer-shell margins (top, right, bottom, left) for a surface anchored at
+    /// `anchor`; margins on edges the surface isn't anchored to are zeroed
+    pub fn for_anchor(&self, anchor: Anchor) -> [i32; 4] {
+        let (top, left) = (anchor.is_top(), anchor.is_left());
+        let (x, y) = (!anchor.centers_x(), !anchor.centers_y());
+        [
+            if y && top { self.top as i32 } else { 0 },
+            if x && !left { self.right as i32 } else { 0 },
+            if y && !top { self.bottom as i32 } else { 0 },
+            if x && left { self.left as i32 } else { 0 },
+        ]
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Size {
     pub width: u32,
@@ -58,18 +173,635 @@ pub struct Theme {
     pub background: String,
     pub foreground: String,
     pub accent: String,
+    /// Fills the whole surface behind the face, e.g. "#00000080" for a
+    /// translucent bar; transparent by default
+    #[serde(default = "default_surface")]
+    pub surface: String,
+}
+
+impl Theme {
+    /// `surface` as a color, transparent if it doesn't parse
+    pub fn surface_color(&self) -> Color {
+        Color::from_hex(&self.surface).unwrap_or(Color::rgba(0, 0, 0, 0))
+    }
+
+    /// `foreground` as a color, white if it doesn't parse
+    pub fn foreground_color(&self) -> Color {
+        Color::from_hex(&self.foreground).unwrap_or(Color::rgba(255, 255, 255, 255))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClockConfig {
+    /// Digit color mode by name ("classic_red", "rainbow_wave", "matrix", "fire", ...);
+    /// updated when scrolling on the clock
+    #[serde(default = "default_color_mode")]
+    pub color_mode: String,
+
+    /// How seconds are shown when enabled (left click toggles them)
+    #[serde(default = "default_seconds_style")]
+    pub seconds_style: SecondsStyle,
+
+    #[serde(default = "default_time_format")]
+    pub time_format: TimeFormat,
+
+    /// Seven-segment digits or an analog dial; toggled with a keybinding
+    #[serde(default = "default_clock_face")]
+    pub face: ClockFace,
+
+    #[serde(default)]
+    pub style: ClockStyle,
+
+    /// Shift the face a pixel every minute and draw outlined digits on a
+    /// transparent face, to spare OLED panels
+    #[serde(default)]
+    pub oled_safe: bool,
+
+    /// Brightness boost (0-1) flashed on a digit when it changes; 0, the
+    /// default, disables it
+    #[serde(default)]
+    pub tick_pulse: f32,
+
+    /// Briefly brighten the whole readout every second
+    #[serde(default)]
+    pub pulse_on_tick: bool,
+
+    /// A small dot in the face's top-right corner that beats once a second
+    #[serde(default)]
+    pub heartbeat: bool,
+
+    /// Blink the colons: lit for the first half of each second
+    #[serde(default)]
+    pub blink_colon: bool,
+
+    /// Draw the colons as round dots instead of squares
+    #[serde(default)]
+    pub round_dots: bool,
+
+    /// Rounding of the face's corners in logical pixels; 0 keeps them sharp
+    #[serde(default)]
+    pub corner_radius: f32,
+
+    /// Opacity (0-1) of the face behind the digits or dial
+    #[serde(default = "default_face_alpha")]
+    pub face_alpha: f32,
+
+    /// Draw the face at all; off leaves the digits floating on the desktop
+    #[serde(default = "default_show_face")]
+    pub show_face: bool,
+
+    /// Shade each digit from bright at the top to darker at the bottom
+    #[serde(default)]
+    pub gradient_segments: bool,
+
+    /// Digit brightness (0-1); shift+scroll on the clock adjusts it
+    #[serde(default = "default_brightness")]
+    pub brightness: f32,
+
+    /// Dim the digits automatically overnight
+    #[serde(default)]
+    pub night_dim: NightDimConfig,
+
+    /// Fields of the digital readout, left to right; seconds only show while
+    /// `seconds_style` is Digits
+    #[serde(default = "default_layout")]
+    pub layout: Vec<LayoutField>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NightDimConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Local "HH:MM" the night starts; it may end the next morning
+    #[serde(default = "default_night_start")]
+    pub start: String,
+
+    #[serde(default = "default_night_end")]
+    pub end: String,
+
+    /// Brightness multiplier (0-1) at night, faded in and out over a few minutes
+    #[serde(default = "default_dim_factor")]
+    pub dim_factor: f32,
+}
+
+impl Default for NightDimConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            start: default_night_start(),
+            end: default_night_end(),
+            dim_factor: default_dim_factor(),
+        }
+    }
+}
+
+fn default_night_start() -> String {
+    "22:00".to_string()
+}
+
+fn default_night_end() -> String {
+    "07:00".to_string()
+}
+
+fn default_dim_factor() -> f32 {
+    0.4
+}
+
+/// Proportions of the seven-segment readout; the clock window is sized to fit
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ClockStyle {
+    /// Digit width as a fraction of digit height
+    #[serde(default = "default_digit_aspect")]
+    pub digit_aspect: f32,
+
+    /// Colon width as a fraction of digit width
+    #[serde(default = "default_colon_ratio")]
+    pub colon_ratio: f32,
+
+    /// Bezel around the digits as a multiple of `spacing`
+    #[serde(default = "default_margin_ratio")]
+    pub margin_ratio: f32,
+
+    /// Gap between glyphs in logical pixels
+    #[serde(default = "default_spacing")]
+    pub spacing: f32,
+}
+
+impl Default for ClockStyle {
+    fn default() -> Self {
+        Self {
+            digit_aspect: default_digit_aspect(),
+            colon_ratio: default_colon_ratio(),
+            margin_ratio: default_margin_ratio(),
+            spacing: default_spacing(),
+        }
+    }
+}
+
+impl ClockStyle {
+    /// Clamp each value into a range that keeps the layout readable
+    fn sanitize(&mut self) {
+        let defaults = ClockStyle::default();
+        for (field, value, default, range) in [
+            ("digit_aspect", &mut self.digit_aspect, defaults.digit_aspect, 0.2..=1.5),
+            ("colon_ratio", &mut self.colon_ratio, defaults.colon_ratio, 0.1..=1.0),
+            ("margin_ratio", &mut self.margin_ratio, defaults.margin_ratio, 0.0..=4.0),
+            ("spacing", &mut self.spacing, defaults.spacing, 1.0..=30.0),
+        ] {
+            if !value.is_finite() {
+                log::warn!("clock.style.{} is not a number, using {}", field, default);
+                *value = default;
+            } else if !range.contains(value) {
+                let clamped = value.clamp(*range.start(), *range.end());
+                log::warn!("clock.style.{} = {} is outside {:?}, using {}", field, value, range, clamped);
+                *value = clamped;
+            }
+        }
+    }
+}
+
+fn default_digit_aspect() -> f32 {
+    0.62
+}
+
+fn default_colon_ratio() -> f32 {
+    0.28
+}
+
+fn default_margin_ratio() -> f32 {
+    1.5
+}
+
+fn default_spacing() -> f32 {
+    6.0
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SecondsStyle {
+    /// `:SS` digits after the minutes
+    Digits,
+    /// A thin bar under HH:MM that fills over each minute
+    Bar,
+    Hidden,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ClockFace {
+    Digital,
+    Analog,
+}
+
+/// One field of the digital readout. Time fields are separated by colons,
+/// Month and Day by a slash, anything else by a blank.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LayoutField {
+    Hours,
+    Minutes,
+    Seconds,
+    /// A single digit, 1 for Monday through 7 for Sunday
+    DayOfWeek,
+    Month,
+    Day,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TimeFormat {
+    /// 12 or 24 hours following LC_ALL/LC_TIME/LANG, 24 if they don't say
+    Auto,
+    TwelveHour,
+    TwentyFourHour,
+}
+
+/// Territories whose locales write the time with AM/PM
+const TWELVE_HOUR_TERRITORIES: &[&str] = &["US", "AU", "NZ", "PH", "IN", "PK", "BD", "EG", "SA"];
+
+impl TimeFormat {
+    /// Resolve `Auto` against the environment; cheap but meant to be cached
+    pub fn is_twelve_hour(self) -> bool {
+        match self {
+            TimeFormat::TwelveHour => true,
+            TimeFormat::TwentyFourHour => false,
+            TimeFormat::Auto => {
+                // Same precedence as setlocale: the first non-empty one wins
+                let locale = ["LC_ALL", "LC_TIME", "LANG"].iter()
+                    .filter_map(|var| std::env::var(var).ok())
+                    .find(|value| !value.is_empty());
+                let twelve_hour = locale.as_deref().is_some_and(locale_is_twelve_hour);
+                log::info!("Time format from locale {:?}: {}", locale, if twelve_hour { "12h" } else { "24h" });
+                twelve_hour
+            }
+        }
+    }
+}
+
+/// `language_TERRITORY.codeset@modifier`, e.g. "en_US.UTF-8"
+fn locale_is_twelve_hour(locale: &str) -> bool {
+    let name = locale.split(['.', '@']).next().unwrap_or_default();
+    let Some((language, territory)) = name.split_once('_') else { return false };
+    // French Canada uses 24 hours, English Canada AM/PM
+    TWELVE_HOUR_TERRITORIES.contains(&territory) || (language == "en" && territory == "CA")
+}
+
+impl Default for ClockConfig {
+    fn default() -> Self {
+        Self {
+            color_mode: default_color_mode(),
+            seconds_style: default_seconds_style(),
+            time_format: default_time_format(),
+            face: default_clock_face(),
+            style: ClockStyle::default(),
+            oled_safe: false,
+            tick_pulse: 0.0,
+            pulse_on_tick: false,
+            heartbeat: false,
+            blink_colon: false,
+            round_dots: false,
+            corner_radius: 0.0,
+            face_alpha: default_face_alpha(),
+            show_face: default_show_face(),
+            gradient_segments: false,
+            brightness: default_brightness(),
+            night_dim: NightDimConfig::default(),
+            layout: default_layout(),
+        }
+    }
+}
+
+fn default_layout() -> Vec<LayoutField> {
+    vec![LayoutField::Hours, LayoutField::Minutes, LayoutField::Seconds]
+}
+
+fn default_face_alpha() -> f32 {
+    1.0
+}
+
+fn default_show_face() -> bool {
+    true
+}
+
+fn default_brightness() -> f32 {
+    1.0
+}
+
+fn default_clock_face() -> ClockFace {
+    ClockFace::Digital
+}
+
+fn default_time_format() -> TimeFormat {
+    TimeFormat::TwelveHour
+}
+
+fn default_seconds_style() -> SecondsStyle {
+    SecondsStyle::Digits
+}
+
+fn default_color_mode() -> String {
+    "classic_red".to_string()
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorldClockConfig {
+    /// IANA timezone (e.g. "America/New_York") for a second, dimmer clock
+    /// under the main one; no world clock when unset
+    #[serde(default)]
+    pub timezone: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatteryConfig {
+    /// Show a battery percentage next to the clock
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Power supply under /sys/class/power_supply to read
+    #[serde(default = "default_battery_supply")]
+    pub supply: String,
+}
+
+impl Default for BatteryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            supply: default_battery_supply(),
+        }
+    }
+}
+
+fn default_battery_supply() -> String {
+    "BAT0".to_string()
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SysMonConfig {
+    /// Show CPU/RAM usage next to the clock
+    #[serde(default)]
+    pub enabled: bool,
+
+    #[serde(default)]
+    pub metric: SysMonMetric,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SysMonMetric {
+    Cpu,
+    Mem,
+    #[default]
+    Both,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CountdownConfig {
+    /// Wall-clock deadline to count down to, RFC 3339 ("2024-12-31T23:59:59Z")
+    /// or without an offset for local time; no countdown when unset
+    #[serde(default)]
+    pub target: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlarmConfig {
+    /// Local times ("HH:MM") to flash the clock and play `sound`
+    #[serde(default)]
+    pub times: Vec<String>,
+
+    /// Also fire at the top of every hour
+    #[serde(default)]
+    pub chime_hourly: bool,
+
+    /// Sound file handed to `paplay`
+    #[serde(default = "default_alarm_sound")]
+    pub sound: String,
+}
+
+impl Default for AlarmConfig {
+    fn default() -> Self {
+        Self {
+            times: Vec::new(),
+            chime_hourly: false,
+            sound: default_alarm_sound(),
+        }
+    }
+}
+
+fn default_alarm_sound() -> String {
+    "/usr/share/sounds/freedesktop/stereo/bell.oga".to_string()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScrollConfig {
+    /// Continuous scroll distance (touchpads) that counts as one step; wheel
+    /// notches always step once each
+    #[serde(default = "default_scroll_step")]
+    pub step: f32,
+}
+
+impl Default for ScrollConfig {
+    fn default() -> Self {
+        Self {
+            step: default_scroll_step(),
+        }
+    }
+}
+
+fn default_scroll_step() -> f32 {
+    10.0
+}
+
+/// Shader effects behind the pomodoro, by `uEffectMode`: 0 is flat color,
+/// 1 wave interference, 2 plasma
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EffectsConfig {
+    /// Background of the timer while it reveals itself
+    #[serde(default = "default_reveal_effect")]
+    pub reveal_mode: i32,
+
+    /// Fullscreen celebration when an interval finishes
+    #[serde(default = "default_completion_effect")]
+    pub completion_mode: i32,
+
+    /// Animation speed multiplier; 0 freezes the effects
+    #[serde(default = "default_effect_speed")]
+    pub speed: f32,
+}
+
+impl Default for EffectsConfig {
+    fn default() -> Self {
+        Self {
+            reveal_mode: default_reveal_effect(),
+            completion_mode: default_completion_effect(),
+            speed: default_effect_speed(),
+        }
+    }
+}
+
+fn default_reveal_effect() -> i32 {
+    1
+}
+
+fn default_completion_effect() -> i32 {
+    2
+}
+
+fn default_effect_speed() -> f32 {
+    1.0
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PomodoroConfig {
+    /// Work durations in minutes, longest first; scrolling up on the timer
+    /// moves toward the front of the list
+    #[serde(default = "default_pomodoro_durations")]
+    pub durations: Vec<u32>,
+
+    /// Duration selected at startup, in minutes; the closest entry of
+    /// `durations` when it isn't listed, the first one when unset
+    #[serde(default)]
+    pub default_minutes: Option<u32>,
+
+    #[serde(default = "default_short_break_minutes")]
+    pub short_break_minutes: u32,
+
+    #[serde(default = "default_long_break_minutes")]
+    pub long_break_minutes: u32,
+
+    /// Every Nth completed work interval is followed by a long break
+    #[serde(default = "default_long_break_interval")]
+    pub long_break_interval: u32,
+
+    /// Length of the completion effect; 0 disables it
+    #[serde(default = "default_celebration_seconds")]
+    pub celebration_seconds: f32,
+
+    /// How a finished interval is celebrated
+    #[serde(default = "default_completion_style")]
+    pub completion_style: CompletionStyle,
+
+    /// Part of the clock's output the Fullscreen style's plasma covers
+    #[serde(default = "default_plasma_region")]
+    pub plasma_region: PlasmaRegion,
+
+    /// Height of a TopBand or BottomBand plasma region in logical pixels
+    #[serde(default = "default_plasma_band_height")]
+    pub plasma_band_height: u32,
+
+    /// Hex tint of the plasma celebration after a work interval
+    #[serde(default = "default_work_completion_color")]
+    pub work_completion_color: String,
+
+    /// Hex tint of the plasma celebration after a break
+    #[serde(default = "default_break_completion_color")]
+    pub break_completion_color: String,
+
+    /// Where the timer window sits relative to the clock
+    #[serde(default = "default_timer_side")]
+    pub timer_side: TimerSide,
+
+    /// Logical pixels between the clock and the timer window; the battery,
+    /// system monitor and countdown windows beside the clock keep it too
+    #[serde(default = "default_timer_gap")]
+    pub timer_gap: u32,
+
+    /// Draw the timer inside the clock's surface, under the time, instead
+    /// of opening a window of its own
+    #[serde(default)]
+    pub inline: bool,
+
+    /// Focus mode: hide the clock while a pomodoro counts down (or is
+    /// paused), leaving only the timer; the focus keybinding toggles it
+    #[serde(default)]
+    pub hide_clock_while_counting: bool,
+
+    /// What the focus session is for, shown in small text under the timer
+    #[serde(default)]
+    pub task: Option<String>,
+
+    /// Rounding of the timer face's corners in logical pixels; 0 keeps them sharp
+    #[serde(default)]
+    pub corner_radius: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TimerSide {
+    /// On the side facing away from the anchored screen edge
+    Auto,
+    Left,
+    Right,
+    /// Stacked under the clock (and world clock), or above them when
+    /// anchored at the bottom
+    Below,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum CompletionStyle {
+    /// Plasma overlay covering the whole output
+    Fullscreen,
+    /// Color cycle on the clock digits
+    ClockPulse,
+    None,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum PlasmaRegion {
+    /// The whole output, over panels and every other window
+    Fullscreen,
+    /// A band across the top of the output, below any panel there
+    TopBand,
+    /// A band across the bottom of the output, above any panel there
+    BottomBand,
+}
+
+impl Default for PomodoroConfig {
+    fn default() -> Self {
+        Self {
+            durations: default_pomodoro_durations(),
+            default_minutes: None,
+            short_break_minutes: default_short_break_minutes(),
+            long_break_minutes: default_long_break_minutes(),
+            long_break_interval: default_long_break_interval(),
+            celebration_seconds: default_celebration_seconds(),
+            completion_style: default_completion_style(),
+            plasma_region: default_plasma_region(),
+            plasma_band_height: default_plasma_band_height(),
+            work_completion_color: default_work_completion_color(),
+            break_completion_color: default_break_completion_color(),
+            timer_side: default_timer_side(),
+            timer_gap: default_timer_gap(),
+            inline: false,
+            hide_clock_while_counting: false,
+            task: None,
+            corner_radius: 0.0,
+        }
+    }
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
+            output: None,
             position: default_position(),
             margins: default_margins(),
             collapsed_size: default_size(),
             expanded_size: default_expanded_size(),
+            expand_on_hover: false,
             theme: default_theme(),
             fps_cap: default_fps_cap(),
+            vsync: default_vsync(),
             animations_enabled: true,
+            antialias: false,
+            clock: ClockConfig::default(),
+            pomodoro: PomodoroConfig::default(),
+            world_clock: WorldClockConfig::default(),
+            battery: BatteryConfig::default(),
+            sysmon: SysMonConfig::default(),
+            alarm: AlarmConfig::default(),
+            countdown: CountdownConfig::default(),
+            scroll: ScrollConfig::default(),
+            effects: EffectsConfig::default(),
+            keybindings: crate::keybindings::default_keybindings(),
+            inhibit_idle_during_pomodoro: default_inhibit_idle(),
+            allow_xdg_fallback: false,
+            path: None,
         }
     }
 }
@@ -106,41 +838,323 @@ fn default_expanded_size() -> Size {
 
 fn default_theme() -> Theme {
     Theme {
-        background: "#1a1a1a".to_string(),
+        background: "#000000".to_string(),
         foreground: "#ffffff".to_string(),
-        accent: "#4a9eff".to_string(),
+        accent: "#ff4040".to_string(),
+        surface: default_surface(),
     }
 }
 
+fn default_surface() -> String {
+    "#00000000".to_string()
+}
+
+fn default_inhibit_idle() -> bool {
+    true
+}
+
+fn default_vsync() -> bool {
+    true
+}
+
+/// Highest `fps_cap` that makes sense; anything above is paced at this
+pub const MAX_FPS_CAP: u32 = 1000;
+
 fn default_fps_cap() -> u32 {
     60
 }
 
+fn default_pomodoro_durations() -> Vec<u32> {
+    vec![30, 25, 20, 15, 10, 5]
+}
+
+fn default_short_break_minutes() -> u32 {
+    5
+}
+
+fn default_long_break_minutes() -> u32 {
+    15
+}
+
+fn default_long_break_interval() -> u32 {
+    4
+}
+
+fn default_celebration_seconds() -> f32 {
+    5.0
+}
+
+fn default_completion_style() -> CompletionStyle {
+    CompletionStyle::Fullscreen
+}
+
+fn default_plasma_region() -> PlasmaRegion {
+    PlasmaRegion::Fullscreen
+}
+
+fn default_plasma_band_height() -> u32 {
+    120
+}
+
+fn default_work_completion_color() -> String {
+    "#80ff99".to_string()
+}
+
+fn default_break_completion_color() -> String {
+    "#80b3ff".to_string()
+}
+
+fn default_timer_side() -> TimerSide {
+    TimerSide::Auto
+}
+
+fn default_timer_gap() -> u32 {
+    10
+}
+
 impl Config {
     pub fn load() -> Result<Self> {
-        let config_dir = dirs::config_dir()
-            .ok_or_else(|| anyhow::anyhow!("Could not find config directory"))?;
-        let config_path = config_dir.join("corna").join("config.toml");
+        let config_path = default_config_path()?;
 
         if config_path.exists() {
-            let contents = std::fs::read_to_string(&config_path)?;
-            let config: Config = toml::from_str(&contents)?;
-            Ok(config)
+            Self::load_from(&config_path)
         } else {
-            Ok(Config::default())
+            Ok(Config { path: Some(config_path), ..Config::default() })
         }
     }
 
-    pub fn save(&self) -> Result<()> {
-        let config_dir = dirs::config_dir()
-            .ok_or_else(|| anyhow::anyhow!("Could not find config directory"))?;
-        let config_dir = config_dir.join("corna");
-        std::fs::create_dir_all(&config_dir)?;
+    /// Unlike `load`, a missing file is an error
+    pub fn load_from(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|source| CornaError::Io { path: path.to_path_buf(), source })?;
+        let mut config: Config = toml::from_str(&contents)
+            .map_err(|e| CornaError::Config(format!("parsing {}: {}", path.display(), e)))?;
+        config.sanitize();
+        config.validate()?;
+        config.path = Some(path.to_path_buf());
+        Ok(config)
+    }
 
-        let config_path = config_dir.join("config.toml");
-        let contents = toml::to_string_pretty(self)?;
-        std::fs::write(&config_path, contents)?;
+    /// Check values serde accepts but corna can't use. Problems corna can work
+    /// around are logged as warnings; the rest fail with the offending field.
+    pub fn validate(&self) -> Result<()> {
+        for (field, size) in [("collapsed_size", &self.collapsed_size), ("expanded_size", &self.expanded_size)] {
+            if size.width == 0 || size.height == 0 {
+                return Err(CornaError::Config(format!(
+                    "{} is {}x{}, both sides need to be at least 1",
+                    field, size.width, size.height
+                )));
+            }
+        }
 
+        let colors = [
+            ("theme.background", &self.theme.background),
+            ("theme.foreground", &self.theme.foreground),
+            ("theme.accent", &self.theme.accent),
+            ("theme.surface", &self.theme.surface),
+            ("pomodoro.work_completion_color", &self.pomodoro.work_completion_color),
+            ("pomodoro.break_completion_color", &self.pomodoro.break_completion_color),
+        ];
+        for (field, hex) in colors {
+            if Color::from_hex(hex).is_err() {
+                log::warn!("{} = \"{}\" is not a #rrggbb or #rrggbbaa color, using the default", field, hex);
+            }
+        }
+
+        if !(1..=MAX_FPS_CAP).contains(&self.fps_cap) {
+            log::warn!("fps_cap = {} is outside 1-{}, frames are paced at the nearest of those", self.fps_cap, MAX_FPS_CAP);
+        }
         Ok(())
     }
-}
\ No newline at end of file
+
+    /// Warn when the margins push the collapsed clock off a `screen` of the
+    /// given logical size
+    pub fn check_margins(&self, screen: [u32; 2]) {
+        let anchor = self.position.anchor;
+        let [top, right, bottom, left] = self.margins.for_anchor(anchor).map(|m| m.max(0) as u32);
+        let size = &self.collapsed_size;
+        if left + right + size.width > screen[0] {
+            log::warn!(
+                "margins.{} = {} puts the clock past the {}px wide screen",
+                if anchor.is_left() { "left" } else { "right" }, left + right, screen[0]
+            );
+        }
+        if top + bottom + size.height > screen[1] {
+            log::warn!(
+                "margins.{} = {} puts the clock past the {}px tall screen",
+                if anchor.is_top() { "top" } else { "bottom" }, top + bottom, screen[1]
+            );
+        }
+    }
+
+    /// Replace values that would break the widget with their defaults
+    fn sanitize(&mut self) {
+        let durations = &mut self.pomodoro.durations;
+        durations.retain(|&minutes| minutes > 0);
+        if durations.is_empty() {
+            log::warn!("pomodoro.durations has no valid entries, using defaults");
+            *durations = default_pomodoro_durations();
+        }
+        self.clock.style.sanitize();
+        if !(0.0..=1.0).contains(&self.clock.tick_pulse) {
+            log::warn!("clock.tick_pulse = {} is outside 0-1, clamping", self.clock.tick_pulse);
+            self.clock.tick_pulse = if self.clock.tick_pulse.is_nan() { 0.0 } else { self.clock.tick_pulse.clamp(0.0, 1.0) };
+        }
+        if !(0.0..=1.0).contains(&self.clock.brightness) {
+            log::warn!("clock.brightness = {} is outside 0-1, clamping", self.clock.brightness);
+            self.clock.brightness = if self.clock.brightness.is_nan() { 1.0 } else { self.clock.brightness.clamp(0.0, 1.0) };
+        }
+        let dim = &mut self.clock.night_dim;
+        if !(0.0..=1.0).contains(&dim.dim_factor) {
+            log::warn!("clock.night_dim.dim_factor = {} is outside 0-1, clamping", dim.dim_factor);
+            dim.dim_factor = if dim.dim_factor.is_nan() { default_dim_factor() } else { dim.dim_factor.clamp(0.0, 1.0) };
+        }
+        if !(0.0..=1.0).contains(&self.clock.face_alpha) {
+            log::warn!("clock.face_alpha = {} is outside 0-1, clamping", self.clock.face_alpha);
+            self.clock.face_alpha = if self.clock.face_alpha.is_nan() { 1.0 } else { self.clock.face_alpha.clamp(0.0, 1.0) };
+        }
+        if self.clock.layout.is_empty() {
+            log::warn!("clock.layout has no fields, using the default");
+            self.clock.layout = default_layout();
+        }
+        for (field, radius) in [
+            ("clock.corner_radius", &mut self.clock.corner_radius),
+            ("pomodoro.corner_radius", &mut self.pomodoro.corner_radius),
+        ] {
+            if radius.is_nan() || *radius < 0.0 {
+                log::warn!("{} = {} is negative, using 0", field, radius);
+                *radius = 0.0;
+            }
+        }
+        let effects = &mut self.effects;
+        for (field, mode, default) in [
+            ("reveal_mode", &mut effects.reveal_mode, default_reveal_effect()),
+            ("completion_mode", &mut effects.completion_mode, default_completion_effect()),
+        ] {
+            if !(0..=MAX_EFFECT_MODE).contains(mode) {
+                log::warn!("effects.{} = {} is not a shader effect (0-{}), using {}", field, mode, MAX_EFFECT_MODE, default);
+                *mode = default;
+            }
+        }
+        if !(effects.speed >= 0.0 && effects.speed.is_finite()) {
+            log::warn!("effects.speed = {} must be 0 or more, using {}", effects.speed, default_effect_speed());
+            effects.speed = default_effect_speed();
+        }
+    }
+
+    /// The `--config` path if one was given, else the default location
+    pub fn file_path(&self) -> Result<PathBuf> {
+        match &self.path {
+            Some(path) => Ok(path.clone()),
+            None => default_config_path(),
+        }
+    }
+
+    /// Write `margins` into the config file as it is on disk now, leaving
+    /// every other key and comment alone. The running config can be behind
+    /// the file (next-start keys), so it's never written out whole
+    pub fn save_margins(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Err(CornaError::Config("not writing over a config file that failed to load".to_string()));
+        };
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(source) => return Err(CornaError::Io { path: path.clone(), source }),
+        };
+        let mut doc: toml_edit::DocumentMut = contents.parse()
+            .map_err(|e| CornaError::Config(format!("parsing {}: {}", path.display(), e)))?;
+
+        let margins = &self.margins;
+        for (key, value) in [("top", margins.top), ("right", margins.right), ("bottom", margins.bottom), ("left", margins.left)] {
+            let item = &mut doc["margins"][key];
+            // Keep a trailing comment on the old value
+            let decor = item.as_value().map(|old| old.decor().clone());
+            *item = toml_edit::value(value as i64);
+            if let (Some(decor), Some(new)) = (decor, item.as_value_mut()) {
+                *new.decor_mut() = decor;
+            }
+        }
+
+        if let Some(config_dir) = path.parent() {
+            std::fs::create_dir_all(config_dir)
+                .map_err(|source| CornaError::Io { path: config_dir.to_path_buf(), source })?;
+        }
+        std::fs::write(path, doc.to_string())
+            .map_err(|source| CornaError::Io { path: path.clone(), source })
+    }
+}
+
+/// How often the watcher checks the config file for changes
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Sends whenever `path` changes, appears or disappears. Polls the modification
+/// time on a background thread, which also catches editors that save by
+/// replacing the file; the thread exits once the receiver is dropped.
+pub fn watch(path: PathBuf) -> Receiver<()> {
+    let (sender, receiver) = mpsc::channel();
+    let modified = |path: &Path| -> Option<SystemTime> {
+        std::fs::metadata(path).and_then(|m| m.modified()).ok()
+    };
+
+    std::thread::spawn(move || {
+        let mut last = modified(&path);
+        loop {
+            std::thread::sleep(WATCH_INTERVAL);
+            let current = modified(&path);
+            if current != last {
+                last = current;
+                if sender.send(()).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+    receiver
+}
+
+/// `$CORNA_CONFIG_DIR/config.toml` when that is set, for packaging and
+/// tests, else `config.toml` under the user's config directory
+pub fn default_config_path() -> Result<PathBuf> {
+    if let Some(dir) = std::env::var_os("CORNA_CONFIG_DIR").filter(|dir| !dir.is_empty()) {
+        return Ok(PathBuf::from(dir).join("config.toml"));
+    }
+    let config_dir = dirs::config_dir()
+        .ok_or_else(|| CornaError::Config("could not find the config directory".to_string()))?;
+    Ok(config_dir.join("corna").join("config.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_config(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("corna-{}-{}.toml", name, std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn save_margins_keeps_the_rest_of_the_file() {
+        let path = temp_config("margins", "# my clock\n[position]\nanchor = \"BottomLeft\"\n\n[margins]\ntop = 1 # from the top\n");
+        // Loaded before the anchor edit on disk, which a drag must not undo
+        let mut config = Config { path: Some(path.clone()), ..Config::default() };
+        config.margins.top = 40;
+        config.margins.left = 7;
+        config.save_margins().unwrap();
+
+        let saved = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(saved.starts_with("# my clock\n"));
+        assert!(saved.contains("top = 40 # from the top"));
+        assert!(saved.contains("anchor = \"BottomLeft\""));
+        assert!(saved.contains("left = 7"));
+        // Keys the file never had stay out of it
+        assert!(!saved.contains("[clock]"));
+    }
+
+    #[test]
+    fn save_margins_refuses_the_fallback_default() {
+        assert!(Config::default().save_margins().is_err());
+    }
+}
@@ -0,0 +1,43 @@
+use khronos_egl as egl;
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// Failures from the EGL, shader and config layers. The binary folds these
+/// into anyhow in `main`; matching on them lets callers react to specific
+/// cases such as a lost GL context.
+#[derive(Debug, Error)]
+pub enum CornaError {
+    /// Creating the wl_egl_window for a surface failed
+    #[error("wayland: {0}")]
+    Wayland(#[from] wayland_egl::Error),
+
+    #[error("EGL: {0}")]
+    Egl(#[from] egl::Error),
+
+    /// None of the config requests matched; lists each one and why it failed
+    #[error("EGL: no usable config, tried {0}")]
+    EglConfig(String),
+
+    /// Compile or link failure, with the driver's info log
+    #[error("shader: {0}")]
+    Shader(String),
+
+    #[error("config: {0}")]
+    Config(String),
+
+    #[error("{}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+impl CornaError {
+    /// The GL context is gone (e.g. after a GPU reset) and has to be recreated
+    pub fn is_context_lost(&self) -> bool {
+        matches!(self, CornaError::Egl(egl::Error::ContextLost))
+    }
+}
+
+pub type Result<T> = std::result::Result<T, CornaError>;
@@ -0,0 +1,117 @@
+use super::Feature;
+use crate::app::UiEvent;
+use crate::config::AlarmConfig;
+use crate::gfx::{draw::DrawContext, math::Rect};
+use crate::wayland::window_manager::WindowId;
+use log::{info, warn};
+use std::process::{Child, Command};
+use time::{Date, OffsetDateTime};
+
+/// Fires on configured "HH:MM" times and, optionally, on every hour. Has no
+/// window of its own; App pulses the clock when it fires.
+pub struct Alarm {
+    times: Vec<(u8, u8)>,
+    chime_hourly: bool,
+    sound: String,
+    /// Minute last evaluated, so the check runs once per minute
+    last_checked: Option<(u8, u8)>,
+    /// Minute last fired, guarding against a second fire in the same minute
+    last_fired: Option<(Date, u8, u8)>,
+    fired: bool,
+    player: Option<Child>,
+}
+
+impl Alarm {
+    pub fn new(config: &AlarmConfig) -> Self {
+        let times = config.times.iter()
+            .filter_map(|time| {
+                let parsed = parse_hh_mm(time);
+                if parsed.is_none() {
+                    warn!("Ignoring alarm time '{}', expected HH:MM", time);
+                }
+                parsed
+            })
+            .collect();
+
+        Self {
+            times,
+            chime_hourly: config.chime_hourly,
+            sound: config.sound.clone(),
+            last_checked: None,
+            last_fired: None,
+            fired: false,
+            player: None,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.chime_hourly || !self.times.is_empty()
+    }
+
+    /// True once after each fire
+    pub fn take_fired(&mut self) -> bool {
+        std::mem::take(&mut self.fired)
+    }
+
+    fn fire(&mut self) {
+        self.fired = true;
+        // A chime still playing from the last fire is left to finish
+        if self.player.is_some() {
+            return;
+        }
+        match Command::new("paplay").arg(&self.sound).spawn() {
+            Ok(child) => self.player = Some(child),
+            Err(e) => warn!("Failed to play alarm sound {}: {}", self.sound, e),
+        }
+    }
+}
+
+impl Feature for Alarm {
+    fn name(&self) -> &'static str {
+        "alarm"
+    }
+
+    fn desired_expanded_size(&self) -> (u32, u32) {
+        (0, 0)
+    }
+
+    fn update(&mut self, _dt: f32, _now: f32) {
+        // Reap the player once it's done
+        if let Some(child) = &mut self.player {
+            if !matches!(child.try_wait(), Ok(None)) {
+                self.player = None;
+            }
+        }
+
+        let Ok(now) = OffsetDateTime::now_local() else { return };
+        let minute = (now.hour(), now.minute());
+        if self.last_checked == Some(minute) {
+            return;
+        }
+        self.last_checked = Some(minute);
+
+        let due = (self.chime_hourly && now.minute() == 0) || self.times.contains(&minute);
+        let marker = (now.date(), now.hour(), now.minute());
+        if due && self.last_fired != Some(marker) {
+            info!("Alarm at {:02}:{:02}", now.hour(), now.minute());
+            self.last_fired = Some(marker);
+            self.fire();
+        }
+    }
+
+    fn handle_event(&mut self, _event: UiEvent) -> bool {
+        false
+    }
+
+    fn window(&self) -> Option<WindowId> {
+        None
+    }
+
+    fn render(&self, _draw: &mut DrawContext, _viewport: Rect) {}
+}
+
+pub fn parse_hh_mm(time: &str) -> Option<(u8, u8)> {
+    let (hour, minute) = time.trim().split_once(':')?;
+    let (hour, minute): (u8, u8) = (hour.parse().ok()?, minute.parse().ok()?);
+    (hour < 24 && minute < 60).then_some((hour, minute))
+}
@@ -0,0 +1,150 @@
+use super::{pomodoro::render_pair, Feature};
+use crate::app::UiEvent;
+use crate::config::{Config, Theme};
+use crate::gfx::{draw::DrawContext, math::{Color, Rect}};
+use crate::wayland::window_manager::WindowId;
+use anyhow::{Context, Result};
+use log::warn;
+use std::path::PathBuf;
+
+/// Logical size of the battery window
+const BATTERY_WINDOW_SIZE: [u32; 2] = [56, 30];
+
+/// sysfs is cheap but not free; the capacity rarely moves faster than this
+const POLL_INTERVAL: f32 = 10.0;
+
+const LOW_THRESHOLD: u8 = 20;
+
+#[derive(Debug, Clone, Copy)]
+struct Reading {
+    capacity: u8,
+    charging: bool,
+}
+
+pub struct Battery {
+    supply_dir: PathBuf,
+    reading: Option<Reading>,
+    last_poll: Option<f32>,
+    read_failed_logged: bool,
+    foreground: Color,
+}
+
+impl Battery {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            supply_dir: PathBuf::from("/sys/class/power_supply").join(&config.battery.supply),
+            reading: None,
+            last_poll: None,
+            read_failed_logged: false,
+            foreground: config.theme.foreground_color(),
+        }
+    }
+
+    pub fn set_theme(&mut self, theme: &Theme) {
+        self.foreground = theme.foreground_color();
+    }
+
+    fn read(&self) -> Result<Reading> {
+        let capacity_path = self.supply_dir.join("capacity");
+        let capacity = std::fs::read_to_string(&capacity_path)
+            .with_context(|| format!("reading {}", capacity_path.display()))?;
+        let capacity: u8 = capacity.trim().parse()
+            .with_context(|| format!("parsing {}", capacity_path.display()))?;
+
+        let status_path = self.supply_dir.join("status");
+        let status = std::fs::read_to_string(&status_path)
+            .with_context(|| format!("reading {}", status_path.display()))?;
+
+        Ok(Reading {
+            capacity: capacity.min(100),
+            charging: status.trim() == "Charging",
+        })
+    }
+
+    fn poll(&mut self) {
+        match self.read() {
+            Ok(reading) => {
+                self.reading = Some(reading);
+                self.read_failed_logged = false;
+            }
+            Err(e) => {
+                if !self.read_failed_logged {
+                    warn!("Battery status unavailable: {:#}", e);
+                    self.read_failed_logged = true;
+                }
+                self.reading = None;
+            }
+        }
+    }
+
+    fn digit_color(&self, reading: Reading) -> Color {
+        if reading.charging {
+            Color::rgba(64, 224, 96, 255)
+        } else if reading.capacity < LOW_THRESHOLD {
+            Color::rgba(255, 176, 0, 255)
+        } else {
+            self.foreground
+        }
+    }
+}
+
+impl Feature for Battery {
+    fn name(&self) -> &'static str {
+        "battery"
+    }
+
+    fn desired_expanded_size(&self) -> (u32, u32) {
+        (BATTERY_WINDOW_SIZE[0], BATTERY_WINDOW_SIZE[1])
+    }
+
+    fn update(&mut self, _dt: f32, now: f32) {
+        if !self.last_poll.is_some_and(|last| now - last < POLL_INTERVAL) {
+            self.last_poll = Some(now);
+            self.poll();
+        }
+    }
+
+    fn handle_event(&mut self, _event: UiEvent) -> bool {
+        false
+    }
+
+    fn window(&self) -> Option<WindowId> {
+        Some(WindowId::Battery)
+    }
+
+    fn render(&self, draw: &mut DrawContext, viewport: Rect) {
+        let outer_padding = 3.0;
+        let margin = 2.0;
+        let spacing = 2.0;
+
+        let face_w = viewport.width - outer_padding * 2.0;
+        let face_h = viewport.height - outer_padding * 2.0;
+        draw.rect(outer_padding, outer_padding, face_w, face_h, Color::rgba(0, 0, 0, 255));
+
+        let digit_height = face_h - margin * 2.0;
+        let digit_width = digit_height * 0.62;
+        let start_x = outer_padding + margin;
+        let start_y = outer_padding + margin;
+        let cell = Rect::new(start_x, start_y, digit_width, digit_height);
+
+        let Some(reading) = self.reading else {
+            // No supply: two dashes
+            render_pair(draw, None, cell, spacing, self.foreground);
+            return;
+        };
+
+        // Two digits only, so a full battery reads 99
+        let color = self.digit_color(reading);
+        render_pair(draw, Some(reading.capacity), cell, spacing, color);
+
+        // Charging: a plus sign after the digits
+        if reading.charging {
+            let size = digit_height * 0.45;
+            let bar = size * 0.3;
+            let cx = start_x + digit_width * 2.0 + spacing * 2.0 + size * 0.5 + 1.0;
+            let cy = start_y + digit_height * 0.5;
+            draw.rect(cx - size * 0.5, cy - bar * 0.5, size, bar, color);
+            draw.rect(cx - bar * 0.5, cy - size * 0.5, bar, size, color);
+        }
+    }
+}
@@ -1,168 +1,685 @@
+use super::{alarm::parse_hh_mm, seg::SEGMENT_MAP, Feature};
 use crate::app::UiEvent;
-use crate::gfx::{anim::Timeline, draw::DrawContext, math::{Color, Rect}};
+use crate::config::{ClockFace, ClockStyle, Config, LayoutField, NightDimConfig, SecondsStyle, Theme};
+use crate::wayland::window_manager::WindowId;
+use crate::gfx::{anim::{lerp, Easing, Timeline}, draw::{DrawContext, SLICE_OVERLAP}, math::{Color, Rect, Vec2}};
+use std::str::FromStr;
 use time::OffsetDateTime;
-use log::info;
-
-// Seven-segment display mapping
-const SEGMENT_MAP: [[bool; 7]; 10] = [
-    [true, true, true, true, true, true, false],     // 0
-    [false, true, true, false, false, false, false], // 1
-    [true, true, false, true, true, false, true],    // 2
-    [true, true, true, true, false, false, true],    // 3
-    [false, true, true, false, false, true, true],   // 4
-    [true, false, true, true, false, true, true],    // 5
-    [true, false, true, true, true, true, true],     // 6
-    [true, true, true, false, false, false, false],  // 7
-    [true, true, true, true, true, true, true],      // 8
-    [true, true, true, true, false, true, true],     // 9
-];
+use time_tz::{OffsetDateTimeExt, Tz};
+use log::{info, warn};
+
+/// Digit color modes, in scroll order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorMode {
+    ClassicRed,
+    Cyan,
+    Green,
+    Amber,
+    Purple,
+    White,
+    RainbowWave,
+    CascadeBreathing,
+    Matrix,
+    Fire,
+    ElectricStorm,
+}
+
+impl ColorMode {
+    pub const ALL: [ColorMode; 11] = [
+        ColorMode::ClassicRed,
+        ColorMode::Cyan,
+        ColorMode::Green,
+        ColorMode::Amber,
+        ColorMode::Purple,
+        ColorMode::White,
+        ColorMode::RainbowWave,
+        ColorMode::CascadeBreathing,
+        ColorMode::Matrix,
+        ColorMode::Fire,
+        ColorMode::ElectricStorm,
+    ];
+
+    /// Name used in the config file
+    pub fn name(self) -> &'static str {
+        match self {
+            ColorMode::ClassicRed => "classic_red",
+            ColorMode::Cyan => "cyan",
+            ColorMode::Green => "green",
+            ColorMode::Amber => "amber",
+            ColorMode::Purple => "purple",
+            ColorMode::White => "white",
+            ColorMode::RainbowWave => "rainbow_wave",
+            ColorMode::CascadeBreathing => "cascade_breathing",
+            ColorMode::Matrix => "matrix",
+            ColorMode::Fire => "fire",
+            ColorMode::ElectricStorm => "electric_storm",
+        }
+    }
+
+    pub fn len() -> usize {
+        Self::ALL.len()
+    }
+
+    pub fn next(self) -> Self {
+        Self::ALL[(self as usize + 1) % Self::len()]
+    }
+
+    pub fn prev(self) -> Self {
+        Self::ALL[(self as usize + Self::len() - 1) % Self::len()]
+    }
+
+    /// Parse a configured name, falling back to classic red on unknown names
+    pub fn from_config(name: &str) -> Self {
+        name.parse().unwrap_or_else(|e| {
+            warn!("{}, using classic_red", e);
+            ColorMode::ClassicRed
+        })
+    }
+
+    /// Color of one segment of the digit at `digit_pos` (out of `total_digits`)
+    pub fn color(self, time: f32, digit_pos: u8, total_digits: u8, segment: u8) -> Color {
+        // Calculate position-based phase offset for waves and animations
+        let pos_offset = digit_pos as f32 / total_digits as f32;
+        let seg_offset = segment as f32 / 7.0;
+
+        match self {
+            ColorMode::ClassicRed => Color::rgba(255, 64, 64, 255),
+            ColorMode::Cyan => Color::rgba(0, 255, 255, 255),
+            ColorMode::Green => Color::rgba(64, 255, 64, 255),
+            ColorMode::Amber => Color::rgba(255, 191, 0, 255),
+            ColorMode::Purple => Color::rgba(191, 64, 255, 255),
+            ColorMode::White => Color::rgba(255, 255, 255, 255),
+
+            ColorMode::RainbowWave => {
+                // Rainbow Wave - flows across digits
+                let hue = (time * 0.2 + pos_offset * 0.5 + seg_offset * 0.05) % 1.0;
+                hsv_to_rgb(hue, 1.0, 1.0)
+            }
+
+            ColorMode::CascadeBreathing => {
+                // Cascade Breathing - pulses from left to right
+                let phase = time + pos_offset * 0.5;
+                let brightness = (phase.sin() * 0.3 + 0.7).max(0.4).min(1.0);
+                let val = (255.0 * brightness) as u8;
+                Color::rgba(val, val / 4, val / 4, 255)
+            }
+
+            ColorMode::Matrix => {
+                // Matrix Rain Effect - segments cascade downward
+                let cascade_time = time * 2.0 + digit_pos as f32 * 0.3 + segment as f32 * 0.1;
+                let intensity = ((cascade_time % 3.0) - 1.5).abs() / 1.5;
+                let green = (64.0 + 191.0 * intensity) as u8;
+                let blue = (255.0 * (1.0 - intensity * 0.7)) as u8;
+                Color::rgba(0, green, blue, 255)
+            }
+
+            ColorMode::Fire => {
+                // Fire Effect - flickering per segment
+                let flicker = (time * 10.0 + digit_pos as f32 * 3.7 + segment as f32 * 5.3).sin();
+                let random = ((digit_pos as f32 * 7.3 + segment as f32 * 13.7).sin() * 43758.5453).fract();
+                let intensity = (0.7 + flicker * 0.2 + random * 0.1).max(0.5).min(1.0);
+
+                let r = (255.0 * intensity) as u8;
+                let g = (191.0 * intensity * 0.7) as u8;
+                let b = (64.0 * intensity * 0.2) as u8;
+                Color::rgba(r, g, b, 255)
+            }
+
+            ColorMode::ElectricStorm => {
+                // Electric Storm - random segment flashes with propagation
+                let storm_phase = time * 3.0 + pos_offset * 2.0;
+                let flash = ((storm_phase * 7.3 + segment as f32 * 11.1).sin() * 137.5).fract();
+                let flash_intensity = if flash > 0.8 { 1.0 } else { 0.6 };
+
+                let base_color = if flash > 0.8 {
+                    Color::rgba(255, 255, 255, 255) // White flash
+                } else {
+                    // Electric blue base with variation
+                    let variation = (time * 0.5 + pos_offset).sin() * 0.2 + 0.8;
+                    Color::rgba(
+                        (100.0 * variation) as u8,
+                        (150.0 * variation) as u8,
+                        (255.0 * flash_intensity) as u8,
+                        255
+                    )
+                };
+                base_color
+            }
+
+        }
+    }
+}
+
+impl FromStr for ColorMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|mode| mode.name() == s)
+            .ok_or_else(|| anyhow::anyhow!("Unknown clock color mode '{}'", s))
+    }
+}
 
 pub struct Clock {
     last_sec: i32,
-    flip_timeline: Timeline,
+    /// Set when the displayed second changes, until `take_second_changed`
+    second_changed: bool,
+    /// Restarted every second; brightens the whole readout with `pulse_on_tick`
+    tick_timeline: Timeline,
+    pulse_on_tick: bool,
+    heartbeat: bool,
+    blink_colon: bool,
+    round_dots: bool,
+    corner_radius: f32,
+    /// Opacity of the face; it is skipped entirely when 0 or `show_face` is off
+    face_alpha: f32,
+    gradient_segments: bool,
+    /// The user's dimming on top of `brightness`, see `set_brightness`
+    user_brightness: f32,
+    night_dim: Option<NightDim>,
+    /// Off for the second half of each second with `blink_colon`
+    colon_visible: bool,
+    /// Brightening flashes when an alarm fires; idle unless `pulsing`
     pulse_timeline: Timeline,
+    pulsing: bool,
+    /// Progress of a pomodoro celebration shown on the digits
+    celebration: Option<f32>,
     hour_digits: [u8; 2],
     minute_digits: [u8; 2],
     second_digits: [u8; 2],
+    /// 1 for Monday through 7 for Sunday
+    weekday: u8,
+    month_digits: [u8; 2],
+    day_digits: [u8; 2],
+    /// Fields of the digital readout, before hidden seconds are dropped
+    layout: Vec<LayoutField>,
+    /// Window height once fully expanded, see `desired_expanded_size`
+    expanded_height: u32,
+    /// Odometer roll per digit position (HHMMSS), from the digit in `rolled_from`
+    roll_timelines: [Timeline; 6],
+    rolled_from: [u8; 6],
+    /// Brief brightening of each digit position after it changes
+    digit_pulses: [Option<Timeline>; 6],
+    tick_pulse: f32,
     is_pm: bool,
+    /// Resolved from `clock.time_format` once, since Auto reads the locale
+    twelve_hour: bool,
+    style: ClockStyle,
+    oled_safe: bool,
+    /// Steps through `OLED_SHIFTS` once a minute in OLED-safe mode
+    shift_timeline: Timeline,
+    shift_index: usize,
+    accent: Color,
+    background: Color,
+    color_mode: ColorMode,
+    // Mode being faded out after a change, until mode_fade completes
+    previous_mode: Option<ColorMode>,
+    mode_fade: Timeline,
+    /// Fraction of the current minute elapsed, for the seconds bar
+    minute_progress: f32,
+    /// Fraction of the current second elapsed, for the heartbeat dot
+    second_progress: f32,
+    /// Seconds since midnight, fractional when animating, for the analog hands
+    day_seconds: f32,
+    face: ClockFace,
+    animations_enabled: bool,
+    /// Shown instead of local time for a world clock
+    timezone: Option<&'static Tz>,
+    /// Scales digit colors, so a secondary clock reads as dimmer
+    brightness: f32,
+    seconds_style: SecondsStyle,
+    /// Shown instead of the wall clock, for reproducible frames
+    fixed_time: Option<OffsetDateTime>,
+    time: f32,
 }
 
 impl Clock {
-    pub fn new() -> Self {
+    pub fn new(config: &Config, color_mode: ColorMode) -> Self {
+        let theme = &config.theme;
         Self {
             last_sec: -1,
-            flip_timeline: Timeline::new(0.12),
-            pulse_timeline: Timeline::new(0.2),
+            second_changed: false,
+            tick_timeline: Timeline::with_easing(TICK_FLASH_SECONDS, Easing::Linear),
+            pulse_on_tick: config.clock.pulse_on_tick,
+            heartbeat: config.clock.heartbeat,
+            blink_colon: config.clock.blink_colon,
+            round_dots: config.clock.round_dots,
+            corner_radius: config.clock.corner_radius,
+            face_alpha: if config.clock.show_face { config.clock.face_alpha } else { 0.0 },
+            gradient_segments: config.clock.gradient_segments,
+            user_brightness: config.clock.brightness,
+            night_dim: NightDim::new(&config.clock.night_dim),
+            colon_visible: true,
+            pulse_timeline: Timeline::with_easing(1.2, Easing::Linear),
+            pulsing: false,
+            celebration: None,
             hour_digits: [0, 0],
             minute_digits: [0, 0],
             second_digits: [0, 0],
+            weekday: 1,
+            month_digits: [0, 1],
+            day_digits: [0, 1],
+            layout: config.clock.layout.clone(),
+            expanded_height: config.expanded_size.height.max(config.collapsed_size.height),
+            roll_timelines: std::array::from_fn(|_| Timeline::new(ROLL_SECONDS)),
+            rolled_from: [0; 6],
+            digit_pulses: Default::default(),
+            tick_pulse: config.clock.tick_pulse,
             is_pm: false,
+            twelve_hour: config.clock.time_format.is_twelve_hour(),
+            style: config.clock.style,
+            oled_safe: config.clock.oled_safe,
+            shift_timeline: Timeline::with_easing(60.0, Easing::Linear),
+            shift_index: 0,
+            accent: parse_accent(theme),
+            background: parse_background(theme),
+            color_mode,
+            previous_mode: None,
+            mode_fade: Timeline::new(0.2),
+            minute_progress: 0.0,
+            second_progress: 0.0,
+            day_seconds: 0.0,
+            face: config.clock.face,
+            animations_enabled: config.animations_enabled,
+            timezone: None,
+            brightness: 1.0,
+            seconds_style: config.clock.seconds_style,
+            fixed_time: None,
+            time: 0.0,
+        }
+    }
+
+    /// A secondary HH:MM clock showing `timezone` at reduced brightness
+    pub fn world(config: &Config, color_mode: ColorMode, timezone: &'static Tz) -> Self {
+        Self {
+            timezone: Some(timezone),
+            brightness: 0.7,
+            seconds_style: SecondsStyle::Hidden,
+            ..Self::new(config, color_mode)
+        }
+    }
+
+    pub fn is_world_clock(&self) -> bool {
+        self.timezone.is_some()
+    }
+
+    pub fn set_theme(&mut self, theme: &Theme) {
+        self.accent = parse_accent(theme);
+        self.background = parse_background(theme);
+    }
+
+    pub fn set_face(&mut self, face: ClockFace) {
+        self.face = face;
+    }
+
+    /// Dim the digits; 1.0 is full strength
+    pub fn set_brightness(&mut self, brightness: f32) {
+        self.user_brightness = brightness.clamp(0.0, 1.0);
+    }
+
+    pub fn set_blink_colon(&mut self, blink: bool) {
+        self.blink_colon = blink;
+    }
+
+    /// Whether `pos` lands on one of the digital readout's colons in a
+    /// `viewport` laid out like `render_clock`'s
+    pub fn colon_contains(&self, viewport: Rect, pos: Vec2) -> bool {
+        if self.face != ClockFace::Digital {
+            return false;
+        }
+        let spec = self.layout_spec();
+        let ClockLayout { digit_width, digit_height, colon_width, margin, face_w, .. } =
+            ClockLayout::new(&self.style, viewport.height, &spec);
+        let [shift_x, shift_y] = if self.oled_safe { OLED_SHIFTS[self.shift_index] } else { [0.0, 0.0] };
+        let start_x = viewport.width - face_w - OUTER_PADDING + shift_x + margin;
+        let start_y = OUTER_PADDING + shift_y + margin;
+
+        // A little slack makes the narrow target easier to hit
+        spec.place(digit_width, colon_width, self.style.spacing).into_iter().any(|(glyph, x, width)| {
+            glyph == Glyph::Colon
+                && Rect::new(start_x + x, start_y, width, digit_height).inset(-COLON_HIT_SLACK).contains(pos)
+        })
+    }
+
+    /// The readout's fields as currently shown
+    fn layout_spec(&self) -> LayoutSpec {
+        LayoutSpec::new(&self.layout, self.seconds_style == SecondsStyle::Digits)
+    }
+
+    /// Digit `index` of `field`, and its HHMMSS animation slot if it has one
+    fn field_digit(&self, field: LayoutField, index: usize) -> (u8, Option<usize>) {
+        match field {
+            LayoutField::Hours => (self.hour_digits[index], Some(index)),
+            LayoutField::Minutes => (self.minute_digits[index], Some(2 + index)),
+            LayoutField::Seconds => (self.second_digits[index], Some(4 + index)),
+            LayoutField::DayOfWeek => (self.weekday, None),
+            LayoutField::Month => (self.month_digits[index], None),
+            LayoutField::Day => (self.day_digits[index], None),
+        }
+    }
+
+    pub fn set_seconds_style(&mut self, style: SecondsStyle) {
+        self.seconds_style = style;
+    }
+
+    /// Freeze the display at `time` (headless snapshots)
+    pub fn set_fixed_time(&mut self, time: OffsetDateTime) {
+        self.fixed_time = Some(time);
+    }
+
+    fn current_time(&self) -> Option<OffsetDateTime> {
+        if self.fixed_time.is_some() {
+            return self.fixed_time;
         }
+        match self.timezone {
+            Some(tz) => Some(OffsetDateTime::now_utc().to_timezone(tz)),
+            None => OffsetDateTime::now_local().ok(),
+        }
+    }
+
+    /// Flash the digits a few times, e.g. for an alarm
+    pub fn pulse(&mut self, now: f32) {
+        self.pulsing = true;
+        self.pulse_timeline.start(now);
+    }
+
+    /// 0..1 boost toward white: three flashes fading out over the pulse
+    fn pulse_strength(&self) -> f32 {
+        if !self.pulsing {
+            return 0.0;
+        }
+        let t = self.pulse_timeline.progress();
+        (1.0 - t) * (t * std::f32::consts::PI * 3.0).sin().abs()
+    }
+
+    pub fn set_celebration(&mut self, progress: Option<f32>) {
+        self.celebration = progress;
     }
 
-    pub fn update(&mut self, _dt: f32, now: f32) {
-        if let Ok(time) = OffsetDateTime::now_local() {
+    /// Switch palettes with a short crossfade from the current one
+    pub fn set_color_mode(&mut self, mode: ColorMode, now: f32) {
+        if mode == self.color_mode {
+            return;
+        }
+        self.previous_mode = Some(self.color_mode);
+        self.color_mode = mode;
+        self.mode_fade.start(now);
+    }
+
+    fn tick(&mut self, now: f32) {
+        self.time = now;
+        if let Some(time) = self.current_time() {
             let sec = time.second() as i32;
 
+            // Without animations the bar advances in whole-second steps
+            let sub_second = if self.animations_enabled { time.nanosecond() as f32 / 1e9 } else { 0.0 };
+            self.minute_progress = (sec as f32 + sub_second) / 60.0;
+            self.second_progress = time.nanosecond() as f32 / 1e9;
+            self.day_seconds = time.hour() as f32 * 3600.0 + time.minute() as f32 * 60.0 + sec as f32 + sub_second;
+
+            let colon_visible = !self.blink_colon || time.nanosecond() < 500_000_000;
+            if colon_visible != self.colon_visible {
+                self.colon_visible = colon_visible;
+                self.second_changed = true;
+            }
+
             if sec != self.last_sec {
+                let first_tick = self.last_sec == -1;
+                let previous = self.digits();
                 self.last_sec = sec;
-                self.flip_timeline.start(now);
-                self.pulse_timeline.start(now);
+                self.second_changed = true;
+                self.tick_timeline.start(now);
 
-                // Update digits - convert to 12h time
-                let mut hour_24 = time.hour() as u8;
+                // Update digits, converting to 12h time unless configured otherwise
+                let hour_24 = time.hour();
                 self.is_pm = hour_24 >= 12;
-                let hour_12 = {
+                let hour = if self.twelve_hour {
                     let mut h = hour_24 % 12;
                     if h == 0 { h = 12; }
                     h
+                } else {
+                    hour_24
                 };
-                let minute = time.minute() as u8;
-                let second = time.second() as u8;
+                let minute = time.minute();
+                let second = time.second();
 
-                self.hour_digits = [hour_12 / 10, hour_12 % 10];
+                self.hour_digits = [hour / 10, hour % 10];
                 self.minute_digits = [minute / 10, minute % 10];
                 self.second_digits = [second / 10, second % 10];
+                self.weekday = time.weekday().number_from_monday();
+                let (month, day) = (time.month() as u8, time.day());
+                self.month_digits = [month / 10, month % 10];
+                self.day_digits = [day / 10, day % 10];
+
+                // HH:MM digits roll over when they change; seconds just snap.
+                // Every changed digit also gets a short pulse.
+                if self.animations_enabled && !first_tick {
+                    let current = self.digits();
+                    for pos in 0..6 {
+                        if current[pos] == previous[pos] {
+                            continue;
+                        }
+                        if pos < 4 {
+                            self.rolled_from[pos] = previous[pos];
+                            self.roll_timelines[pos].start(now);
+                        }
+                        if self.tick_pulse > 0.0 {
+                            let mut pulse = Timeline::with_easing(TICK_PULSE_SECONDS, Easing::Linear);
+                            pulse.start(now);
+                            self.digit_pulses[pos] = Some(pulse);
+                        }
+                    }
+                }
             }
         }
 
-        self.flip_timeline.update(now);
+        self.tick_timeline.update(now);
+        for roll in &mut self.roll_timelines {
+            roll.update(now);
+        }
+        for slot in &mut self.digit_pulses {
+            if let Some(pulse) = slot {
+                pulse.update(now);
+                if pulse.is_complete() {
+                    *slot = None;
+                }
+            }
+        }
         self.pulse_timeline.update(now);
+        if self.pulsing && self.pulse_timeline.is_complete() {
+            self.pulsing = false;
+        }
+
+        self.mode_fade.update(now);
+        if self.mode_fade.is_complete() {
+            self.previous_mode = None;
+        }
+
+        if self.oled_safe {
+            self.shift_timeline.update(now);
+            if self.shift_timeline.is_complete() {
+                self.shift_index = (self.shift_index + 1) % OLED_SHIFTS.len();
+                self.shift_timeline.start(now);
+            }
+        }
     }
 
-    pub fn render(&self, draw: &mut DrawContext, viewport: Rect, show_seconds: bool, color_mode: u8, time: f32) {
-        self.render_clock(draw, viewport, show_seconds, color_mode, time);
+    /// Whether the second rolled over (or a colon blinked) since the last call
+    pub fn take_second_changed(&mut self) -> bool {
+        std::mem::take(&mut self.second_changed)
     }
 
-    fn render_clock(&self, draw: &mut DrawContext, viewport: Rect, show_seconds: bool, color_mode: u8, time: f32) {
-        // Compact 12h time HH:MM or HH:MM:SS
-        let outer_padding = 4.0;
+    /// Whether anything is changing between seconds; these effects run even
+    /// with `animations_enabled` off
+    pub fn is_animating(&self) -> bool {
+        self.pulsing
+            || self.previous_mode.is_some()
+            || self.celebration.is_some()
+            || self.digit_pulses.iter().any(Option::is_some)
+            || (self.pulse_on_tick && !self.tick_timeline.is_complete())
+            || self.heartbeat
+    }
 
-        // Ratios
-        let r_w = 0.62;   // digit_width = r_w * dh
-        let r_c = 0.28;   // colon_width = r_c * digit_width
-        let r_m = 1.5;    // margin = r_m * spacing
+    fn digits(&self) -> [u8; 6] {
+        let [h0, h1] = self.hour_digits;
+        let [m0, m1] = self.minute_digits;
+        let [s0, s1] = self.second_digits;
+        [h0, h1, m0, m1, s0, s1]
+    }
 
-        // Fixed inter-glyph spacing
-        let spacing = 6.0f32;
+    fn render_clock(&self, draw: &mut DrawContext, viewport: Rect, seconds_style: SecondsStyle, time: f32) {
+        let spec = LayoutSpec::new(&self.layout, seconds_style == SecondsStyle::Digits);
+        let ClockLayout { digit_width, digit_height, colon_width, total_width, margin, face_w, face_h } =
+            ClockLayout::new(&self.style, viewport.height, &spec);
 
-        // Compute max digit height by height constraint only (keep height consistent)
-        let margin_h = r_m * spacing;
-        let mut dh_by_h = viewport.height - outer_padding * 2.0 - margin_h * 2.0;
-        if dh_by_h < 0.0 { dh_by_h = 0.0; }
+        // Face anchored to top-right inside viewport with outer padding,
+        // nudged around within that padding in OLED-safe mode
+        let [shift_x, shift_y] = if self.oled_safe { OLED_SHIFTS[self.shift_index] } else { [0.0, 0.0] };
+        let face_x = viewport.width - face_w - OUTER_PADDING + shift_x;
+        let face_y = OUTER_PADDING + shift_y;
 
-        // Use height constraint for digit size (don't change based on seconds display)
-        let digit_height = dh_by_h;
+        // Background face (theme background); left transparent for OLED
+        if let Some(color) = self.face_color() {
+            draw.round_rect(face_x, face_y, face_w, face_h, self.corner_radius, color);
+        }
 
-        let digit_width = digit_height * r_w;
-        let colon_width = digit_width * r_c;
+        // Digits start inside bezel
+        let start_x = face_x + margin;
+        let start_y = face_y + margin;
 
-        let total_width = if show_seconds {
-            digit_width * 6.0 + spacing * 7.0 + colon_width * 2.0
-        } else {
-            digit_width * 4.0 + spacing * 3.0 + colon_width
-        };
+        let num_digits = spec.digit_count() as u8;
+        let dot = digit_width * 0.11;
 
-        // Larger bezel margin around readout
-        let mut margin = spacing * r_m;
-        if margin < 4.0 { margin = 4.0; }
+        // Separators take the color of the digit after them
+        let mut digit_pos = 0;
+        let mut placed = spec.place(digit_width, colon_width, self.style.spacing).into_iter().peekable();
+        while let Some((glyph, x, width)) = placed.next() {
+            let x = start_x + x;
+            let next_slot = match placed.peek() {
+                Some(&(Glyph::Digit(field, index), ..)) => self.field_digit(field, index).1,
+                _ => None,
+            };
+            match glyph {
+                Glyph::Digit(field, index) => {
+                    let (digit, slot) = self.field_digit(field, index);
+                    let at = DigitPos { pos: digit_pos, total: num_digits, slot };
+                    self.render_digit_with_pos(draw, digit, x, start_y, digit_width, digit_height, time, at);
+                    digit_pos += 1;
+                }
+                Glyph::Colon if self.colon_visible => {
+                    let colon_color = self.segment_color(time, digit_pos, num_digits, 0, next_slot);
+                    self.render_colon(draw, x, start_y, dot, digit_height, colon_color);
+                }
+                Glyph::Slash => {
+                    let color = self.segment_color(time, digit_pos, num_digits, 0, next_slot);
+                    draw.line(x, start_y + digit_height, x + width, start_y, dot, color);
+                }
+                Glyph::Colon | Glyph::Blank => {}
+            }
+        }
 
-        // Compute face rect anchored to top-right inside viewport with outer padding
-        let face_w = total_width + margin * 2.0;
-        let face_h = digit_height + margin * 2.0;
-        let face_x = viewport.width - face_w - outer_padding;
-        let face_y = outer_padding;
+        // Seconds bar in the bottom bezel, filling over the minute
+        if seconds_style == SecondsStyle::Bar {
+            let bar_h = 2.0;
+            let bar_y = start_y + digit_height + (margin - bar_h) * 0.5;
+            let bar_color = self.segment_color(time, 0, num_digits, 0, None);
+            draw.rect(start_x, bar_y, total_width * self.minute_progress, bar_h, bar_color);
+        }
 
-        // Background face (black)
-        draw.rect(face_x, face_y, face_w, face_h, Color::rgba(0, 0, 0, 255));
+        // Heartbeat dot in the top-right bezel: swells and brightens at the
+        // top of each second, then fades until the next
+        if self.heartbeat {
+            let beat = (1.0 - self.second_progress).powi(3);
+            let radius = margin * 0.2 * (0.6 + 0.4 * beat);
+            let color = self.segment_color(time, 0, num_digits, 0, None);
+            let color = color.with_alpha(color.a * (0.3 + 0.7 * beat));
+            draw.circle(face_x + face_w - margin * 0.5, face_y + margin * 0.5, radius, 16, color);
+        }
+    }
 
-        // Digits start inside bezel
-        let start_x = face_x + margin;
-        let start_y = face_y + margin;
+    /// Two `dot`-sized colon dots at `x`, square or round with `round_dots`
+    fn render_colon(&self, draw: &mut DrawContext, x: f32, y: f32, dot: f32, digit_height: f32, color: Color) {
+        for offset in [0.3, 0.62] {
+            let dot_y = y + digit_height * offset;
+            if self.round_dots {
+                draw.circle(x + dot * 0.5, dot_y + dot * 0.5, dot * 0.5, 12, color);
+            } else {
+                draw.rect(x, dot_y, dot, dot, color);
+            }
+        }
+    }
 
-        let num_digits = if show_seconds { 6 } else { 4 };
+    /// The theme background at `face_alpha`, or None when no face is drawn
+    fn face_color(&self) -> Option<Color> {
+        if self.oled_safe || self.face_alpha <= 0.0 {
+            return None;
+        }
+        Some(self.background.with_alpha(self.background.a * self.face_alpha))
+    }
 
-        // Render HH with position info
-        self.render_digit_with_pos(draw, self.hour_digits[0], start_x, start_y,
-            digit_width, digit_height, color_mode, time, 0, num_digits);
-        self.render_digit_with_pos(draw, self.hour_digits[1], start_x + digit_width + spacing, start_y,
-            digit_width, digit_height, color_mode, time, 1, num_digits);
+    /// Dial with 12 ticks and hour/minute/second hands, centred in the viewport
+    fn render_analog(&self, draw: &mut DrawContext, viewport: Rect, seconds_style: SecondsStyle, time: f32) {
+        use std::f32::consts::{FRAC_PI_2, TAU};
 
-        // Colon with position color
-        let colon_x = start_x + digit_width * 2.0 + spacing * 2.0;
-        let dot = digit_width * 0.11;
-        let colon_color = self.get_color_for_position(color_mode, time, 2, num_digits, 0);
-        draw.rect(colon_x, start_y + digit_height * 0.3, dot, dot, colon_color);
-        draw.rect(colon_x, start_y + digit_height * 0.62, dot, dot, colon_color);
-
-        // Minutes with position info
-        let minute_x = colon_x + colon_width + spacing;
-        self.render_digit_with_pos(draw, self.minute_digits[0], minute_x, start_y,
-            digit_width, digit_height, color_mode, time, 2, num_digits);
-        self.render_digit_with_pos(draw, self.minute_digits[1], minute_x + digit_width + spacing, start_y,
-            digit_width, digit_height, color_mode, time, 3, num_digits);
-
-        // Seconds (if enabled)
-        if show_seconds {
-            // Second colon with position color
-            let colon2_x = minute_x + digit_width * 2.0 + spacing * 2.0;
-            let colon2_color = self.get_color_for_position(color_mode, time, 4, num_digits, 0);
-            draw.rect(colon2_x, start_y + digit_height * 0.3, dot, dot, colon2_color);
-            draw.rect(colon2_x, start_y + digit_height * 0.62, dot, dot, colon2_color);
-
-            // Second digits with position info
-            let second_x = colon2_x + colon_width + spacing;
-            self.render_digit_with_pos(draw, self.second_digits[0], second_x, start_y,
-                digit_width, digit_height, color_mode, time, 4, num_digits);
-            self.render_digit_with_pos(draw, self.second_digits[1], second_x + digit_width + spacing, start_y,
-                digit_width, digit_height, color_mode, time, 5, num_digits);
+        let [shift_x, shift_y] = if self.oled_safe { OLED_SHIFTS[self.shift_index] } else { [0.0, 0.0] };
+        let center = viewport.center();
+        let (cx, cy) = (center.x + shift_x, center.y + shift_y);
+        let radius = (viewport.width.min(viewport.height) * 0.5 - OUTER_PADDING).max(1.0);
+
+        if let Some(color) = self.face_color() {
+            draw.circle(cx, cy, radius, 64, color);
+        }
+
+        // Clockwise from 12 o'clock, in screen space
+        let point = |angle: f32, r: f32| (cx + angle.cos() * r, cy + angle.sin() * r);
+        let turn = |fraction: f32| fraction * TAU - FRAC_PI_2;
+
+        for tick in 0..12 {
+            let angle = turn(tick as f32 / 12.0);
+            let quarter = tick % 3 == 0;
+            let (x0, y0) = point(angle, radius * if quarter { 0.72 } else { 0.8 });
+            let (x1, y1) = point(angle, radius * 0.92);
+            let color = self.segment_color(time, 0, 3, tick as u8 % 7, None);
+            draw.line(x0, y0, x1, y1, if quarter { 2.0 } else { 1.0 }, color);
+        }
+
+        let seconds = self.day_seconds;
+        let mut hands = vec![
+            (turn((seconds / 3600.0 % 12.0) / 12.0), 0.5, 3.0, 0),
+            (turn(seconds / 60.0 % 60.0 / 60.0), 0.75, 2.0, 1),
+        ];
+        if seconds_style != SecondsStyle::Hidden {
+            hands.push((turn(seconds % 60.0 / 60.0), 0.85, 1.0, 2));
         }
+        for (angle, length, thickness, pos) in hands {
+            let (x, y) = point(angle, radius * length);
+            draw.line(cx, cy, x, y, thickness, self.segment_color(time, pos, 3, 0, None));
+        }
+
+        let hub = self.segment_color(time, 0, 3, 0, None);
+        draw.circle(cx, cy, 2.5, 12, hub);
     }
 
     fn render_digit_with_pos(&self, draw: &mut DrawContext, digit: u8, x: f32, y: f32,
-                             width: f32, height: f32, color_mode: u8, time: f32,
-                             digit_pos: u8, total_digits: u8) {
+                             width: f32, height: f32, time: f32, at: DigitPos) {
+        let roll = at.slot.map(|slot| (&self.roll_timelines[slot], self.rolled_from[slot]));
+        let Some((roll, from)) = roll.filter(|&(roll, from)| !roll.is_complete() && from != digit) else {
+            self.render_digit(draw, digit, Rect::new(x, y, width, height), time, at);
+            return;
+        };
+
+        // Odometer roll: the old digit slides up out of the cell as the new one follows it in
+        let offset = roll.eased_progress() * height;
+        draw.push_clip(Rect::new(x, y, width, height));
+        self.render_digit(draw, from, Rect::new(x, y - offset, width, height), time, at);
+        self.render_digit(draw, digit, Rect::new(x, y + height - offset, width, height), time, at);
+        draw.pop_clip();
+    }
+
+    fn render_digit(&self, draw: &mut DrawContext, digit: u8, cell: Rect, time: f32, at: DigitPos) {
         if digit > 9 { return; }
+        let Rect { x, y, width, height } = cell;
         let segments = SEGMENT_MAP[digit as usize];
         let segment_width = width * 0.8;
         let segment_thickness = width * 0.15;
@@ -173,7 +690,14 @@ impl Clock {
         // Render each segment with its own color based on position
         for (seg_idx, &is_on) in segments.iter().enumerate() {
             if is_on {
-                let color = self.get_color_for_position(color_mode, time, digit_pos, total_digits, seg_idx as u8);
+                let color = self.segment_color(time, at.pos, at.total, seg_idx as u8, at.slot);
+                // With gradient_segments the whole digit darkens toward its foot
+                let color = if self.gradient_segments {
+                    let foot = Color::new(color.r * GRADIENT_FOOT, color.g * GRADIENT_FOOT, color.b * GRADIENT_FOOT, color.a);
+                    Shade { top: color, bottom: foot, y, height }
+                } else {
+                    Shade::flat(color)
+                };
 
                 match seg_idx {
                     0 => self.render_horizontal_segment(draw, x + h_offset, y, segment_width, segment_thickness, bevel, color),
@@ -189,80 +713,69 @@ impl Clock {
         }
     }
 
-    fn get_color_for_position(&self, mode: u8, time: f32, digit_pos: u8, total_digits: u8, segment: u8) -> Color {
-        // Calculate position-based phase offset for waves and animations
-        let pos_offset = digit_pos as f32 / total_digits as f32;
-        let seg_offset = segment as f32 / 7.0;
-
-        match mode {
-            0 => Color::rgba(255, 64, 64, 255),      // Classic Red
-            1 => Color::rgba(0, 255, 255, 255),      // Cyan
-            2 => Color::rgba(64, 255, 64, 255),      // Green
-            3 => Color::rgba(255, 191, 0, 255),      // Amber
-            4 => Color::rgba(191, 64, 255, 255),     // Purple
-            5 => Color::rgba(255, 255, 255, 255),    // White
-
-            6 => {
-                // Rainbow Wave - flows across digits
-                let hue = (time * 0.2 + pos_offset * 0.5 + seg_offset * 0.05) % 1.0;
-                self.hsv_to_rgb(hue, 1.0, 1.0)
-            }
-
-            7 => {
-                // Cascade Breathing - pulses from left to right
-                let phase = time + pos_offset * 0.5;
-                let brightness = (phase.sin() * 0.3 + 0.7).max(0.4).min(1.0);
-                let val = (255.0 * brightness) as u8;
-                Color::rgba(val, val / 4, val / 4, 255)
-            }
-
-            8 => {
-                // Matrix Rain Effect - segments cascade downward
-                let cascade_time = time * 2.0 + digit_pos as f32 * 0.3 + segment as f32 * 0.1;
-                let intensity = ((cascade_time % 3.0) - 1.5).abs() / 1.5;
-                let green = (64.0 + 191.0 * intensity) as u8;
-                let blue = (255.0 * (1.0 - intensity * 0.7)) as u8;
-                Color::rgba(0, green, blue, 255)
-            }
-
-            9 => {
-                // Fire Effect - flickering per segment
-                let flicker = (time * 10.0 + digit_pos as f32 * 3.7 + segment as f32 * 5.3).sin();
-                let random = ((digit_pos as f32 * 7.3 + segment as f32 * 13.7).sin() * 43758.5453).fract();
-                let intensity = (0.7 + flicker * 0.2 + random * 0.1).max(0.5).min(1.0);
-
-                let r = (255.0 * intensity) as u8;
-                let g = (191.0 * intensity * 0.7) as u8;
-                let b = (64.0 * intensity * 0.2) as u8;
-                Color::rgba(r, g, b, 255)
-            }
+    /// Current palette color, crossfaded from the previous mode right after a change
+    /// `slot` picks the HHMMSS digit whose change pulse applies, if any
+    fn segment_color(&self, time: f32, digit_pos: u8, total_digits: u8, segment: u8, slot: Option<usize>) -> Color {
+        let mut color = self.get_color_for_position(self.color_mode, time, digit_pos, total_digits, segment, slot);
+        if let Some(previous) = self.previous_mode {
+            let from = self.get_color_for_position(previous, time, digit_pos, total_digits, segment, slot);
+            let t = self.mode_fade.eased_progress();
+            color = Color::lerp(from, color, t);
+        }
+        // Celebrations take over with a fast, bright rainbow that eases in and out
+        if let Some(progress) = self.celebration {
+            let hue = (time * 1.5 + digit_pos as f32 / total_digits as f32 * 0.5 + segment as f32 * 0.05) % 1.0;
+            let strength = (progress * 8.0).min((1.0 - progress) * 8.0).clamp(0.0, 1.0);
+            color = Color::lerp(color, hsv_to_rgb(hue, 0.8, 1.0), strength);
+        }
+        let night = self.night_dim.as_ref().map_or(1.0, NightDim::factor);
+        let brightness = self.brightness * self.user_brightness * night;
+        let color = Color::new(color.r * brightness, color.g * brightness, color.b * brightness, color.a);
+        Color::lerp(color, Color::rgba(255, 255, 255, 255).with_alpha(color.a), self.pulse_strength() * 0.8)
+    }
 
-            10 => {
-                // Electric Storm - random segment flashes with propagation
-                let storm_phase = time * 3.0 + pos_offset * 2.0;
-                let flash = ((storm_phase * 7.3 + segment as f32 * 11.1).sin() * 137.5).fract();
-                let flash_intensity = if flash > 0.8 { 1.0 } else { 0.6 };
+    fn get_color_for_position(&self, mode: ColorMode, time: f32, digit_pos: u8, total_digits: u8, segment: u8, slot: Option<usize>) -> Color {
+        // The classic mode follows the theme accent
+        let color = if mode == ColorMode::ClassicRed {
+            self.accent
+        } else {
+            mode.color(time, digit_pos, total_digits, segment)
+        };
 
-                let base_color = if flash > 0.8 {
-                    Color::rgba(255, 255, 255, 255) // White flash
-                } else {
-                    // Electric blue base with variation
-                    let variation = (time * 0.5 + pos_offset).sin() * 0.2 + 0.8;
-                    Color::rgba(
-                        (100.0 * variation) as u8,
-                        (150.0 * variation) as u8,
-                        (255.0 * flash_intensity) as u8,
-                        255
-                    )
-                };
-                base_color
-            }
+        // A digit that just changed starts bright and fades back, and with
+        // pulse_on_tick the whole readout does the same every second
+        let mut boost = 1.0;
+        if let Some(pulse) = slot.and_then(|slot| self.digit_pulses[slot].as_ref()) {
+            boost *= 1.0 + self.tick_pulse * (1.0 - pulse.eased_progress());
+        }
+        if self.pulse_on_tick {
+            boost *= 1.0 + TICK_FLASH_BOOST * (1.0 - self.tick_timeline.eased_progress());
+        }
+        if boost == 1.0 {
+            return color;
+        }
+        Color::new((color.r * boost).min(1.0), (color.g * boost).min(1.0), (color.b * boost).min(1.0), color.a)
+    }
 
-            _ => Color::rgba(255, 64, 64, 255), // Default to red
+    /// One slice of a segment. In OLED-safe mode only the outline is lit:
+    /// the outermost slices whole, the others just their two ends.
+    fn render_slice(&self, draw: &mut DrawContext, rect: Rect, shade: Shade, index: usize, count: usize, horizontal: bool) {
+        let (top, bottom) = (shade.at(rect.y), shade.at(rect.y + rect.height));
+        if !self.oled_safe || index == 0 || index + 1 == count {
+            draw.segment_slice(rect, [top, bottom], index, count, horizontal);
+            return;
+        }
+        let edge = OUTLINE_WIDTH;
+        if horizontal {
+            draw.rect_gradient(rect.x, rect.y, edge, rect.height, top, bottom);
+            draw.rect_gradient(rect.x + rect.width - edge, rect.y, edge, rect.height, top, bottom);
+        } else {
+            draw.rect(rect.x, rect.y, rect.width, edge, top);
+            draw.rect(rect.x, rect.y + rect.height - edge, rect.width, edge, bottom);
         }
     }
 
-    fn render_horizontal_segment(&self, draw: &mut DrawContext, x: f32, y: f32, width: f32, thickness: f32, bevel: f32, color: Color) {
+    fn render_horizontal_segment(&self, draw: &mut DrawContext, x: f32, y: f32, width: f32, thickness: f32, bevel: f32, shade: Shade) {
         let steps = 20;
         for i in 0..steps {
             let t = i as f32 / (steps - 1) as f32;
@@ -271,14 +784,14 @@ impl Clock {
             let x_inset = distance_from_center * bevel;
             let slice_x = x + x_inset;
             let slice_width = width - (2.0 * x_inset);
-            let slice_height = thickness / steps as f32 + 0.5;
+            let slice_height = thickness / (steps - 1) as f32 + SLICE_OVERLAP;
             if slice_width > 0.0 {
-                draw.rect(slice_x, y_pos, slice_width, slice_height, color);
+                self.render_slice(draw, Rect::new(slice_x, y_pos, slice_width, slice_height), shade, i, steps, true);
             }
         }
     }
 
-    fn render_vertical_segment(&self, draw: &mut DrawContext, x: f32, y: f32, height: f32, thickness: f32, bevel: f32, color: Color, is_bottom: bool) {
+    fn render_vertical_segment(&self, draw: &mut DrawContext, x: f32, y: f32, height: f32, thickness: f32, bevel: f32, shade: Shade, is_bottom: bool) {
         let steps = 20;
         for i in 0..steps {
             let t = i as f32 / (steps - 1) as f32;
@@ -288,14 +801,14 @@ impl Clock {
             let y_inset_bottom = if is_bottom { distance_from_center * bevel } else { 0.0 };
             let slice_y = y + y_inset_top;
             let slice_height = height - y_inset_top - y_inset_bottom;
-            let slice_width = thickness / steps as f32 + 0.5;
+            let slice_width = thickness / (steps - 1) as f32 + SLICE_OVERLAP;
             if slice_height > 0.0 {
-                draw.rect(x_pos, slice_y, slice_width, slice_height, color);
+                self.render_slice(draw, Rect::new(x_pos, slice_y, slice_width, slice_height), shade, i, steps, false);
             }
         }
     }
 
-    fn render_middle_segment(&self, draw: &mut DrawContext, x: f32, y: f32, width: f32, thickness: f32, bevel: f32, color: Color) {
+    fn render_middle_segment(&self, draw: &mut DrawContext, x: f32, y: f32, width: f32, thickness: f32, bevel: f32, shade: Shade) {
         let steps = 20;
         for i in 0..steps {
             let t = i as f32 / (steps - 1) as f32;
@@ -304,38 +817,332 @@ impl Clock {
             let x_inset = distance_from_center * bevel * 1.2;
             let slice_x = x + x_inset;
             let slice_width = width - (2.0 * x_inset);
-            let slice_height = thickness / steps as f32 + 0.5;
+            let slice_height = thickness / (steps - 1) as f32 + SLICE_OVERLAP;
             if slice_width > 0.0 {
-                draw.rect(slice_x, y_pos, slice_width, slice_height, color);
+                self.render_slice(draw, Rect::new(slice_x, y_pos, slice_width, slice_height), shade, i, steps, true);
             }
         }
     }
+}
 
-    fn hsv_to_rgb(&self, h: f32, s: f32, v: f32) -> Color {
-        let h = h * 360.0;
-        let c = v * s;
-        let x = c * (1.0 - ((h / 60.0) % 2.0 - 1.0).abs());
-        let m = v - c;
+impl Feature for Clock {
+    fn name(&self) -> &'static str {
+        if self.is_world_clock() { "world_clock" } else { "clock" }
+    }
 
-        let (r, g, b) = if h < 60.0 {
-            (c, x, 0.0)
-        } else if h < 120.0 {
-            (x, c, 0.0)
-        } else if h < 180.0 {
-            (0.0, c, x)
-        } else if h < 240.0 {
-            (0.0, x, c)
-        } else if h < 300.0 {
-            (x, 0.0, c)
-        } else {
-            (c, 0.0, x)
+    /// What `App::clock_size` reaches fully expanded, when the seconds
+    /// digits always show. World clocks follow the main clock's width
+    fn desired_expanded_size(&self) -> (u32, u32) {
+        let spec = LayoutSpec::new(&self.layout, true);
+        (clock_width(&self.style, &spec, self.expanded_height), self.expanded_height)
+    }
+
+    fn update(&mut self, _dt: f32, now: f32) {
+        self.tick(now);
+
+        // Night is judged by local time, world clocks included
+        if let Some(dim) = &mut self.night_dim {
+            let local = self.fixed_time.or_else(|| OffsetDateTime::now_local().ok());
+            if let Some(local) = local {
+                dim.update(local.hour() as u16 * 60 + local.minute() as u16, now);
+            }
+        }
+    }
+
+    fn handle_event(&mut self, _event: UiEvent) -> bool {
+        false
+    }
+
+    fn window(&self) -> Option<WindowId> {
+        Some(if self.is_world_clock() { WindowId::WorldClock } else { WindowId::Clock })
+    }
+
+    fn render(&self, draw: &mut DrawContext, viewport: Rect) {
+        match self.face {
+            ClockFace::Digital => self.render_clock(draw, viewport, self.seconds_style, self.time),
+            ClockFace::Analog => self.render_analog(draw, viewport, self.seconds_style, self.time),
+        }
+    }
+}
+
+/// Fades the digits toward `factor` between `start` and `end`, in minutes
+/// past local midnight
+struct NightDim {
+    start: u16,
+    end: u16,
+    factor: f32,
+    /// Unknown until the first update, which applies without a fade
+    night: Option<bool>,
+    /// Multiplier the current fade started from
+    from: f32,
+    fade: Timeline,
+}
+
+impl NightDim {
+    /// None when disabled or the times don't parse
+    fn new(config: &NightDimConfig) -> Option<Self> {
+        if !config.enabled {
+            return None;
+        }
+        let minutes = |time: &str| parse_hh_mm(time).map(|(h, m)| h as u16 * 60 + m as u16);
+        let (Some(start), Some(end)) = (minutes(&config.start), minutes(&config.end)) else {
+            warn!("Ignoring clock.night_dim, start and end need to be HH:MM");
+            return None;
         };
+        Some(Self {
+            start,
+            end,
+            factor: config.dim_factor,
+            night: None,
+            from: 1.0,
+            fade: Timeline::new(NIGHT_FADE_SECONDS),
+        })
+    }
+
+    fn is_night(&self, minute: u16) -> bool {
+        if self.start <= self.end {
+            (self.start..self.end).contains(&minute)
+        } else {
+            minute >= self.start || minute < self.end
+        }
+    }
+
+    fn update(&mut self, minute: u16, now: f32) {
+        let night = self.is_night(minute);
+        match self.night {
+            None => {
+                self.night = Some(night);
+                self.fade.start(now - NIGHT_FADE_SECONDS);
+            }
+            Some(was) if was != night => {
+                self.from = self.factor();
+                self.night = Some(night);
+                self.fade.start(now);
+            }
+            _ => {}
+        }
+        self.fade.update(now);
+    }
+
+    fn factor(&self) -> f32 {
+        let target = if self.night == Some(true) { self.factor } else { 1.0 };
+        lerp(self.from, target, self.fade.eased_progress())
+    }
+}
 
-        Color::rgba(
-            ((r + m) * 255.0) as u8,
-            ((g + m) * 255.0) as u8,
-            ((b + m) * 255.0) as u8,
-            255,
-        )
+/// A segment fill running from `top` at `y` to `bottom` at `y + height`
+#[derive(Clone, Copy)]
+struct Shade {
+    top: Color,
+    bottom: Color,
+    y: f32,
+    height: f32,
+}
+
+impl Shade {
+    fn flat(color: Color) -> Self {
+        Self { top: color, bottom: color, y: 0.0, height: 1.0 }
+    }
+
+    fn at(&self, y: f32) -> Color {
+        Color::lerp(self.top, self.bottom, ((y - self.y) / self.height).clamp(0.0, 1.0))
     }
-}
\ No newline at end of file
+}
+
+/// Extra reach around a colon for clicks
+const COLON_HIT_SLACK: f32 = 3.0;
+
+/// Brightness at the foot of a digit with `gradient_segments`
+const GRADIENT_FOOT: f32 = 0.45;
+
+/// How long night dimming takes to fade in or out
+const NIGHT_FADE_SECONDS: f32 = 180.0;
+
+/// Length of the odometer roll when an HH:MM digit changes
+const ROLL_SECONDS: f32 = 0.35;
+
+/// Length and strength of the whole-readout flash with `pulse_on_tick`
+const TICK_FLASH_SECONDS: f32 = 0.2;
+const TICK_FLASH_BOOST: f32 = 0.3;
+
+/// Length of the brightening on a digit that just changed
+const TICK_PULSE_SECONDS: f32 = 0.25;
+
+/// Gap between the face and the window edge
+const OUTER_PADDING: f32 = 4.0;
+
+/// Face offsets cycled in OLED-safe mode, small enough to stay inside the padding
+const OLED_SHIFTS: [[f32; 2]; 8] = [
+    [0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0],
+    [-1.0, 1.0], [-1.0, 0.0], [-1.0, -1.0], [0.0, -1.0],
+];
+
+/// Stroke width of outlined digits
+const OUTLINE_WIDTH: f32 = 1.0;
+
+/// Where a digit sits: `pos` of `total` in the readout, which the color
+/// modes spread over, and its HHMMSS `slot` for roll and pulse animations.
+/// Date digits have no slot and just snap.
+#[derive(Debug, Clone, Copy)]
+struct DigitPos {
+    pos: u8,
+    total: u8,
+    slot: Option<usize>,
+}
+
+/// What fills one cell of the digital readout
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Glyph {
+    /// Digit `index` of a field, counted from the left
+    Digit(LayoutField, usize),
+    Colon,
+    Slash,
+    Blank,
+}
+
+/// The digital readout's fields in order, with the seconds dropped while
+/// they're hidden
+pub struct LayoutSpec {
+    fields: Vec<LayoutField>,
+}
+
+impl LayoutSpec {
+    pub fn new(fields: &[LayoutField], show_seconds: bool) -> Self {
+        let fields = fields.iter().copied()
+            .filter(|&field| show_seconds || field != LayoutField::Seconds)
+            .collect();
+        Self { fields }
+    }
+
+    fn digits(field: LayoutField) -> usize {
+        if field == LayoutField::DayOfWeek { 1 } else { 2 }
+    }
+
+    fn digit_count(&self) -> usize {
+        self.fields.iter().map(|&field| Self::digits(field)).sum()
+    }
+
+    /// Colons between time fields, a slash between month and day, a blank
+    /// anywhere else
+    fn separator(before: LayoutField, after: LayoutField) -> Glyph {
+        use LayoutField::*;
+        match (before, after) {
+            (Hours | Minutes | Seconds, Hours | Minutes | Seconds) => Glyph::Colon,
+            (Month | Day, Month | Day) => Glyph::Slash,
+            _ => Glyph::Blank,
+        }
+    }
+
+    /// Every glyph left to right with its x offset from the start of the
+    /// readout and its width; separators are `colon_width` wide
+    fn place(&self, digit_width: f32, colon_width: f32, spacing: f32) -> Vec<(Glyph, f32, f32)> {
+        let mut glyphs = Vec::new();
+        for (i, &field) in self.fields.iter().enumerate() {
+            if i > 0 {
+                glyphs.push(Self::separator(self.fields[i - 1], field));
+            }
+            glyphs.extend((0..Self::digits(field)).map(|index| Glyph::Digit(field, index)));
+        }
+
+        let mut x = 0.0;
+        glyphs.into_iter().map(|glyph| {
+            let width = if matches!(glyph, Glyph::Digit(..)) { digit_width } else { colon_width };
+            let placed = (glyph, x, width);
+            x += width + spacing;
+            placed
+        }).collect()
+    }
+
+    /// Width of the whole glyph run
+    fn width(&self, digit_width: f32, colon_width: f32, spacing: f32) -> f32 {
+        self.place(digit_width, colon_width, spacing).last().map_or(0.0, |&(_, x, width)| x + width)
+    }
+}
+
+/// Geometry of the digital readout for a window `height` tall
+struct ClockLayout {
+    digit_width: f32,
+    digit_height: f32,
+    colon_width: f32,
+    /// Width of the glyph run inside the bezel
+    total_width: f32,
+    margin: f32,
+    face_w: f32,
+    face_h: f32,
+}
+
+impl ClockLayout {
+    fn new(style: &ClockStyle, height: f32, spec: &LayoutSpec) -> Self {
+        let spacing = style.spacing;
+
+        // Digit size follows the height only, so it doesn't jump when seconds toggle
+        let margin_h = style.margin_ratio * spacing;
+        let digit_height = (height - OUTER_PADDING * 2.0 - margin_h * 2.0).max(0.0);
+        let digit_width = digit_height * style.digit_aspect;
+        let colon_width = digit_width * style.colon_ratio;
+
+        let total_width = spec.width(digit_width, colon_width, spacing);
+
+        // Larger bezel margin around readout
+        let margin = (spacing * style.margin_ratio).max(4.0);
+
+        Self {
+            digit_width,
+            digit_height,
+            colon_width,
+            total_width,
+            margin,
+            face_w: total_width + margin * 2.0,
+            face_h: digit_height + margin * 2.0,
+        }
+    }
+}
+
+/// Logical window width that fits the clock face showing `spec` at `height`
+pub fn clock_width(style: &ClockStyle, spec: &LayoutSpec, height: u32) -> u32 {
+    let layout = ClockLayout::new(style, height as f32, spec);
+    (layout.face_w + OUTER_PADDING * 2.0).ceil() as u32
+}
+
+fn parse_accent(theme: &Theme) -> Color {
+    parse_theme_color("accent", &theme.accent, Color::rgba(255, 64, 64, 255))
+}
+
+fn parse_background(theme: &Theme) -> Color {
+    parse_theme_color("background", &theme.background, Color::rgba(0, 0, 0, 255))
+}
+
+fn parse_theme_color(field: &str, hex: &str, fallback: Color) -> Color {
+    Color::from_hex(hex).unwrap_or_else(|e| {
+        warn!("theme.{}: {}, using default", field, e);
+        fallback
+    })
+}
+
+fn hsv_to_rgb(h: f32, s: f32, v: f32) -> Color {
+    let h = h * 360.0;
+    let c = v * s;
+    let x = c * (1.0 - ((h / 60.0) % 2.0 - 1.0).abs());
+    let m = v - c;
+
+    let (r, g, b) = if h < 60.0 {
+        (c, x, 0.0)
+    } else if h < 120.0 {
+        (x, c, 0.0)
+    } else if h < 180.0 {
+        (0.0, c, x)
+    } else if h < 240.0 {
+        (0.0, x, c)
+    } else if h < 300.0 {
+        (x, 0.0, c)
+    } else {
+        (c, 0.0, x)
+    };
+
+    Color::rgba(
+        ((r + m) * 255.0) as u8,
+        ((g + m) * 255.0) as u8,
+        ((b + m) * 255.0) as u8,
+        255,
+    )
+}
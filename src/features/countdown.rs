@@ -0,0 +1,211 @@
+use super::{pomodoro::{parse_tint, render_pair, render_plasma}, Feature};
+use crate::app::UiEvent;
+use crate::config::{CompletionStyle, Config, EffectsConfig, Theme};
+use crate::gfx::{anim::{Easing, Timeline}, draw::DrawContext, math::{Color, Rect}};
+use crate::wayland::window_manager::WindowId;
+use log::{info, warn};
+use time::format_description::well_known::Rfc3339;
+use time::{OffsetDateTime, PrimitiveDateTime, UtcOffset};
+
+/// Logical size of the countdown window, HH:MM:SS at the timer's digit size
+const COUNTDOWN_WINDOW_SIZE: [u32; 2] = [112, 30];
+
+/// Two hour digits; a deadline further out holds at 99:59:59
+const MAX_DISPLAY_SECONDS: i64 = 100 * 3600 - 1;
+
+/// Counts down to a fixed wall-clock time in its own window, then plays the
+/// pomodoro's completion effect once it reaches zero
+pub struct Countdown {
+    target: OffsetDateTime,
+    /// Whole seconds left, rounded up so 00:00:00 only shows at the deadline
+    remaining: i64,
+    /// Set at the deadline, or from the start when it had already passed
+    finished: bool,
+    completion: Option<Timeline>,
+    completion_duration: f32,
+    completion_style: CompletionStyle,
+    tint: Color,
+    completion_effect: i32,
+    effect_speed: f32,
+    corner_radius: f32,
+    foreground: Color,
+    time: f32,
+}
+
+impl Countdown {
+    /// None without a `countdown.target`, or when it can't be parsed
+    pub fn new(config: &Config) -> Option<Self> {
+        let target = config.countdown.target.as_deref()?;
+        let Some(target) = parse_target(target) else {
+            warn!("Ignoring countdown.target '{}', expected RFC 3339 like 2024-12-31T23:59:59", target);
+            return None;
+        };
+        let now = now();
+        if target <= now {
+            info!("Countdown target {} has already passed", target);
+        }
+
+        let pomodoro = &config.pomodoro;
+        Some(Self {
+            target,
+            remaining: seconds_until(target, now),
+            finished: target <= now,
+            completion: None,
+            completion_duration: pomodoro.celebration_seconds.max(0.0),
+            completion_style: pomodoro.completion_style,
+            tint: parse_tint("work_completion_color", &pomodoro.work_completion_color),
+            completion_effect: config.effects.completion_mode,
+            effect_speed: config.effects.speed,
+            corner_radius: pomodoro.corner_radius,
+            foreground: config.theme.foreground_color(),
+            time: 0.0,
+        })
+    }
+
+    pub fn set_effects(&mut self, effects: &EffectsConfig) {
+        self.completion_effect = effects.completion_mode;
+        self.effect_speed = effects.speed;
+    }
+
+    pub fn set_theme(&mut self, theme: &Theme) {
+        self.foreground = theme.foreground_color();
+    }
+
+    /// Progress through a clock-pulse celebration, if one is running
+    pub fn clock_pulse_progress(&self) -> Option<f32> {
+        match &self.completion {
+            Some(tl) if self.completion_style == CompletionStyle::ClockPulse => Some(tl.progress()),
+            _ => None,
+        }
+    }
+
+    fn finish(&mut self, now: f32) {
+        self.finished = true;
+        info!("Countdown to {} complete", self.target);
+        if self.completion_duration > 0.0 && self.completion_style != CompletionStyle::None {
+            let mut tl = Timeline::with_easing(self.completion_duration, Easing::Linear);
+            tl.start(now);
+            self.completion = Some(tl);
+        }
+    }
+
+    fn render_display(&self, draw: &mut DrawContext, viewport: Rect) {
+        let outer_padding = 3.0;
+        let margin = 2.0;
+        let spacing = 2.0;
+
+        let face_w = viewport.width - outer_padding * 2.0;
+        let face_h = viewport.height - outer_padding * 2.0;
+        draw.round_rect(outer_padding, outer_padding, face_w, face_h, self.corner_radius, Color::rgba(0, 0, 0, 255));
+
+        let digit_height = face_h - margin * 2.0;
+        let digit_width = digit_height * 0.62;
+        let colon_width = digit_width * 0.28;
+        let dot = digit_width * 0.11;
+        let total_width = digit_width * 6.0 + colon_width * 2.0 + spacing * 7.0;
+        let mut x = outer_padding + ((face_w - total_width) * 0.5).max(margin);
+        let y = outer_padding + margin;
+
+        // Finished countdowns stay on 00:00:00, dimmed
+        let color = if self.finished { self.foreground.with_alpha(self.foreground.a * 0.5) } else { self.foreground };
+        let total = self.remaining.clamp(0, MAX_DISPLAY_SECONDS);
+        let groups = [total / 3600, total / 60 % 60, total % 60];
+        for (i, value) in groups.into_iter().enumerate() {
+            if i > 0 {
+                draw.rect(x, y + digit_height * 0.3, dot, dot, color);
+                draw.rect(x, y + digit_height * 0.62, dot, dot, color);
+                x += colon_width + spacing;
+            }
+            render_pair(draw, Some(value as u8), Rect::new(x, y, digit_width, digit_height), spacing, color);
+            x += (digit_width + spacing) * 2.0;
+        }
+    }
+}
+
+impl Feature for Countdown {
+    fn name(&self) -> &'static str {
+        "countdown"
+    }
+
+    fn desired_expanded_size(&self) -> (u32, u32) {
+        (COUNTDOWN_WINDOW_SIZE[0], COUNTDOWN_WINDOW_SIZE[1])
+    }
+
+    fn update(&mut self, _dt: f32, now: f32) {
+        self.time = now;
+        self.remaining = seconds_until(self.target, self::now());
+        if self.remaining == 0 && !self.finished {
+            self.finish(now);
+        }
+        if let Some(tl) = &mut self.completion {
+            tl.update(now);
+            if tl.is_complete() {
+                self.completion = None;
+            }
+        }
+    }
+
+    fn handle_event(&mut self, _event: UiEvent) -> bool {
+        false
+    }
+
+    /// The plasma overlay takes over while a fullscreen celebration plays
+    fn window(&self) -> Option<WindowId> {
+        match self.completion {
+            Some(_) if self.completion_style == CompletionStyle::Fullscreen => Some(WindowId::Plasma),
+            _ => Some(WindowId::Countdown),
+        }
+    }
+
+    fn render(&self, draw: &mut DrawContext, viewport: Rect) {
+        match &self.completion {
+            Some(tl) if self.completion_style == CompletionStyle::Fullscreen => {
+                render_plasma(draw, viewport, tl.progress(), self.tint, self.completion_effect, self.time, self.effect_speed);
+            }
+            _ => self.render_display(draw, viewport),
+        }
+    }
+}
+
+fn now() -> OffsetDateTime {
+    OffsetDateTime::now_local().unwrap_or_else(|_| OffsetDateTime::now_utc())
+}
+
+/// Whole seconds from `now` until `target`, rounded up; 0 once it's passed
+fn seconds_until(target: OffsetDateTime, now: OffsetDateTime) -> i64 {
+    ((target - now).as_seconds_f64().ceil() as i64).max(0)
+}
+
+/// An RFC 3339 timestamp, or one without an offset taken as local time
+pub fn parse_target(target: &str) -> Option<OffsetDateTime> {
+    let target = target.trim();
+    if let Ok(time) = OffsetDateTime::parse(target, &Rfc3339) {
+        return Some(time);
+    }
+    let format = time::format_description::parse_borrowed::<2>("[year]-[month]-[day]T[hour]:[minute]:[second]").ok()?;
+    let local = PrimitiveDateTime::parse(target, &format).ok()?;
+    let offset = UtcOffset::local_offset_at(local.assume_utc()).unwrap_or(UtcOffset::UTC);
+    Some(local.assume_offset(offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_rfc3339_and_offsetless_targets() {
+        let utc = parse_target("2024-12-31T23:59:59Z").unwrap();
+        assert_eq!(utc.unix_timestamp(), 1_735_689_599);
+        let local = parse_target("2024-12-31T23:59:59").unwrap();
+        assert_eq!((local.hour(), local.minute(), local.second()), (23, 59, 59));
+        assert!(parse_target("tomorrow").is_none());
+    }
+
+    #[test]
+    fn remaining_rounds_up_and_stops_at_zero() {
+        let target = parse_target("2024-12-31T23:59:59Z").unwrap();
+        assert_eq!(seconds_until(target, target - time::Duration::milliseconds(1500)), 2);
+        assert_eq!(seconds_until(target, target), 0);
+        assert_eq!(seconds_until(target, target + time::Duration::HOUR), 0);
+    }
+}
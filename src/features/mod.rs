@@ -1,13 +1,27 @@
+pub mod alarm;
+pub mod battery;
 pub mod clock;
+pub mod countdown;
 pub mod pomodoro;
+pub mod seg;
+pub mod sysmon;
 
 use crate::app::UiEvent;
 use crate::gfx::{draw::DrawContext, math::Rect};
+use crate::wayland::window_manager::WindowId;
+use std::any::Any;
 
-pub trait Feature {
+/// A self-contained piece of UI. Everything `render` needs (time, colors,
+/// modes) is captured during `update` or through the feature's own setters.
+pub trait Feature: Any {
     fn name(&self) -> &'static str;
     fn desired_expanded_size(&self) -> (u32, u32);
+    /// `now` is `App::time`, monotonic seconds since startup, for timelines
+    /// and animation; read the wall clock for anything tied to real seconds
     fn update(&mut self, dt: f32, now: f32);
+    /// Returns true if the event was consumed
     fn handle_event(&mut self, event: UiEvent) -> bool;
+    /// Window this feature currently draws into, if any
+    fn window(&self) -> Option<WindowId>;
     fn render(&self, draw: &mut DrawContext, viewport: Rect);
-}
\ No newline at end of file
+}
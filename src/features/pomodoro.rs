@@ -1,176 +1,539 @@
+use super::{seg::SEGMENT_MAP, Feature};
 use crate::app::UiEvent;
-use crate::gfx::{anim::{Timeline, lerp}, draw::DrawContext, math::{Color, Rect, Vec2}};
-use log::info;
-
-// Reuse clock's segment map
-const SEGMENT_MAP: [[bool; 7]; 10] = [
-    [true, true, true, true, true, true, false],     // 0
-    [false, true, true, false, false, false, false], // 1
-    [true, true, false, true, true, false, true],    // 2
-    [true, true, true, true, false, false, true],    // 3
-    [false, true, true, false, false, true, true],   // 4
-    [true, false, true, true, false, true, true],    // 5
-    [true, false, true, true, true, true, true],     // 6
-    [true, true, true, false, false, false, false],  // 7
-    [true, true, true, true, true, true, true],      // 8
-    [true, true, true, true, false, true, true],     // 9
-];
+use crate::config::{CompletionStyle, EffectsConfig, PomodoroConfig};
+use crate::gfx::{anim::{Easing, Timeline, lerp}, draw::{DrawContext, SLICE_OVERLAP}, font, math::{Color, Rect, Vec2}};
+use crate::stats::{self, Stats};
+use crate::wayland::{window_manager::WindowId, ActiveSurface};
+use log::{info, warn};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Phase {
+    Work,
+    ShortBreak,
+    LongBreak,
+}
 
 #[derive(Debug, Clone)]
 pub enum PomodoroMode {
     Idle,
     Reveal { start: f32, tl: Timeline },
     Counting { start: f32 },
+    Paused { remaining: f32 },
     Completion { start: f32, tl: Timeline },
+    /// Counting up from `start` until stopped; clicks record laps
+    Stopwatch { start: f32 },
 }
 
+/// How long a keyboard duration change keeps the idle timer on screen
+const DURATION_FLASH_SECONDS: f32 = 1.0;
+
+/// Logical size of the separate timer window
+pub const TIMER_WINDOW_SIZE: [u32; 2] = [104, 30];
+
+/// Extra timer window height for the task label's line of text
+const TASK_LABEL_HEIGHT: u32 = 10;
+
+/// Extra timer window width for an `HH:` group at the timer's digit size
+const HOURS_GROUP_WIDTH: u32 = 42;
+
+/// How long a lap's split stays frozen on the stopwatch display
+const LAP_HOLD_SECONDS: f32 = 2.0;
+
+/// Time added or taken off a running countdown per scroll step
+pub const EXTEND_STEP_SECONDS: f32 = 300.0;
+
+/// Longest a phase can be stretched to with `extend`
+const MAX_PHASE_SECONDS: f32 = 4.0 * 3600.0;
+
+/// Shortening a countdown leaves at least this much on it
+const MIN_REMAINING_SECONDS: f32 = 60.0;
+
 pub struct Pomodoro {
     pub mode: PomodoroMode,
+    /// Selectable work durations in seconds, cycled with the scroll wheel
+    durations: Vec<f32>,
     duration: f32,
+    /// Seconds `extend` added to (or took off) the current phase
+    extension: f32,
+    /// Seconds left, or elapsed for the stopwatch
     remaining: f32,
+    hour_digits: [u8; 2],
     minute_digits: [u8; 2],
     second_digits: [u8; 2],
     flip_tl: Timeline,
     last_sec: i32,
     duration_index: usize,
+    pub phase: Phase,
+    pub completed_work_count: u32,
+    short_break: f32,
+    long_break: f32,
+    long_break_interval: u32,
+    stats: Stats,
+    /// Length of the completion celebration in seconds; 0 skips it
+    completion_duration: f32,
+    completion_style: CompletionStyle,
+    /// Plasma tints for a finished work interval and a finished break
+    work_tint: Color,
+    break_tint: Color,
+    /// Shader effects behind the reveal and the completion, and how fast
+    /// they animate
+    reveal_effect: i32,
+    completion_effect: i32,
+    effect_speed: f32,
+    /// Shows the idle timer for a moment after the duration changes
+    duration_flash: Option<Timeline>,
+    /// Stopwatch lap splits, in seconds since the stopwatch started
+    laps: Vec<f32>,
+    /// Latest split, shown instead of the running time until the timeline ends
+    lap_hold: Option<(f32, Timeline)>,
+    /// Label for the focus session, shown under the digits
+    task: Option<String>,
+    corner_radius: f32,
+    time: f32,
 }
 
 impl Pomodoro {
-    const DURATIONS: [f32; 6] = [
-        30.0 * 60.0,  // 30 minutes
-        25.0 * 60.0,  // 25 minutes
-        20.0 * 60.0,  // 20 minutes
-        15.0 * 60.0,  // 15 minutes
-        10.0 * 60.0,  // 10 minutes
-        5.0 * 60.0,   // 5 minutes
-    ];
-
-    pub fn new() -> Self {
-        Self {
+    /// `stats` are the completed pomodoros so far, see `today_count`
+    pub fn new(config: &PomodoroConfig, stats: Stats) -> Self {
+        let mut pomodoro = Self {
             mode: PomodoroMode::Idle,
+            durations: Vec::new(),
             duration_index: 0,
-            duration: Self::DURATIONS[0],
-            remaining: Self::DURATIONS[0],
+            duration: 0.0,
+            extension: 0.0,
+            remaining: 0.0,
+            hour_digits: [0, 0],
             minute_digits: [0, 0],
             second_digits: [0, 0],
             flip_tl: Timeline::new(0.12),
             last_sec: -1,
+            phase: Phase::Work,
+            completed_work_count: 0,
+            short_break: config.short_break_minutes as f32 * 60.0,
+            long_break: config.long_break_minutes as f32 * 60.0,
+            long_break_interval: config.long_break_interval.max(1),
+            stats,
+            completion_duration: config.celebration_seconds.max(0.0),
+            completion_style: config.completion_style,
+            work_tint: parse_tint("work_completion_color", &config.work_completion_color),
+            break_tint: parse_tint("break_completion_color", &config.break_completion_color),
+            reveal_effect: 1,
+            completion_effect: 2,
+            effect_speed: 1.0,
+            duration_flash: None,
+            laps: Vec::new(),
+            lap_hold: None,
+            task: None,
+            corner_radius: config.corner_radius,
+            time: 0.0,
+        };
+        pomodoro.set_task(config.task.as_deref());
+        pomodoro.set_durations(&config.durations);
+        if let Some(minutes) = config.default_minutes {
+            pomodoro.select_minutes(minutes);
+        }
+        pomodoro
+    }
+
+    /// Name the focus session; None or a blank name removes the label
+    pub fn set_task(&mut self, task: Option<&str>) {
+        self.task = task.map(str::trim).filter(|task| !task.is_empty()).map(str::to_string);
+    }
+
+    pub fn set_effects(&mut self, effects: &EffectsConfig) {
+        self.reveal_effect = effects.reveal_mode;
+        self.completion_effect = effects.completion_mode;
+        self.effect_speed = effects.speed;
+    }
+
+    /// Replace the selectable durations (in minutes), keeping the current
+    /// selection where possible. An empty list is ignored.
+    pub fn set_durations(&mut self, minutes: &[u32]) {
+        if minutes.is_empty() {
+            warn!("Ignoring empty pomodoro duration list");
+            return;
+        }
+
+        self.durations = minutes.iter().map(|&m| m as f32 * 60.0).collect();
+        self.duration_index = self.duration_index.min(self.durations.len() - 1);
+        self.duration = self.durations[self.duration_index];
+        if matches!(self.mode, PomodoroMode::Idle) {
+            self.remaining = self.duration;
+        }
+    }
+
+    /// Select the listed duration closest to `minutes`, the earlier one in
+    /// the list on a tie. Only resets the remaining time when idle.
+    pub fn select_minutes(&mut self, minutes: u32) {
+        let wanted = minutes as f32 * 60.0;
+        let Some(index) = (0..self.durations.len())
+            .min_by(|&a, &b| (self.durations[a] - wanted).abs().total_cmp(&(self.durations[b] - wanted).abs()))
+        else {
+            return;
+        };
+        self.duration_index = index;
+        self.duration = self.durations[index];
+        if matches!(self.mode, PomodoroMode::Idle) {
+            self.remaining = self.duration;
         }
     }
 
     pub fn start(&mut self, now: f32) {
+        self.phase = Phase::Work;
+        self.start_phase(now);
+    }
+
+    fn start_phase(&mut self, now: f32) {
         self.mode = PomodoroMode::Counting { start: now };
-        self.remaining = self.duration;
+        self.duration_flash = None;
+        self.extension = 0.0;
+        self.remaining = self.phase_duration();
         self.last_sec = -1;
-        info!("Pomodoro started! Mode: {:?}, Duration: {}", self.mode, self.duration);
+        info!("Pomodoro {:?} started! Mode: {:?}, Duration: {}", self.phase, self.mode, self.remaining);
     }
 
     pub fn stop(&mut self) {
+        if let PomodoroMode::Stopwatch { start } = self.mode {
+            info!("Stopwatch stopped at {:.1}s after {} laps", self.time - start, self.laps.len());
+            self.lap_hold = None;
+        }
         info!("Stopping pomodoro timer");
         self.mode = PomodoroMode::Idle;
+        self.phase = Phase::Work;
+        self.extension = 0.0;
         self.remaining = self.duration;
         self.last_sec = -1;
     }
 
+    /// Freeze the countdown, keeping the remaining time
+    pub fn pause(&mut self) {
+        if matches!(self.mode, PomodoroMode::Counting { .. }) {
+            self.mode = PomodoroMode::Paused { remaining: self.remaining };
+            info!("Pomodoro paused with {:.0}s remaining", self.remaining);
+        }
+    }
+
+    /// Continue counting down from where `pause` left off
+    pub fn resume(&mut self, now: f32) {
+        if let PomodoroMode::Paused { remaining } = self.mode {
+            // Back-date the start so the countdown picks up at the frozen value
+            let start = now - (self.phase_duration() - remaining);
+            self.mode = PomodoroMode::Counting { start };
+            info!("Pomodoro resumed with {:.0}s remaining", remaining);
+        }
+    }
+
+    /// Start counting up from zero when idle, or stop a running stopwatch
+    pub fn toggle_stopwatch(&mut self, now: f32) {
+        match self.mode {
+            PomodoroMode::Idle => {
+                self.mode = PomodoroMode::Stopwatch { start: now };
+                self.duration_flash = None;
+                self.laps.clear();
+                self.lap_hold = None;
+                self.remaining = 0.0;
+                self.last_sec = -1;
+                info!("Stopwatch started");
+            }
+            PomodoroMode::Stopwatch { .. } => self.stop(),
+            _ => info!("Stopwatch unavailable while a pomodoro is running"),
+        }
+    }
+
+    /// Record a split and hold it on the display for a moment
+    pub fn lap(&mut self, now: f32) {
+        let PomodoroMode::Stopwatch { start } = self.mode else { return };
+        let split = now - start;
+        self.laps.push(split);
+        let mut hold = Timeline::with_easing(LAP_HOLD_SECONDS, Easing::Linear);
+        hold.start(now);
+        self.lap_hold = Some((split, hold));
+        info!("Lap {}: {:.1}s", self.laps.len(), split);
+    }
+
+    pub fn toggle_pause(&mut self, now: f32) {
+        match self.mode {
+            PomodoroMode::Counting { .. } => self.pause(),
+            PomodoroMode::Paused { .. } => self.resume(now),
+            _ => {}
+        }
+    }
+
+    /// Length of the current phase in seconds
+    pub fn phase_duration(&self) -> f32 {
+        let base = match self.phase {
+            Phase::Work => self.duration,
+            Phase::ShortBreak => self.short_break,
+            Phase::LongBreak => self.long_break,
+        };
+        base + self.extension
+    }
+
+    /// Add `secs` to the running countdown, or take them off when negative,
+    /// without touching the selected duration. The phase stays under
+    /// `MAX_PHASE_SECONDS` and a shortened one keeps a minute left.
+    pub fn extend(&mut self, secs: f32) {
+        if !matches!(self.mode, PomodoroMode::Counting { .. }) {
+            return;
+        }
+        let elapsed = self.phase_duration() - self.remaining;
+        let longest = (MAX_PHASE_SECONDS - elapsed).max(self.remaining);
+        let shortest = MIN_REMAINING_SECONDS.min(self.remaining);
+        let remaining = (self.remaining + secs).min(longest).max(shortest);
+
+        // tick counts down from the start, so growing the phase grows what's left
+        self.extension += remaining - self.remaining;
+        self.remaining = remaining;
+        info!("Pomodoro extended by {:.0}s, {:.0}s remaining", secs, remaining);
+    }
+
+    /// Display tint for the current phase: blue while working, greens on breaks
+    pub fn phase_color(&self) -> Color {
+        match self.phase {
+            Phase::Work => Color::rgba(64, 128, 255, 255),
+            Phase::ShortBreak => Color::rgba(64, 224, 96, 255),
+            Phase::LongBreak => Color::rgba(32, 224, 192, 255),
+        }
+    }
+
+    /// Number of work intervals completed today, across restarts
+    pub fn today_count(&self) -> u32 {
+        self.stats.count_for(stats::today())
+    }
+
+    fn record_completed_work(&mut self) {
+        self.stats.record_pomodoro(stats::today(), self.phase_duration() / 60.0);
+        if let Err(e) = self.stats.save() {
+            warn!("Failed to save pomodoro stats: {}", e);
+        }
+        info!("Completed pomodoros today: {}", self.today_count());
+    }
+
+    /// Called once the completion effect has played: a finished work interval
+    /// rolls straight into a break, a finished break waits for the next start
+    fn advance_phase(&mut self, now: f32) {
+        match self.phase {
+            Phase::Work => {
+                self.phase = if self.completed_work_count % self.long_break_interval == 0 {
+                    Phase::LongBreak
+                } else {
+                    Phase::ShortBreak
+                };
+                self.start_phase(now);
+            }
+            Phase::ShortBreak | Phase::LongBreak => {
+                self.phase = Phase::Work;
+                self.mode = PomodoroMode::Idle;
+                self.extension = 0.0;
+                self.remaining = self.duration;
+                self.last_sec = -1;
+            }
+        }
+    }
+
+    /// Step through `durations`, which are listed longest first: a positive
+    /// `delta` (scroll up) moves toward the front, i.e. more minutes
     pub fn cycle_duration(&mut self, delta: f32) {
         info!("cycle_duration called with delta: {}, current mode: {:?}", delta, self.mode);
         // Allow duration change when idle OR when counting (will update remaining time)
         // This way users can adjust duration even when timer is running
+        let old_duration = self.phase_duration();
 
         if delta > 0.0 {
-            // Scroll up - go to next duration
-            self.duration_index = (self.duration_index + 1) % Self::DURATIONS.len();
-        } else {
-            // Scroll down - go to previous duration
+            // Scroll up - go to the previous (longer) duration
             if self.duration_index == 0 {
-                self.duration_index = Self::DURATIONS.len() - 1;
+                self.duration_index = self.durations.len() - 1;
             } else {
                 self.duration_index -= 1;
             }
+        } else {
+            // Scroll down - go to the next (shorter) duration
+            self.duration_index = (self.duration_index + 1) % self.durations.len();
         }
 
-        self.duration = Self::DURATIONS[self.duration_index];
-        self.remaining = self.duration;
-
-        // Update remaining time if timer is running
-        if matches!(self.mode, PomodoroMode::Counting { .. }) {
-            // Keep the same proportion of time remaining
-            let proportion = self.remaining / Self::DURATIONS[self.duration_index];
-            self.remaining = self.duration * proportion;
+        self.duration = self.durations[self.duration_index];
+
+        if let PomodoroMode::Counting { .. } = self.mode {
+            // Keep the same proportion of the work interval remaining; breaks
+            // don't depend on the selected duration
+            if self.phase == Phase::Work && old_duration > 0.0 {
+                let proportion = self.remaining / old_duration;
+                self.remaining = self.phase_duration() * proportion;
+                // tick derives remaining from the start, so back-date it to match
+                let start = self.time - (self.phase_duration() - self.remaining);
+                self.mode = PomodoroMode::Counting { start };
+            }
+        } else {
+            self.remaining = self.duration;
         }
 
         let minutes = (self.duration / 60.0) as u32;
         info!("Changed timer duration to: {} minutes (index: {})", minutes, self.duration_index);
     }
 
+    /// Show the selected duration in the timer window for a moment, even
+    /// while idle
+    pub fn flash_duration(&mut self, now: f32) {
+        let mut flash = Timeline::with_easing(DURATION_FLASH_SECONDS, Easing::Linear);
+        flash.start(now);
+        self.duration_flash = Some(flash);
+    }
+
+    fn is_flashing_duration(&self) -> bool {
+        matches!(self.mode, PomodoroMode::Idle) && self.duration_flash.is_some()
+    }
+
     pub fn trigger_completion(&mut self, now: f32) {
         info!("trigger_completion called at time {}, current mode: {:?}", now, self.mode);
         if matches!(self.mode, PomodoroMode::Counting { .. }) {
-            self.mode = PomodoroMode::Completion {
-                start: now,
-                tl: Timeline::new(2.0),
-            };
-            self.remaining = 0.0;
+            self.finish_phase(now);
             info!("Pomodoro completion triggered manually! Mode is now: {:?}", self.mode);
         } else {
             info!("Cannot trigger completion - not in Counting mode");
         }
     }
 
-    pub fn update(&mut self, now: f32) {
+    /// Progress through a clock-pulse celebration, if one is running
+    pub fn clock_pulse_progress(&self) -> Option<f32> {
+        match &self.mode {
+            PomodoroMode::Completion { tl, .. } if self.completion_style == CompletionStyle::ClockPulse => Some(tl.progress()),
+            _ => None,
+        }
+    }
+
+    /// End the running phase: celebrate for `completion_duration`, or move
+    /// straight on when the celebration is disabled
+    fn finish_phase(&mut self, now: f32) {
+        self.remaining = 0.0;
+        if self.phase == Phase::Work {
+            self.completed_work_count += 1;
+            self.record_completed_work();
+        }
+        info!("Pomodoro {:?} complete! Work intervals done: {}", self.phase, self.completed_work_count);
+
+        if self.completion_duration > 0.0 && self.completion_style != CompletionStyle::None {
+            let mut tl = Timeline::with_easing(self.completion_duration, Easing::Linear);
+            tl.start(now);
+            self.mode = PomodoroMode::Completion { start: now, tl };
+        } else {
+            self.advance_phase(now);
+        }
+    }
+
+    fn tick(&mut self, now: f32) {
+        self.time = now;
         self.flip_tl.update(now);
+        if let Some(flash) = &mut self.duration_flash {
+            flash.update(now);
+            if flash.is_complete() {
+                self.duration_flash = None;
+            }
+        }
         match &mut self.mode {
             PomodoroMode::Idle => {}
             PomodoroMode::Reveal { .. } => {
                 // This should no longer be used, but keep for compatibility
             }
+            PomodoroMode::Paused { remaining } => {
+                self.remaining = *remaining;
+            }
             PomodoroMode::Counting { start } => {
-                self.remaining = (self.duration - (now - *start)).max(0.0);
+                let start = *start;
+                self.remaining = (self.phase_duration() - (now - start)).max(0.0);
                 let current_sec = self.remaining.floor() as i32;
                 if current_sec != self.last_sec {
                     self.last_sec = current_sec;
                     self.flip_tl.start(now);
                 }
                 if self.remaining <= 0.0 {
-                    let mut tl = Timeline::new(5.0);  // 5 seconds of awesome visualization
-                    tl.start(now);  // START the timeline!
-                    self.mode = PomodoroMode::Completion {
-                        start: now,
-                        tl,
-                    };
-                    info!("Pomodoro complete!");
+                    self.finish_phase(now);
                 }
             }
             PomodoroMode::Completion { tl, .. } => {
                 tl.update(now);
                 if tl.is_complete() {
-                    self.mode = PomodoroMode::Idle;
                     info!("Pomodoro completion animation finished");
+                    self.advance_phase(now);
                 }
             }
+            PomodoroMode::Stopwatch { start } => {
+                self.remaining = now - *start;
+                let current_sec = self.remaining.floor() as i32;
+                if current_sec != self.last_sec {
+                    self.last_sec = current_sec;
+                    self.flip_tl.start(now);
+                }
+            }
+        }
+        if let Some((_, hold)) = &mut self.lap_hold {
+            hold.update(now);
+            if hold.is_complete() {
+                self.lap_hold = None;
+            }
         }
         self.update_digits();
     }
 
+    /// Seconds shown on the timer: a held lap split, else `remaining`
+    fn displayed_seconds(&self) -> f32 {
+        self.lap_hold.as_ref().map_or(self.remaining, |(split, _)| *split)
+    }
+
+    /// Countdowns of an hour or more show HH:MM:SS from start to finish so
+    /// the window keeps one width; the stopwatch runs MM:SS up to 99:59
+    fn shows_hours(&self) -> bool {
+        match self.mode {
+            PomodoroMode::Stopwatch { .. } => self.displayed_seconds() >= 100.0 * 60.0,
+            _ => self.phase_duration() >= 60.0 * 60.0,
+        }
+    }
+
     fn update_digits(&mut self) {
-        let total_sec = self.remaining.floor() as u32;
-        let mins = total_sec / 60;
+        let total_sec = self.displayed_seconds().floor() as u32;
+        let (hours, mins) = if self.shows_hours() {
+            (total_sec / 3600, total_sec / 60 % 60)
+        } else {
+            (0, total_sec / 60)
+        };
         let secs = total_sec % 60;
+        self.hour_digits = [(hours / 10 % 10) as u8, (hours % 10) as u8];
         self.minute_digits = [(mins / 10) as u8, (mins % 10) as u8];
         self.second_digits = [(secs / 10) as u8, (secs % 10) as u8];
     }
 
-    pub fn render(&self, draw: &mut DrawContext, viewport: Rect, _time: f32) {
+    /// The timer display on its own, for a timer window fading out after
+    /// the pomodoro has already let it go
+    pub fn render_closing(&self, draw: &mut DrawContext, viewport: Rect) {
+        self.render_timer_display(draw, viewport, 1.0);
+    }
+
+    fn render_mode(&self, draw: &mut DrawContext, viewport: Rect) {
+        let time = self.time;
         match &self.mode {
-            PomodoroMode::Idle => return,
-            PomodoroMode::Completion { .. } => {
-                draw.set_effect_mode(2);
-                draw.rect(0.0, 0.0, viewport.width, viewport.height, Color::rgba(255, 255, 255, 255));
-                draw.set_effect_mode(0);
+            PomodoroMode::Idle => {
+                // Hold the flashed duration, then fade it out over the last third
+                if let Some(flash) = &self.duration_flash {
+                    let alpha = ((1.0 - flash.progress()) * 3.0).min(1.0);
+                    self.render_timer_display(draw, viewport, alpha);
+                }
+            }
+            PomodoroMode::Completion { tl, .. } => {
+                // The plasma shader fades in/out with the completion progress,
+                // tinted by the phase that just finished
+                let tint = if self.phase == Phase::Work { self.work_tint } else { self.break_tint };
+                render_plasma(draw, viewport, tl.progress(), tint, self.completion_effect, time, self.effect_speed);
             }
             PomodoroMode::Counting { .. } => {
                 // Show blue LCD timer display
-                self.render_timer_display(draw, viewport);
+                self.render_timer_display(draw, viewport, 1.0);
+            }
+            PomodoroMode::Stopwatch { .. } => {
+                self.render_timer_display(draw, viewport, 1.0);
+            }
+            PomodoroMode::Paused { .. } => {
+                // Slow 2s blink so the paused state is obvious at a glance
+                let blink = 0.3 + 0.7 * (0.5 + 0.5 * (time * std::f32::consts::PI).cos());
+                self.render_timer_display(draw, viewport, blink);
             }
             _ => {
                 let (reveal_progress, flip_progress) = match &self.mode {
@@ -180,9 +543,11 @@ impl Pomodoro {
                 };
 
                 // Reveal pattern background
-                draw.set_effect_mode(1);
+                draw.set_time(time * self.effect_speed);
+                draw.set_effect_mode(self.reveal_effect);
                 draw.rect(0.0, 0.0, viewport.width, viewport.height, Color::rgba(0, 0, 0, 255));
                 draw.set_effect_mode(0);
+                draw.set_time(time);
 
                 // Timer display (adapted from clock)
                 let outer_padding = 8.0;
@@ -210,15 +575,15 @@ impl Pomodoro {
                 let face_x = (viewport.width - face_w) / 2.0;
                 let face_y = (viewport.height - face_h) / 2.0;
 
-                draw.rect(face_x, face_y, face_w, face_h, Color::rgba(0, 0, 0, 255));
+                draw.round_rect(face_x, face_y, face_w, face_h, self.corner_radius, Color::rgba(0, 0, 0, 255));
 
                 let start_x = face_x + margin;
                 let start_y = face_y + margin;
                 let seg_color = Color::rgba(74, 158, 255, 255); // Accent
 
                 // Minutes
-                self.render_digit(draw, self.minute_digits[0], start_x, start_y, digit_width, digit_height, seg_color, 1.0);
-                self.render_digit(draw, self.minute_digits[1], start_x + digit_width + spacing, start_y, digit_width, digit_height, seg_color, 1.0);
+                render_digit(draw, self.minute_digits[0], start_x, start_y, digit_width, digit_height, seg_color, 1.0);
+                render_digit(draw, self.minute_digits[1], start_x + digit_width + spacing, start_y, digit_width, digit_height, seg_color, 1.0);
 
                 // Colon (always visible)
                 let colon_x = start_x + digit_width * 2.0 + spacing * 2.0;
@@ -229,122 +594,418 @@ impl Pomodoro {
                 // Seconds with flip
                 let sec_x = colon_x + colon_width + spacing;
                 let flip_scale = 1.0 - flip_progress * 0.2;
-                self.render_digit(draw, self.second_digits[0], sec_x, start_y, digit_width, digit_height * flip_scale, seg_color, 1.0);
-                self.render_digit(draw, self.second_digits[1], sec_x + digit_width + spacing, start_y, digit_width, digit_height * flip_scale, seg_color, 1.0);
+                render_digit(draw, self.second_digits[0], sec_x, start_y, digit_width, digit_height * flip_scale, seg_color, 1.0);
+                render_digit(draw, self.second_digits[1], sec_x + digit_width + spacing, start_y, digit_width, digit_height * flip_scale, seg_color, 1.0);
             }
         }
     }
 
-    fn render_timer_display(&self, draw: &mut DrawContext, viewport: Rect) {
+    fn render_timer_display(&self, draw: &mut DrawContext, viewport: Rect, alpha: f32) {
         // Blue LCD timer display in separate window
-        // Viewport is 80x30 for the timer window
+        // Viewport is TIMER_WINDOW_SIZE: a progress ring on the left, MM:SS on the right,
+        // and the task label along the bottom when there is one
         let outer_padding = 3.0;
+        let label_height = if self.task.is_some() { TASK_LABEL_HEIGHT as f32 } else { 0.0 };
+        let display_height = viewport.height - label_height;
 
         // Size to fit the small window
-        let digit_height = viewport.height - outer_padding * 2.0;
+        let digit_height = display_height - outer_padding * 2.0;
         let digit_width = digit_height * 0.62;
         let spacing = 2.0;
         let colon_width = digit_width * 0.28;
         let margin = 2.0;
 
-        // Blue color for timer
-        let seg_color = Color::rgba(64, 128, 255, 255);
+        // Tinted by phase: blue for work, green for breaks, amber for the stopwatch
+        let stopwatch = matches!(self.mode, PomodoroMode::Stopwatch { .. });
+        let seg_color = if stopwatch { Color::rgba(255, 176, 48, 255) } else { self.phase_color() };
 
-        let total_width = digit_width * 4.0 + spacing * 3.0 + colon_width;
-
-        // Center in the small viewport
         let face_w = viewport.width - outer_padding * 2.0;
-        let face_h = viewport.height - outer_padding * 2.0;
+        let face_h = display_height - outer_padding * 2.0;
         let face_x = outer_padding;
         let face_y = outer_padding;
 
-        // Background face (black)
-        draw.rect(face_x, face_y, face_w, face_h, Color::rgba(0, 0, 0, 255));
+        // Background face (black), running on under the label
+        draw.round_rect(face_x, face_y, face_w, face_h + label_height, self.corner_radius, Color::rgba(0, 0, 0, 255));
+
+        // Task label, centered, or clipped at the right edge once it's too long
+        if let Some(task) = &self.task {
+            let label = Rect::new(face_x + margin, face_y + face_h, face_w - margin * 2.0, label_height);
+            let width = font::text_width(task, 1.0);
+            let x = label.x + ((label.width - width) * 0.5).max(0.0);
+            let y = label.y + (label.height - font::GLYPH_HEIGHT as f32) * 0.5 - 1.0;
+            draw.push_clip(label);
+            draw.text(x, y, 1.0, task, seg_color.with_alpha(seg_color.a * alpha * 0.8));
+            draw.pop_clip();
+        }
 
-        let start_x = face_x + margin;
+        // Progress ring: a dim full track with the remaining fraction swept
+        // clockwise from 12 o'clock on top, emptying as the phase runs down
+        let ring_size = face_h;
+        let ring_center = Vec2::new(face_x + ring_size / 2.0, face_y + face_h / 2.0);
+        let ring_radius = ring_size / 2.0 - 3.0;
+        let ring_thickness = 2.5;
+        // The stopwatch ring sweeps once a minute instead
+        let progress = if stopwatch {
+            self.remaining % 60.0 / 60.0
+        } else {
+            (self.remaining / self.phase_duration()).clamp(0.0, 1.0)
+        };
+        let top = -std::f32::consts::FRAC_PI_2;
+        let track_color = seg_color.with_alpha(seg_color.a * alpha * 0.2);
+        let ring_color = seg_color.with_alpha(seg_color.a * alpha);
+        draw.arc(ring_center, ring_radius, ring_thickness, 0.0, std::f32::consts::TAU, track_color);
+        draw.arc(ring_center, ring_radius, ring_thickness, top, top + std::f32::consts::TAU * progress, ring_color);
+
+        let mut start_x = face_x + ring_size + margin;
         let start_y = face_y + margin;
+        let dot = digit_width * 0.11;
+        let colon_color = seg_color.with_alpha(seg_color.a * alpha);
+
+        // HH: ahead of MM:SS once the time needs it; the window widens to fit
+        if self.shows_hours() {
+            render_digit(draw, self.hour_digits[0], start_x, start_y, digit_width, digit_height, seg_color, alpha);
+            render_digit(draw, self.hour_digits[1], start_x + digit_width + spacing, start_y, digit_width, digit_height, seg_color, alpha);
+            let colon_x = start_x + digit_width * 2.0 + spacing * 2.0;
+            draw.rect(colon_x, start_y + digit_height * 0.3, dot, dot, colon_color);
+            draw.rect(colon_x, start_y + digit_height * 0.62, dot, dot, colon_color);
+            start_x = colon_x + colon_width + spacing;
+        }
 
         // Render MM:SS
-        self.render_digit(draw, self.minute_digits[0], start_x, start_y, digit_width, digit_height, seg_color, 1.0);
-        self.render_digit(draw, self.minute_digits[1], start_x + digit_width + spacing, start_y, digit_width, digit_height, seg_color, 1.0);
+        render_digit(draw, self.minute_digits[0], start_x, start_y, digit_width, digit_height, seg_color, alpha);
+        render_digit(draw, self.minute_digits[1], start_x + digit_width + spacing, start_y, digit_width, digit_height, seg_color, alpha);
 
         // Colon
         let colon_x = start_x + digit_width * 2.0 + spacing * 2.0;
-        let dot = digit_width * 0.11;
-        draw.rect(colon_x, start_y + digit_height * 0.3, dot, dot, seg_color);
-        draw.rect(colon_x, start_y + digit_height * 0.62, dot, dot, seg_color);
+        draw.rect(colon_x, start_y + digit_height * 0.3, dot, dot, colon_color);
+        draw.rect(colon_x, start_y + digit_height * 0.62, dot, dot, colon_color);
 
         // Seconds
         let second_x = colon_x + colon_width + spacing;
-        self.render_digit(draw, self.second_digits[0], second_x, start_y, digit_width, digit_height, seg_color, 1.0);
-        self.render_digit(draw, self.second_digits[1], second_x + digit_width + spacing, start_y, digit_width, digit_height, seg_color, 1.0);
-    }
-
-    fn render_digit(&self, draw: &mut DrawContext, digit: u8, x: f32, y: f32, width: f32, height: f32, color: Color, alpha: f32) {
-        if digit > 9 { return; }
-        let segments = SEGMENT_MAP[digit as usize];
-        let segment_width = width * 0.8;
-        let segment_thickness = width * 0.15;
-        let h_offset = width * 0.1;
-        let v_segment_height = height * 0.4;
-        let bevel = segment_thickness * 0.5;
-        let color = Color::new(color.r, color.g, color.b, color.a * alpha);
-
-        if segments[0] { self.render_horizontal_segment(draw, x + h_offset, y, segment_width, segment_thickness, bevel, color); }
-        if segments[1] { self.render_vertical_segment(draw, x + width - segment_thickness, y + segment_thickness, v_segment_height, segment_thickness, bevel, color, false); }
-        if segments[2] { self.render_vertical_segment(draw, x + width - segment_thickness, y + height * 0.5 + segment_thickness * 0.5, v_segment_height, segment_thickness, bevel, color, true); }
-        if segments[3] { self.render_horizontal_segment(draw, x + h_offset, y + height - segment_thickness, segment_width, segment_thickness, bevel, color); }
-        if segments[4] { self.render_vertical_segment(draw, x, y + height * 0.5 + segment_thickness * 0.5, v_segment_height, segment_thickness, bevel, color, true); }
-        if segments[5] { self.render_vertical_segment(draw, x, y + segment_thickness, v_segment_height, segment_thickness, bevel, color, false); }
-        if segments[6] { self.render_middle_segment(draw, x + h_offset, y + height * 0.5 - segment_thickness * 0.5, segment_width, segment_thickness, bevel, color); }
-    }
-
-    fn render_horizontal_segment(&self, draw: &mut DrawContext, x: f32, y: f32, width: f32, thickness: f32, bevel: f32, color: Color) {
-        let steps = 20;
-        for i in 0..steps {
-            let t = i as f32 / (steps - 1) as f32;
-            let y_pos = y + (t * thickness);
-            let distance_from_center = (t - 0.5).abs() * 2.0;
-            let x_inset = distance_from_center * bevel;
-            let slice_x = x + x_inset;
-            let slice_width = width - (2.0 * x_inset);
-            let slice_height = thickness / steps as f32 + 0.5;
-            if slice_width > 0.0 {
-                draw.rect(slice_x, y_pos, slice_width, slice_height, color);
+        render_digit(draw, self.second_digits[0], second_x, start_y, digit_width, digit_height, seg_color, alpha);
+        render_digit(draw, self.second_digits[1], second_x + digit_width + spacing, start_y, digit_width, digit_height, seg_color, alpha);
+    }
+}
+
+pub(super) fn parse_tint(field: &str, hex: &str) -> Color {
+    Color::from_hex(hex).unwrap_or_else(|e| {
+        warn!("pomodoro.{}: {}, leaving the plasma untinted", field, e);
+        Color::new(1.0, 1.0, 1.0, 1.0)
+    })
+}
+
+/// Completion effect `effect` filling `viewport`, faded in and out over
+/// `progress` and tinted; also played when a countdown reaches zero
+pub(super) fn render_plasma(draw: &mut DrawContext, viewport: Rect, progress: f32, tint: Color, effect: i32, time: f32, speed: f32) {
+    draw.set_progress(progress);
+    draw.set_tint(tint);
+    draw.set_time(time * speed);
+    draw.set_effect_mode(effect);
+    draw.rect(0.0, 0.0, viewport.width, viewport.height, Color::rgba(255, 255, 255, 255));
+    draw.set_effect_mode(0);
+    draw.set_time(time);
+    draw.set_tint(Color::new(1.0, 1.0, 1.0, 1.0));
+}
+
+/// Two flat digits the size of `cell`, `spacing` apart, for the battery,
+/// sysmon and countdown readouts. Capped at 99; `None` draws two dimmed
+/// dashes instead
+pub(super) fn render_pair(draw: &mut DrawContext, value: Option<u8>, cell: Rect, spacing: f32, color: Color) {
+    let Rect { x, y, width, height } = cell;
+    match value {
+        Some(value) => {
+            let value = value.min(99);
+            render_digit(draw, value / 10, x, y, width, height, color, 1.0);
+            render_digit(draw, value % 10, x + width + spacing, y, width, height, color, 1.0);
+        }
+        None => {
+            let dash_h = width * 0.15;
+            let dash_color = color.with_alpha(0.4);
+            for i in 0..2 {
+                let dash_x = x + i as f32 * (width + spacing);
+                draw.rect(dash_x + width * 0.1, y + (height - dash_h) * 0.5, width * 0.8, dash_h, dash_color);
             }
         }
     }
+}
 
-    fn render_vertical_segment(&self, draw: &mut DrawContext, x: f32, y: f32, height: f32, thickness: f32, bevel: f32, color: Color, is_bottom: bool) {
-        let steps = 20;
-        for i in 0..steps {
-            let t = i as f32 / (steps - 1) as f32;
-            let x_pos = x + (t * thickness);
-            let distance_from_center = (t - 0.5).abs() * 2.0;
-            let y_inset_top = if !is_bottom { distance_from_center * bevel } else { 0.0 };
-            let y_inset_bottom = if is_bottom { distance_from_center * bevel } else { 0.0 };
-            let slice_y = y + y_inset_top;
-            let slice_height = height - y_inset_top - y_inset_bottom;
-            let slice_width = thickness / steps as f32 + 0.5;
-            if slice_height > 0.0 {
-                draw.rect(x_pos, slice_y, slice_width, slice_height, color);
-            }
+/// Flat-colored seven-segment digit
+pub(super) fn render_digit(draw: &mut DrawContext, digit: u8, x: f32, y: f32, width: f32, height: f32, color: Color, alpha: f32) {
+    if digit > 9 { return; }
+    let segments = SEGMENT_MAP[digit as usize];
+    let segment_width = width * 0.8;
+    let segment_thickness = width * 0.15;
+    let h_offset = width * 0.1;
+    let v_segment_height = height * 0.4;
+    let bevel = segment_thickness * 0.5;
+    let color = color.with_alpha(color.a * alpha);
+
+    if segments[0] { render_horizontal_segment(draw, x + h_offset, y, segment_width, segment_thickness, bevel, color); }
+    if segments[1] { render_vertical_segment(draw, x + width - segment_thickness, y + segment_thickness, v_segment_height, segment_thickness, bevel, color, false); }
+    if segments[2] { render_vertical_segment(draw, x + width - segment_thickness, y + height * 0.5 + segment_thickness * 0.5, v_segment_height, segment_thickness, bevel, color, true); }
+    if segments[3] { render_horizontal_segment(draw, x + h_offset, y + height - segment_thickness, segment_width, segment_thickness, bevel, color); }
+    if segments[4] { render_vertical_segment(draw, x, y + height * 0.5 + segment_thickness * 0.5, v_segment_height, segment_thickness, bevel, color, true); }
+    if segments[5] { render_vertical_segment(draw, x, y + segment_thickness, v_segment_height, segment_thickness, bevel, color, false); }
+    if segments[6] { render_middle_segment(draw, x + h_offset, y + height * 0.5 - segment_thickness * 0.5, segment_width, segment_thickness, bevel, color); }
+}
+
+fn render_horizontal_segment(draw: &mut DrawContext, x: f32, y: f32, width: f32, thickness: f32, bevel: f32, color: Color) {
+    let steps = 20;
+    for i in 0..steps {
+        let t = i as f32 / (steps - 1) as f32;
+        let y_pos = y + (t * thickness);
+        let distance_from_center = (t - 0.5).abs() * 2.0;
+        let x_inset = distance_from_center * bevel;
+        let slice_x = x + x_inset;
+        let slice_width = width - (2.0 * x_inset);
+        let slice_height = thickness / (steps - 1) as f32 + SLICE_OVERLAP;
+        if slice_width > 0.0 {
+            draw.segment_slice(Rect::new(slice_x, y_pos, slice_width, slice_height), [color, color], i, steps, true);
         }
     }
+}
+
+fn render_vertical_segment(draw: &mut DrawContext, x: f32, y: f32, height: f32, thickness: f32, bevel: f32, color: Color, is_bottom: bool) {
+    let steps = 20;
+    for i in 0..steps {
+        let t = i as f32 / (steps - 1) as f32;
+        let x_pos = x + (t * thickness);
+        let distance_from_center = (t - 0.5).abs() * 2.0;
+        let y_inset_top = if !is_bottom { distance_from_center * bevel } else { 0.0 };
+        let y_inset_bottom = if is_bottom { distance_from_center * bevel } else { 0.0 };
+        let slice_y = y + y_inset_top;
+        let slice_height = height - y_inset_top - y_inset_bottom;
+        let slice_width = thickness / (steps - 1) as f32 + SLICE_OVERLAP;
+        if slice_height > 0.0 {
+            draw.segment_slice(Rect::new(x_pos, slice_y, slice_width, slice_height), [color, color], i, steps, false);
+        }
+    }
+}
 
-    fn render_middle_segment(&self, draw: &mut DrawContext, x: f32, y: f32, width: f32, thickness: f32, bevel: f32, color: Color) {
-        let steps = 20;
-        for i in 0..steps {
-            let t = i as f32 / (steps - 1) as f32;
-            let y_pos = y + (t * thickness);
-            let distance_from_center = (t - 0.5).abs() * 2.0;
-            let x_inset = distance_from_center * bevel * 1.2;
-            let slice_x = x + x_inset;
-            let slice_width = width - (2.0 * x_inset);
-            let slice_height = thickness / steps as f32 + 0.5;
-            if slice_width > 0.0 {
-                draw.rect(slice_x, y_pos, slice_width, slice_height, color);
+fn render_middle_segment(draw: &mut DrawContext, x: f32, y: f32, width: f32, thickness: f32, bevel: f32, color: Color) {
+    let steps = 20;
+    for i in 0..steps {
+        let t = i as f32 / (steps - 1) as f32;
+        let y_pos = y + (t * thickness);
+        let distance_from_center = (t - 0.5).abs() * 2.0;
+        let x_inset = distance_from_center * bevel * 1.2;
+        let slice_x = x + x_inset;
+        let slice_width = width - (2.0 * x_inset);
+        let slice_height = thickness / (steps - 1) as f32 + SLICE_OVERLAP;
+        if slice_width > 0.0 {
+            draw.segment_slice(Rect::new(slice_x, y_pos, slice_width, slice_height), [color, color], i, steps, true);
+        }
+    }
+}
+
+impl Feature for Pomodoro {
+    fn name(&self) -> &'static str {
+        "pomodoro"
+    }
+
+    fn desired_expanded_size(&self) -> (u32, u32) {
+        let extra = if self.shows_hours() { HOURS_GROUP_WIDTH } else { 0 };
+        let label = if self.task.is_some() { TASK_LABEL_HEIGHT } else { 0 };
+        (TIMER_WINDOW_SIZE[0] + extra, TIMER_WINDOW_SIZE[1] + label)
+    }
+
+    fn update(&mut self, _dt: f32, now: f32) {
+        self.tick(now);
+    }
+
+    fn handle_event(&mut self, event: UiEvent) -> bool {
+        match event {
+            // Clicking the stopwatch records a lap
+            UiEvent::PointerDown { button: 0x110, surface: Some(ActiveSurface::Timer), .. }
+                if matches!(self.mode, PomodoroMode::Stopwatch { .. }) =>
+            {
+                self.lap(self.time);
+                true
+            }
+            UiEvent::Scroll { surface: Some(ActiveSurface::Timer), .. }
+                if matches!(self.mode, PomodoroMode::Stopwatch { .. }) => true,
+            // Scrolling a running countdown lengthens or shortens it
+            UiEvent::Scroll { delta, surface: Some(ActiveSurface::Timer), .. }
+                if matches!(self.mode, PomodoroMode::Counting { .. }) =>
+            {
+                self.extend(EXTEND_STEP_SECONDS * delta.signum());
+                true
+            }
+            UiEvent::Scroll { surface: Some(ActiveSurface::Timer), .. }
+                if !matches!(self.mode, PomodoroMode::Idle) => true,
+            // Scrolling the idle timer cycles through durations
+            UiEvent::Scroll { delta, surface: Some(ActiveSurface::Timer), .. } => {
+                self.cycle_duration(delta);
+                // Keep a flashed idle timer up while it is being scrolled
+                if self.is_flashing_duration() {
+                    self.flash_duration(self.time);
+                }
+                true
             }
+            _ => false,
+        }
+    }
+
+    fn window(&self) -> Option<WindowId> {
+        match self.mode {
+            PomodoroMode::Counting { .. } | PomodoroMode::Paused { .. } | PomodoroMode::Stopwatch { .. } => Some(WindowId::Timer),
+            PomodoroMode::Completion { .. } if self.completion_style == CompletionStyle::Fullscreen => Some(WindowId::Plasma),
+            PomodoroMode::Completion { .. } => None,
+            PomodoroMode::Idle if self.duration_flash.is_some() => Some(WindowId::Timer),
+            PomodoroMode::Idle | PomodoroMode::Reveal { .. } => None,
         }
     }
-}
\ No newline at end of file
+
+    fn render(&self, draw: &mut DrawContext, viewport: Rect) {
+        self.render_mode(draw, viewport);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Default durations are 30, 25, 20, 15, 10 and 5 minutes, starting at 30
+    fn counting_pomodoro(elapsed: f32) -> Pomodoro {
+        let mut pomodoro = Pomodoro::new(&PomodoroConfig::default(), Stats::default());
+        pomodoro.start(0.0);
+        pomodoro.update(0.0, elapsed);
+        pomodoro
+    }
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-3, "expected {expected}, got {actual}");
+    }
+
+    #[test]
+    fn cycling_while_counting_keeps_the_remaining_proportion() {
+        // A quarter of the 30 minutes gone
+        let mut pomodoro = counting_pomodoro(450.0);
+        assert_close(pomodoro.remaining, 1350.0);
+
+        pomodoro.cycle_duration(-1.0);
+        assert_close(pomodoro.duration, 25.0 * 60.0);
+        assert_close(pomodoro.remaining, 0.75 * 25.0 * 60.0);
+
+        pomodoro.cycle_duration(1.0);
+        assert_close(pomodoro.duration, 30.0 * 60.0);
+        assert_close(pomodoro.remaining, 0.75 * 30.0 * 60.0);
+    }
+
+    #[test]
+    fn scaled_remaining_survives_the_next_tick() {
+        let mut pomodoro = counting_pomodoro(450.0);
+        pomodoro.cycle_duration(-1.0);
+
+        pomodoro.update(0.0, 460.0);
+        assert_close(pomodoro.remaining, 0.75 * 25.0 * 60.0 - 10.0);
+        assert!(matches!(pomodoro.mode, PomodoroMode::Counting { .. }));
+    }
+
+    #[test]
+    fn cycling_while_idle_resets_remaining() {
+        let mut pomodoro = Pomodoro::new(&PomodoroConfig::default(), Stats::default());
+        pomodoro.cycle_duration(1.0);
+        assert_close(pomodoro.duration, 5.0 * 60.0);
+        assert_close(pomodoro.remaining, 5.0 * 60.0);
+    }
+
+    #[test]
+    fn scrolling_up_lengthens_the_timer() {
+        let mut pomodoro = Pomodoro::new(&PomodoroConfig::default(), Stats::default());
+        pomodoro.cycle_duration(-1.0);
+        assert_close(pomodoro.duration, 25.0 * 60.0);
+
+        pomodoro.cycle_duration(1.0);
+        assert_close(pomodoro.duration, 30.0 * 60.0);
+    }
+
+    #[test]
+    fn extending_adds_to_the_remaining_time() {
+        let mut pomodoro = counting_pomodoro(600.0);
+        pomodoro.extend(EXTEND_STEP_SECONDS);
+        assert_close(pomodoro.remaining, 1500.0);
+        assert_close(pomodoro.duration, 30.0 * 60.0);
+
+        pomodoro.update(0.0, 610.0);
+        assert_close(pomodoro.remaining, 1490.0);
+
+        // The next phase starts from its own length again
+        pomodoro.stop();
+        pomodoro.start(0.0);
+        assert_close(pomodoro.remaining, 30.0 * 60.0);
+    }
+
+    #[test]
+    fn extending_is_clamped() {
+        let mut pomodoro = counting_pomodoro(29.5 * 60.0);
+        pomodoro.extend(-EXTEND_STEP_SECONDS);
+        assert_close(pomodoro.remaining, 30.0);
+
+        let mut pomodoro = counting_pomodoro(20.0 * 60.0);
+        pomodoro.extend(-EXTEND_STEP_SECONDS * 3.0);
+        assert_close(pomodoro.remaining, MIN_REMAINING_SECONDS);
+
+        pomodoro.extend(MAX_PHASE_SECONDS);
+        assert_close(pomodoro.phase_duration(), MAX_PHASE_SECONDS);
+    }
+
+    #[test]
+    fn default_minutes_selects_the_closest_duration() {
+        let config = PomodoroConfig { default_minutes: Some(25), ..PomodoroConfig::default() };
+        let pomodoro = Pomodoro::new(&config, Stats::default());
+        assert_eq!(pomodoro.duration_index, 1);
+        assert_close(pomodoro.remaining, 25.0 * 60.0);
+
+        // Unlisted values snap to the nearest entry
+        let mut pomodoro = Pomodoro::new(&PomodoroConfig::default(), Stats::default());
+        pomodoro.select_minutes(27);
+        assert_close(pomodoro.duration, 25.0 * 60.0);
+        pomodoro.select_minutes(90);
+        assert_close(pomodoro.duration, 30.0 * 60.0);
+    }
+
+    #[test]
+    fn a_task_label_makes_the_timer_taller() {
+        let mut pomodoro = Pomodoro::new(&PomodoroConfig::default(), Stats::default());
+        assert_eq!(pomodoro.desired_expanded_size().1, TIMER_WINDOW_SIZE[1]);
+
+        pomodoro.set_task(Some("  write the report "));
+        assert_eq!(pomodoro.task.as_deref(), Some("write the report"));
+        assert_eq!(pomodoro.desired_expanded_size().1, TIMER_WINDOW_SIZE[1] + TASK_LABEL_HEIGHT);
+
+        pomodoro.set_task(Some("   "));
+        assert_eq!(pomodoro.desired_expanded_size().1, TIMER_WINDOW_SIZE[1]);
+    }
+
+    #[test]
+    fn stopwatch_rolls_over_into_hours() {
+        let mut pomodoro = Pomodoro::new(&PomodoroConfig::default(), Stats::default());
+        pomodoro.toggle_stopwatch(0.0);
+
+        pomodoro.update(0.0, 99.0 * 60.0 + 59.0);
+        assert_eq!((pomodoro.minute_digits, pomodoro.second_digits), ([9, 9], [5, 9]));
+        assert_eq!(pomodoro.desired_expanded_size().0, TIMER_WINDOW_SIZE[0]);
+
+        pomodoro.update(0.0, 100.0 * 60.0);
+        assert_eq!(pomodoro.hour_digits, [0, 1]);
+        assert_eq!((pomodoro.minute_digits, pomodoro.second_digits), ([4, 0], [0, 0]));
+        assert!(pomodoro.desired_expanded_size().0 > TIMER_WINDOW_SIZE[0]);
+    }
+
+    #[test]
+    fn hour_long_countdown_shows_hours_throughout() {
+        let config = PomodoroConfig { durations: vec![90], ..PomodoroConfig::default() };
+        let mut pomodoro = Pomodoro::new(&config, Stats::default());
+        pomodoro.start(0.0);
+
+        pomodoro.update(0.0, 0.0);
+        assert_eq!((pomodoro.hour_digits, pomodoro.minute_digits), ([0, 1], [3, 0]));
+        assert!(pomodoro.desired_expanded_size().0 > TIMER_WINDOW_SIZE[0]);
+
+        // Under an hour left, still HH:MM:SS at the same width
+        pomodoro.update(0.0, 31.0 * 60.0);
+        assert_eq!((pomodoro.hour_digits, pomodoro.minute_digits), ([0, 0], [5, 9]));
+        assert!(pomodoro.desired_expanded_size().0 > TIMER_WINDOW_SIZE[0]);
+    }
+}
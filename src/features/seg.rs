@@ -0,0 +1,40 @@
+// Seven-segment display mapping
+// Each digit has 7 segments: A(top), B(top-right), C(bottom-right), D(bottom),
+// E(bottom-left), F(top-left), G(middle)
+pub const SEGMENT_MAP: [[bool; 7]; 10] = [
+    [true, true, true, true, true, true, false],     // 0
+    [false, true, true, false, false, false, false], // 1
+    [true, true, false, true, true, false, true],    // 2
+    [true, true, true, true, false, false, true],    // 3
+    [false, true, true, false, false, true, true],   // 4
+    [true, false, true, true, false, true, true],    // 5
+    [true, false, true, true, true, true, true],     // 6
+    [true, true, true, false, false, false, false],  // 7
+    [true, true, true, true, true, true, true],      // 8
+    [true, true, true, true, false, true, true],     // 9
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The usual 0-9 encoding with segment A in bit 0 through G in bit 6
+    const CANONICAL: [u8; 10] = [0x3f, 0x06, 0x5b, 0x4f, 0x66, 0x6d, 0x7d, 0x07, 0x7f, 0x6f];
+
+    #[test]
+    fn digits_match_the_canonical_encoding() {
+        for (digit, (segments, bits)) in SEGMENT_MAP.iter().zip(CANONICAL).enumerate() {
+            let encoded = segments.iter().enumerate().fold(0u8, |acc, (i, &on)| acc | ((on as u8) << i));
+            assert_eq!(encoded, bits, "digit {} lights the wrong segments", digit);
+        }
+    }
+
+    #[test]
+    fn renderers_share_this_map() {
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/src/features/");
+        for file in ["clock.rs", "pomodoro.rs"] {
+            let source = std::fs::read_to_string(format!("{}{}", dir, file)).unwrap();
+            assert!(!source.contains("const SEGMENT_MAP"), "{} defines its own SEGMENT_MAP", file);
+        }
+    }
+}
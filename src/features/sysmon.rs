@@ -0,0 +1,212 @@
+use super::{pomodoro::render_pair, Feature};
+use crate::app::UiEvent;
+use crate::config::{SysMonConfig, SysMonMetric};
+use crate::gfx::{anim::{Easing, Timeline}, draw::DrawContext, math::{Color, Rect}};
+use crate::wayland::window_manager::WindowId;
+use anyhow::{Context, Result};
+use log::warn;
+
+const SAMPLE_INTERVAL: f32 = 1.0;
+
+/// Logical width of one digit pair, and the window height
+const PAIR_WIDTH: u32 = 40;
+const PAIR_GAP: u32 = 4;
+const WINDOW_HEIGHT: u32 = 30;
+
+const CPU_COLOR: Color = Color { r: 0.0, g: 0.8, b: 1.0, a: 1.0 };
+const MEM_COLOR: Color = Color { r: 0.8, g: 0.5, b: 1.0, a: 1.0 };
+
+/// Cumulative jiffies from the aggregate `cpu` line of /proc/stat
+#[derive(Debug, Clone, Copy)]
+struct CpuTimes {
+    idle: u64,
+    total: u64,
+}
+
+pub struct SysMon {
+    metric: SysMonMetric,
+    sample_tl: Timeline,
+    prev_cpu: Option<CpuTimes>,
+    cpu: Option<u8>,
+    mem: Option<u8>,
+    read_failed_logged: bool,
+}
+
+impl SysMon {
+    pub fn new(config: &SysMonConfig) -> Self {
+        Self {
+            metric: config.metric,
+            sample_tl: Timeline::with_easing(SAMPLE_INTERVAL, Easing::Linear),
+            prev_cpu: None,
+            cpu: None,
+            mem: None,
+            read_failed_logged: false,
+        }
+    }
+
+    fn shows_cpu(&self) -> bool {
+        matches!(self.metric, SysMonMetric::Cpu | SysMonMetric::Both)
+    }
+
+    fn shows_mem(&self) -> bool {
+        matches!(self.metric, SysMonMetric::Mem | SysMonMetric::Both)
+    }
+
+    fn sample(&mut self) {
+        if let Err(e) = self.try_sample() {
+            if !self.read_failed_logged {
+                warn!("System monitor sample failed: {:#}", e);
+                self.read_failed_logged = true;
+            }
+        }
+    }
+
+    fn try_sample(&mut self) -> Result<()> {
+        if self.shows_cpu() {
+            let stat = std::fs::read_to_string("/proc/stat").context("reading /proc/stat")?;
+            let times = parse_cpu_times(&stat).context("parsing /proc/stat")?;
+            // Load is the busy share of the jiffies since the previous sample
+            if let Some(prev) = self.prev_cpu {
+                let total = times.total.saturating_sub(prev.total);
+                let idle = times.idle.saturating_sub(prev.idle);
+                if let Some(busy) = (100 * (total - idle.min(total))).checked_div(total) {
+                    self.cpu = Some(busy as u8);
+                }
+            }
+            self.prev_cpu = Some(times);
+        }
+        if self.shows_mem() {
+            let meminfo = std::fs::read_to_string("/proc/meminfo").context("reading /proc/meminfo")?;
+            self.mem = Some(parse_mem_used(&meminfo).context("parsing /proc/meminfo")?);
+        }
+        Ok(())
+    }
+
+    fn render_pair(&self, draw: &mut DrawContext, value: Option<u8>, x: f32, height: f32, color: Color) {
+        let outer_padding = 3.0;
+        let margin = 2.0;
+        let spacing = 2.0;
+
+        let face_w = PAIR_WIDTH as f32 - outer_padding * 2.0;
+        let face_h = height - outer_padding * 2.0;
+        draw.rect(x + outer_padding, outer_padding, face_w, face_h, Color::rgba(0, 0, 0, 255));
+
+        let digit_height = face_h - margin * 2.0;
+        let digit_width = digit_height * 0.62;
+        let start_x = x + outer_padding + (face_w - digit_width * 2.0 - spacing) * 0.5;
+        let start_y = outer_padding + margin;
+
+        // Two digits only, so a pegged meter reads 99; dashes until sampled
+        render_pair(draw, value, Rect::new(start_x, start_y, digit_width, digit_height), spacing, color);
+    }
+}
+
+impl Feature for SysMon {
+    fn name(&self) -> &'static str {
+        "sysmon"
+    }
+
+    fn desired_expanded_size(&self) -> (u32, u32) {
+        let width = match self.metric {
+            SysMonMetric::Both => PAIR_WIDTH * 2 + PAIR_GAP,
+            SysMonMetric::Cpu | SysMonMetric::Mem => PAIR_WIDTH,
+        };
+        (width, WINDOW_HEIGHT)
+    }
+
+    fn update(&mut self, _dt: f32, now: f32) {
+        self.sample_tl.update(now);
+        if self.sample_tl.is_complete() {
+            self.sample();
+            self.sample_tl.start(now);
+        }
+    }
+
+    fn handle_event(&mut self, _event: UiEvent) -> bool {
+        false
+    }
+
+    fn window(&self) -> Option<WindowId> {
+        Some(WindowId::SysMon)
+    }
+
+    fn render(&self, draw: &mut DrawContext, viewport: Rect) {
+        let mut x = 0.0;
+        if self.shows_cpu() {
+            self.render_pair(draw, self.cpu, x, viewport.height, CPU_COLOR);
+            x += (PAIR_WIDTH + PAIR_GAP) as f32;
+        }
+        if self.shows_mem() {
+            self.render_pair(draw, self.mem, x, viewport.height, MEM_COLOR);
+        }
+    }
+}
+
+fn parse_cpu_times(stat: &str) -> Option<CpuTimes> {
+    let line = stat.lines().find(|l| l.starts_with("cpu "))?;
+    let fields: Vec<u64> = line.split_whitespace().skip(1).map(|f| f.parse().ok()).collect::<Option<_>>()?;
+    if fields.len() < 4 {
+        return None;
+    }
+    // user nice system idle iowait irq softirq steal; guest time is already counted in user
+    let idle = fields[3] + fields.get(4).copied().unwrap_or(0);
+    let total = fields.iter().take(8).sum();
+    Some(CpuTimes { idle, total })
+}
+
+/// Used memory as a percentage, counting reclaimable cache as free
+fn parse_mem_used(meminfo: &str) -> Option<u8> {
+    let field = |name: &str| -> Option<u64> {
+        let line = meminfo.lines().find(|l| l.starts_with(name))?;
+        line[name.len()..].split_whitespace().next()?.parse().ok()
+    };
+    let total = field("MemTotal:")?;
+    let available = field("MemAvailable:")?;
+    if total == 0 {
+        return None;
+    }
+    Some((100 * total.saturating_sub(available) / total) as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STAT: &str = "\
+cpu  4705 150 1120 16250 520 30 45 12 3 0
+cpu0 2350 75 560 8125 260 15 22 6 1 0
+intr 114930548 113199788 3 0 5 263 0 4
+";
+
+    const MEMINFO: &str = "\
+MemTotal:       16000000 kB
+MemFree:         2000000 kB
+MemAvailable:    4000000 kB
+Buffers:          300000 kB
+";
+
+    #[test]
+    fn cpu_times_count_iowait_as_idle() {
+        let times = parse_cpu_times(STAT).unwrap();
+        assert_eq!(times.idle, 16250 + 520);
+        // Guest columns are left out, they're already in user
+        assert_eq!(times.total, 4705 + 150 + 1120 + 16250 + 520 + 30 + 45 + 12);
+    }
+
+    #[test]
+    fn cpu_times_need_the_aggregate_line() {
+        assert!(parse_cpu_times("cpu0 2350 75 560 8125\n").is_none());
+        assert!(parse_cpu_times("cpu  1 2 3\n").is_none());
+    }
+
+    #[test]
+    fn mem_used_counts_available_as_free() {
+        assert_eq!(parse_mem_used(MEMINFO), Some(75));
+    }
+
+    #[test]
+    fn mem_used_skips_an_empty_total() {
+        assert_eq!(parse_mem_used("MemTotal: 0 kB\nMemAvailable: 0 kB\n"), None);
+        assert_eq!(parse_mem_used("MemTotal: 100 kB\n"), None);
+    }
+}
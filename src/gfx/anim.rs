@@ -10,19 +10,62 @@ pub fn lerp(a: f32, b: f32, t: f32) -> f32 {
     a + (b - a) * t
 }
 
+#[derive(Debug, Clone, Copy)]
+pub enum Easing {
+    Linear,
+    EaseInOut,
+    /// Unit-mass spring pulled toward 1.0; damping below 2*sqrt(stiffness)
+    /// overshoots before settling
+    Spring { stiffness: f32, damping: f32 },
+}
+
+impl Easing {
+    /// Spring position `t` seconds after release from 0 toward 1
+    fn spring(t: f32, stiffness: f32, damping: f32) -> f32 {
+        let omega = stiffness.max(f32::EPSILON).sqrt();
+        let zeta = damping / (2.0 * omega);
+        if zeta < 1.0 {
+            let decay = zeta * omega;
+            let omega_d = omega * (1.0 - zeta * zeta).sqrt();
+            1.0 - (-decay * t).exp() * ((omega_d * t).cos() + decay / omega_d * (omega_d * t).sin())
+        } else {
+            // Critically damped (overdamped springs are treated the same)
+            1.0 - (1.0 + omega * t) * (-omega * t).exp()
+        }
+    }
+
+    /// Seconds until a spring stays within 2% of its target
+    fn settle_time(&self) -> f32 {
+        match *self {
+            Easing::Spring { stiffness, damping } => {
+                let omega = stiffness.max(f32::EPSILON).sqrt();
+                let decay = (damping / 2.0).min(omega);
+                4.0 / decay.max(f32::EPSILON)
+            }
+            _ => 0.0,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Timeline {
     pub start_time: f32,
     pub duration: f32,
     pub current_time: f32,
+    pub easing: Easing,
 }
 
 impl Timeline {
     pub fn new(duration: f32) -> Self {
+        Self::with_easing(duration, Easing::EaseInOut)
+    }
+
+    pub fn with_easing(duration: f32, easing: Easing) -> Self {
         Self {
             start_time: 0.0,
             duration,
             current_time: 0.0,
+            easing,
         }
     }
 
@@ -40,11 +83,24 @@ impl Timeline {
         (elapsed / self.duration).min(1.0).max(0.0)
     }
 
+    /// Springs are complete once they've settled, which may outlast `duration`
     pub fn is_complete(&self) -> bool {
-        self.progress() >= 1.0
+        let elapsed = self.current_time - self.start_time;
+        self.progress() >= 1.0 && elapsed >= self.easing.settle_time()
     }
 
+    /// Progress shaped by the easing; springs may briefly exceed 1.0
     pub fn eased_progress(&self) -> f32 {
-        ease_in_out(self.progress())
+        match self.easing {
+            Easing::Linear => self.progress(),
+            Easing::EaseInOut => ease_in_out(self.progress()),
+            Easing::Spring { stiffness, damping } => {
+                if self.is_complete() {
+                    return 1.0;
+                }
+                let elapsed = (self.current_time - self.start_time).max(0.0);
+                Easing::spring(elapsed, stiffness, damping)
+            }
+        }
     }
 }
\ No newline at end of file
@@ -1,4 +1,5 @@
-use super::math::{Color, Rect};
+use super::font;
+use super::math::{Color, Rect, Vec2};
 use anyhow::Result;
 use glow::HasContext;
 use crate::app::UiMode;
@@ -8,21 +9,99 @@ use crate::app::UiMode;
 pub struct Vertex {
     pub pos: [f32; 2],
     pub uv: [f32; 2],
+    pub color: [f32; 4],
 }
 
+/// Attribute locations of the UI shader, looked up once per program
+struct VertexAttribs {
+    pos: Option<u32>,
+    uv: Option<u32>,
+    color: Option<u32>,
+}
+
+impl VertexAttribs {
+    fn query(gl: &glow::Context, program: glow::Program) -> Self {
+        unsafe {
+            Self {
+                pos: gl.get_attrib_location(program, "aPos"),
+                uv: gl.get_attrib_location(program, "aUV"),
+                color: gl.get_attrib_location(program, "aColor"),
+            }
+        }
+    }
+
+    /// Point the attributes at the currently bound vertex buffer
+    unsafe fn enable(&self, gl: &glow::Context) {
+        let stride = std::mem::size_of::<Vertex>() as i32;
+        for (location, size, offset) in [(self.pos, 2, 0), (self.uv, 2, 8), (self.color, 4, 16)] {
+            if let Some(location) = location {
+                gl.enable_vertex_attrib_array(location);
+                gl.vertex_attrib_pointer_f32(location, size, glow::FLOAT, false, stride, offset);
+            }
+        }
+    }
+}
+
+/// A texture uploaded with `DrawContext::load_texture`, only valid with the
+/// context that made it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextureId(usize);
+
+/// What the driver behind a `DrawContext` offers, for logs and bug reports
+#[derive(Debug, Clone)]
+pub struct GlInfo {
+    pub renderer: String,
+    /// The driver's `GL_VERSION` string, e.g. "OpenGL ES 3.2 Mesa 24.0"
+    pub version: String,
+    pub max_texture_size: i32,
+    pub vertex_arrays: bool,
+    /// `dFdx`/`fwidth` in fragment shaders (core in GLES3)
+    pub derivatives: bool,
+}
+
+/// How far each slice handed to `segment_slice` reaches over the next one
+/// in its stack, so no seam shows between them
+pub const SLICE_OVERLAP: f32 = 0.5;
+
+/// Vertices the VBO holds before it first has to grow
+const INITIAL_VBO_VERTICES: usize = 4096;
+
+/// Highest `uEffectMode` the UI fragment shader knows
+pub const MAX_EFFECT_MODE: i32 = 2;
+
 pub struct DrawContext {
     gl: glow::Context,
     program: glow::Program,
     vbo: glow::Buffer,
+    /// Bytes allocated for `vbo`; flushes that fit update it in place
+    vbo_capacity: usize,
     vao: Option<glow::VertexArray>,
+    attribs: VertexAttribs,
     vertices: Vec<Vertex>,
     viewport: [f32; 2],
+    /// Part of the viewport drawing currently lands in, see `set_region`
+    region: Rect,
+    scale: f32,
+    antialias: bool,
+    /// What `begin` clears the surface to
+    clear_color: Color,
+    /// Active clip rects, innermost last; the scissor box follows the top
+    clips: Vec<Rect>,
+    textures: Vec<glow::Texture>,
+    /// Texture the queued geometry samples, if any
+    bound_texture: Option<TextureId>,
+    /// Bitmap font atlas, uploaded on first use; inner None if that failed
+    font: Option<Option<TextureId>>,
 
     // Uniform locations
     u_viewport: Option<glow::UniformLocation>,
-    u_color: Option<glow::UniformLocation>,
     u_time: Option<glow::UniformLocation>,
     u_effect_mode: Option<glow::UniformLocation>,
+    u_progress: Option<glow::UniformLocation>,
+    u_tint: Option<glow::UniformLocation>,
+    u_global_alpha: Option<glow::UniformLocation>,
+    u_texture: Option<glow::UniformLocation>,
+    u_use_texture: Option<glow::UniformLocation>,
 }
 
 impl DrawContext {
@@ -32,81 +111,435 @@ impl DrawContext {
                 .map_err(|e| anyhow::anyhow!("Failed to create buffer: {}", e))?
         };
 
-        // VAOs are not universally available in GLES2
-        let vao = None;
+        // Sized up front so flushes can update it in place instead of
+        // handing the driver a new store every time
+        let vbo_capacity = INITIAL_VBO_VERTICES * std::mem::size_of::<Vertex>();
+        unsafe {
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(vbo));
+            gl.buffer_data_size(glow::ARRAY_BUFFER, vbo_capacity as i32, glow::DYNAMIC_DRAW);
+        }
 
-        let u_viewport = unsafe { gl.get_uniform_location(program, "uViewport") };
-        let u_color = unsafe { gl.get_uniform_location(program, "uColor") };
-        let u_time = unsafe { gl.get_uniform_location(program, "uTime") };
-        let u_effect_mode = unsafe { gl.get_uniform_location(program, "uEffectMode") };
+        let attribs = VertexAttribs::query(&gl, program);
+
+        // VAOs are core in GLES3 but only an extension in GLES2. When present,
+        // record the attribute setup once instead of redoing it every flush.
+        let vao_supported = (gl.version().is_embedded && gl.version().major >= 3)
+            || gl.supported_extensions().contains("GL_OES_vertex_array_object");
+        let vao = if vao_supported {
+            unsafe {
+                let vao = gl.create_vertex_array()
+                    .map_err(|e| anyhow::anyhow!("Failed to create vertex array: {}", e))?;
+                gl.bind_vertex_array(Some(vao));
+                gl.bind_buffer(glow::ARRAY_BUFFER, Some(vbo));
+                attribs.enable(&gl);
+                gl.bind_vertex_array(None);
+                Some(vao)
+            }
+        } else {
+            None
+        };
+        log::info!("Vertex array objects {}", if vao.is_some() { "enabled" } else { "unavailable" });
 
-        Ok(Self {
+        let mut draw = Self {
             gl,
             program,
             vbo,
+            vbo_capacity,
             vao,
-            vertices: Vec::with_capacity(1024),
+            attribs,
+            vertices: Vec::with_capacity(INITIAL_VBO_VERTICES),
             viewport: [800.0, 600.0],
-            u_viewport,
-            u_color,
-            u_time,
-            u_effect_mode,
-        })
+            region: Rect::new(0.0, 0.0, 800.0, 600.0),
+            scale: 1.0,
+            clips: Vec::new(),
+            textures: Vec::new(),
+            bound_texture: None,
+            font: None,
+            antialias: false,
+            clear_color: Color::new(0.0, 0.0, 0.0, 0.0),
+            u_viewport: None,
+            u_time: None,
+            u_effect_mode: None,
+            u_progress: None,
+            u_tint: None,
+            u_global_alpha: None,
+            u_texture: None,
+            u_use_texture: None,
+        };
+        draw.locate_uniforms();
+        Ok(draw)
+    }
+
+    fn locate_uniforms(&mut self) {
+        let (gl, program) = (&self.gl, self.program);
+        unsafe {
+            self.u_viewport = gl.get_uniform_location(program, "uViewport");
+            self.u_time = gl.get_uniform_location(program, "uTime");
+            self.u_effect_mode = gl.get_uniform_location(program, "uEffectMode");
+            self.u_progress = gl.get_uniform_location(program, "uProgress");
+            self.u_tint = gl.get_uniform_location(program, "uTint");
+            self.u_global_alpha = gl.get_uniform_location(program, "uGlobalAlpha");
+            self.u_texture = gl.get_uniform_location(program, "uTexture");
+            self.u_use_texture = gl.get_uniform_location(program, "uUseTexture");
+        }
+    }
+
+    /// Compile `vert_src`/`frag_src` and swap the result in for the current
+    /// program. On failure the old program stays. Needs this context current.
+    pub fn reload_program(&mut self, vert_src: &str, frag_src: &str) -> Result<()> {
+        let program = super::gl::load_shader_program(&self.gl, vert_src, frag_src)?;
+        unsafe { self.gl.delete_program(self.program) };
+        self.program = program;
+        self.attribs = VertexAttribs::query(&self.gl, program);
+        if let Some(vao) = self.vao {
+            unsafe {
+                self.gl.bind_vertex_array(Some(vao));
+                self.gl.bind_buffer(glow::ARRAY_BUFFER, Some(self.vbo));
+                self.attribs.enable(&self.gl);
+                self.gl.bind_vertex_array(None);
+            }
+        }
+        self.locate_uniforms();
+        Ok(())
     }
 
-    pub fn begin(&mut self, viewport_px: [f32; 2]) {
-        self.viewport = viewport_px;
+    pub fn gl_info(&self) -> GlInfo {
+        let gl = &self.gl;
+        let version = gl.version();
+        unsafe {
+            GlInfo {
+                renderer: gl.get_parameter_string(glow::RENDERER),
+                version: gl.get_parameter_string(glow::VERSION),
+                max_texture_size: gl.get_parameter_i32(glow::MAX_TEXTURE_SIZE),
+                vertex_arrays: self.vao.is_some(),
+                derivatives: (version.is_embedded && version.major >= 3)
+                    || gl.supported_extensions().contains("GL_OES_standard_derivatives"),
+            }
+        }
+    }
+
+    /// `viewport` is in logical units; the GL viewport covers it at `scale`
+    /// physical pixels per unit, so callers keep laying out in logical space
+    pub fn begin(&mut self, viewport: [f32; 2], scale: f32) {
+        self.viewport = viewport;
+        self.region = Rect::new(0.0, 0.0, viewport[0], viewport[1]);
+        self.scale = scale;
         self.vertices.clear();
+        self.clips.clear();
 
         unsafe {
-            self.gl.viewport(0, 0, viewport_px[0] as i32, viewport_px[1] as i32);
-            self.gl.clear_color(0.0, 0.0, 0.0, 0.0);
+            self.gl.disable(glow::SCISSOR_TEST);
+            self.gl.viewport(0, 0, (viewport[0] * scale) as i32, (viewport[1] * scale) as i32);
+            // Stored premultiplied, the way the compositor reads the buffer
+            let c = self.clear_color;
+            self.gl.clear_color(c.r * c.a, c.g * c.a, c.b * c.a, c.a);
             self.gl.clear(glow::COLOR_BUFFER_BIT);
 
-            // Enable alpha blending for transparency
+            // Enable alpha blending for transparency. Destination alpha is
+            // accumulated separately so a translucent face over the cleared
+            // surface keeps its own alpha rather than its square. Color comes
+            // out multiplied by that alpha, which is the premultiplied buffer
+            // Wayland compositors expect, so no shader-side premultiply needed
             self.gl.enable(glow::BLEND);
-            self.gl.blend_func(glow::SRC_ALPHA, glow::ONE_MINUS_SRC_ALPHA);
+            self.gl.blend_func_separate(glow::SRC_ALPHA, glow::ONE_MINUS_SRC_ALPHA, glow::ONE, glow::ONE_MINUS_SRC_ALPHA);
 
             self.gl.use_program(Some(self.program));
             if let Some(loc) = self.u_viewport {
-                self.gl.uniform_2_f32(Some(&loc), viewport_px[0], viewport_px[1]);
+                self.gl.uniform_2_f32(Some(&loc), viewport[0], viewport[1]);
             }
+            if let Some(loc) = self.u_texture {
+                self.gl.uniform_1_i32(Some(&loc), 0);
+            }
+            if let Some(loc) = self.u_use_texture {
+                self.gl.uniform_1_i32(Some(&loc), 0);
+            }
+            self.gl.bind_texture(glow::TEXTURE_2D, None);
         }
+        self.bound_texture = None;
+        self.set_tint(Color::new(1.0, 1.0, 1.0, 1.0));
+        self.set_opacity(1.0);
+    }
+
+    /// Draw into `region` of the viewport as if it were the whole of it: from
+    /// here on (0, 0) is its top-left corner and nothing spills outside it.
+    /// Lets a feature render into part of another's surface. Pass the full
+    /// viewport to go back; `begin` does as well.
+    pub fn set_region(&mut self, region: Rect) {
+        self.flush_batch();
+        self.region = region;
+        let scale = self.scale;
+        unsafe {
+            self.gl.viewport(
+                (region.x * scale) as i32,
+                ((self.viewport[1] - region.y - region.height) * scale) as i32,
+                (region.width * scale) as i32,
+                (region.height * scale) as i32,
+            );
+            if let Some(loc) = self.u_viewport {
+                self.gl.uniform_2_f32(Some(&loc), region.width, region.height);
+            }
+        }
+        self.apply_scissor(self.clips.last().copied());
+    }
+
+    /// Upload `width`x`height` RGBA pixels, top row first, for `textured_quad`.
+    /// Sampled without filtering, so bitmap glyphs stay crisp at integer scales.
+    pub fn load_texture(&mut self, rgba: &[u8], width: u32, height: u32) -> Result<TextureId> {
+        if rgba.len() != width as usize * height as usize * 4 {
+            anyhow::bail!("Texture data is {} bytes, expected {}x{} RGBA", rgba.len(), width, height);
+        }
+        let max = unsafe { self.gl.get_parameter_i32(glow::MAX_TEXTURE_SIZE) };
+        if width.max(height) > max as u32 {
+            anyhow::bail!("Texture is {}x{}, the driver allows at most {}px a side", width, height, max);
+        }
+        self.flush_batch();
+        unsafe {
+            let texture = self.gl.create_texture()
+                .map_err(|e| anyhow::anyhow!("Failed to create texture: {}", e))?;
+            self.gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+            for (param, value) in [
+                (glow::TEXTURE_MIN_FILTER, glow::NEAREST),
+                (glow::TEXTURE_MAG_FILTER, glow::NEAREST),
+                (glow::TEXTURE_WRAP_S, glow::CLAMP_TO_EDGE),
+                (glow::TEXTURE_WRAP_T, glow::CLAMP_TO_EDGE),
+            ] {
+                self.gl.tex_parameter_i32(glow::TEXTURE_2D, param, value as i32);
+            }
+            self.gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 1);
+            self.gl.tex_image_2d(
+                glow::TEXTURE_2D, 0, glow::RGBA as i32, width as i32, height as i32, 0,
+                glow::RGBA, glow::UNSIGNED_BYTE, Some(rgba),
+            );
+            // Whatever was bound for the queued geometry comes back
+            let bound = self.bound_texture.map(|id| self.textures[id.0]);
+            self.gl.bind_texture(glow::TEXTURE_2D, bound);
+            self.textures.push(texture);
+        }
+        Ok(TextureId(self.textures.len() - 1))
+    }
+
+    /// Switch the texture the next geometry samples, drawing what's queued
+    /// with the previous one first
+    fn bind_texture(&mut self, texture: Option<TextureId>) {
+        if texture == self.bound_texture {
+            return;
+        }
+        self.flush_batch();
+        unsafe {
+            self.gl.bind_texture(glow::TEXTURE_2D, texture.map(|id| self.textures[id.0]));
+            if let Some(loc) = self.u_use_texture {
+                self.gl.uniform_1_i32(Some(&loc), texture.is_some() as i32);
+            }
+        }
+        self.bound_texture = texture;
+    }
+
+    /// `uv` of `texture` (in 0-1 texture space, top-left origin) stretched
+    /// over `rect` and multiplied by `tint`
+    pub fn textured_quad(&mut self, rect: Rect, uv: Rect, texture: TextureId, tint: Color) {
+        self.bind_texture(Some(texture));
+        self.vertices.extend_from_slice(&quad(rect, uv, tint, tint));
     }
 
     pub fn rect(&mut self, x: f32, y: f32, w: f32, h: f32, color: Color) {
-        let x2 = x + w;
-        let y2 = y + h;
+        self.rect_gradient(x, y, w, h, color, color);
+    }
+
+    /// Rect shaded from `top` along its upper edge to `bottom` along its lower
+    pub fn rect_gradient(&mut self, x: f32, y: f32, w: f32, h: f32, top: Color, bottom: Color) {
+        // Geometry accumulates until flush (or a uniform change) so a whole
+        // frame normally goes out in a single draw call
+        self.bind_texture(None);
+        self.vertices.extend_from_slice(&quad(Rect::new(x, y, w, h), Rect::new(0.0, 0.0, 1.0, 1.0), top, bottom));
+    }
+
+    /// `text` in the built-in 5x7 bitmap font, top-left at (x, y), each font
+    /// pixel `scale` units square. See `font::text_width` for its extent.
+    pub fn text(&mut self, x: f32, y: f32, scale: f32, text: &str, color: Color) {
+        if self.font.is_none() {
+            let (rgba, width, height) = font::atlas();
+            let loaded = self.load_texture(&rgba, width, height)
+                .map_err(|e| log::warn!("Bitmap font unavailable, text won't be drawn: {}", e))
+                .ok();
+            self.font = Some(loaded);
+        }
+        let Some(Some(font)) = self.font else { return };
+
+        let (width, height) = (font::GLYPH_WIDTH as f32 * scale, font::GLYPH_HEIGHT as f32 * scale);
+        for (c, offset) in font::glyph_offsets(text) {
+            if c != ' ' {
+                self.textured_quad(Rect::new(x + offset * scale, y, width, height), font::glyph_uv(c), font, color);
+            }
+        }
+    }
 
+    /// Segment from (x0, y0) to (x1, y1), `thickness` wide and centred on it
+    pub fn line(&mut self, x0: f32, y0: f32, x1: f32, y1: f32, thickness: f32, color: Color) {
+        let Some([a, b, c, d]) = line_quad(x0, y0, x1, y1, thickness) else { return };
+        self.bind_texture(None);
+        let color = color.to_array();
         self.vertices.extend_from_slice(&[
-            Vertex { pos: [x, y], uv: [0.0, 0.0] },
-            Vertex { pos: [x2, y], uv: [1.0, 0.0] },
-            Vertex { pos: [x2, y2], uv: [1.0, 1.0] },
+            Vertex { pos: a, uv: [0.0, 0.0], color },
+            Vertex { pos: b, uv: [1.0, 0.0], color },
+            Vertex { pos: c, uv: [1.0, 1.0], color },
 
-            Vertex { pos: [x, y], uv: [0.0, 0.0] },
-            Vertex { pos: [x2, y2], uv: [1.0, 1.0] },
-            Vertex { pos: [x, y2], uv: [0.0, 1.0] },
+            Vertex { pos: a, uv: [0.0, 0.0], color },
+            Vertex { pos: c, uv: [1.0, 1.0], color },
+            Vertex { pos: d, uv: [0.0, 1.0], color },
         ]);
+    }
 
-        self.set_color(color);
-        self.flush_batch();
+    /// Filled circle as a fan of `segments` triangles around (cx, cy)
+    pub fn circle(&mut self, cx: f32, cy: f32, radius: f32, segments: usize, color: Color) {
+        self.bind_texture(None);
+        let color = color.to_array();
+        let uv = |[x, y]: [f32; 2]| [0.5 + (x - cx) / (radius * 2.0), 0.5 + (y - cy) / (radius * 2.0)];
+        for pos in circle_fan(cx, cy, radius, segments) {
+            self.vertices.push(Vertex { pos, uv: uv(pos), color });
+        }
     }
 
-    pub fn round_rect(&mut self, x: f32, y: f32, w: f32, h: f32, _radius: f32, color: Color) {
-        // Simplified version - just draw a regular rect for now
-        // TODO: implement proper rounded corners with triangles
-        self.rect(x, y, w, h, color);
+    /// Feather the edges of seven-segment slices (see `segment_slice`)
+    pub fn set_antialias(&mut self, antialias: bool) {
+        self.antialias = antialias;
     }
 
-    fn set_color(&mut self, color: Color) {
+    /// Color `begin` fills the surface with before anything is drawn;
+    /// transparent unless set
+    pub fn set_clear_color(&mut self, color: Color) {
+        self.clear_color = color;
+    }
+
+    /// Slice `index` of `count` stacked to build a beveled digit segment,
+    /// each overlapping the next by `SLICE_OVERLAP`. The slices step inward
+    /// toward the bevel on their ends (left/right when `horizontal`,
+    /// top/bottom otherwise); with antialiasing those ends and the outermost
+    /// slices get partial alpha so the steps blend in.
+    /// `colors` shade the slice from its top edge to its bottom one.
+    pub fn segment_slice(&mut self, rect: Rect, colors: [Color; 2], index: usize, count: usize, horizontal: bool) {
+        let [top, bottom] = colors;
+        if !self.antialias {
+            self.rect_gradient(rect.x, rect.y, rect.width, rect.height, top, bottom);
+            return;
+        }
+
+        // Outermost slices only partially cover the segment's long edge
+        let coverage = if index == 0 || index + 1 == count { 0.5 } else { 1.0 };
+        let core = |color: Color| color.with_alpha(color.a * coverage);
+        let edge = |color: Color| color.with_alpha(color.a * coverage * 0.5);
+        let feather = 0.75;
+
+        self.rect_gradient(rect.x, rect.y, rect.width, rect.height, core(top), core(bottom));
+        if horizontal {
+            let (lead, trail) = feather_trim(index, count, rect.height);
+            let (y, height) = (rect.y + lead, rect.height - lead - trail);
+            self.rect_gradient(rect.x - feather, y, feather, height, edge(top), edge(bottom));
+            self.rect_gradient(rect.x + rect.width, y, feather, height, edge(top), edge(bottom));
+        } else {
+            let (lead, trail) = feather_trim(index, count, rect.width);
+            let (x, width) = (rect.x + lead, rect.width - lead - trail);
+            self.rect(x, rect.y - feather, width, feather, edge(top));
+            self.rect(x, rect.y + rect.height, width, feather, edge(bottom));
+        }
+    }
+
+    /// Ring segment centred on `center` from `start_angle` to `end_angle`
+    /// (radians, clockwise on screen from +x), `thickness` wide around `radius`
+    pub fn arc(&mut self, center: Vec2, radius: f32, thickness: f32, start_angle: f32, end_angle: f32, color: Color) {
+        let sweep = end_angle - start_angle;
+        if sweep.abs() <= f32::EPSILON || radius <= 0.0 {
+            return;
+        }
+
+        // Roughly one quad per 2px of arc length keeps small rings smooth
+        let steps = ((sweep.abs() * radius / 2.0).ceil() as usize).max(8);
+        let inner = (radius - thickness * 0.5).max(0.0);
+        let outer = radius + thickness * 0.5;
+        self.bind_texture(None);
+        let color = color.to_array();
+
+        let point = |angle: f32, r: f32| [center.x + angle.cos() * r, center.y + angle.sin() * r];
+        for i in 0..steps {
+            let a0 = start_angle + sweep * (i as f32 / steps as f32);
+            let a1 = start_angle + sweep * ((i + 1) as f32 / steps as f32);
+            let (i0, o0, i1, o1) = (point(a0, inner), point(a0, outer), point(a1, inner), point(a1, outer));
+
+            self.vertices.extend_from_slice(&[
+                Vertex { pos: i0, uv: [0.0, 0.0], color },
+                Vertex { pos: o0, uv: [0.0, 1.0], color },
+                Vertex { pos: o1, uv: [1.0, 1.0], color },
+
+                Vertex { pos: i0, uv: [0.0, 0.0], color },
+                Vertex { pos: o1, uv: [1.0, 1.0], color },
+                Vertex { pos: i1, uv: [1.0, 0.0], color },
+            ]);
+        }
+    }
+
+    /// Rect with its corners rounded to `radius`, at most half its shorter
+    /// side; a radius of 0 draws a plain rect
+    pub fn round_rect(&mut self, x: f32, y: f32, w: f32, h: f32, radius: f32, color: Color) {
+        let radius = radius.min(w.min(h) * 0.5);
+        if radius <= 0.0 || radius.is_nan() {
+            self.rect(x, y, w, h, color);
+            return;
+        }
+        self.bind_texture(None);
+        let color = color.to_array();
+        for pos in round_rect_triangles(Rect::new(x, y, w, h), radius) {
+            let uv = [(pos[0] - x) / w, (pos[1] - y) / h];
+            self.vertices.push(Vertex { pos, uv, color });
+        }
+    }
+
+    /// Restrict drawing to `clip` until the matching `pop_clip`. Clip rects
+    /// are in the same top-left logical space as `rect`; a nested clip is
+    /// intersected with the one it is pushed inside.
+    pub fn push_clip(&mut self, clip: Rect) {
+        let clip = match self.clips.last() {
+            Some(outer) => {
+                let x = clip.x.max(outer.x);
+                let y = clip.y.max(outer.y);
+                let right = (clip.x + clip.width).min(outer.x + outer.width);
+                let bottom = (clip.y + clip.height).min(outer.y + outer.height);
+                Rect::new(x, y, (right - x).max(0.0), (bottom - y).max(0.0))
+            }
+            None => clip,
+        };
+        self.clips.push(clip);
+        self.apply_scissor(Some(clip));
+    }
+
+    /// Drop the innermost clip, restoring the one before it
+    pub fn pop_clip(&mut self) {
+        self.clips.pop();
+        self.apply_scissor(self.clips.last().copied());
+    }
+
+    fn apply_scissor(&mut self, clip: Option<Rect>) {
+        // Queued geometry belongs to the clip that was active when it was added
+        self.flush_batch();
         unsafe {
-            if let Some(loc) = self.u_color {
-                self.gl.uniform_4_f32(Some(&loc), color.r, color.g, color.b, color.a);
+            match clip {
+                Some(clip) => {
+                    // GL scissor boxes are in physical pixels from the bottom-left
+                    let region = self.region;
+                    let x = ((region.x + clip.x) * self.scale).floor();
+                    let y = ((self.viewport[1] - region.y - clip.y - clip.height) * self.scale).floor();
+                    let w = (clip.width * self.scale).ceil();
+                    let h = (clip.height * self.scale).ceil();
+                    self.gl.enable(glow::SCISSOR_TEST);
+                    self.gl.scissor(x as i32, y as i32, w.max(0.0) as i32, h.max(0.0) as i32);
+                }
+                None => self.gl.disable(glow::SCISSOR_TEST),
             }
         }
     }
 
+    // Uniform setters flush first so already-queued geometry keeps the old value
+
     pub fn set_time(&mut self, time: f32) {
+        self.flush_batch();
         unsafe {
             if let Some(loc) = self.u_time {
                 self.gl.uniform_1_f32(Some(&loc), time);
@@ -115,6 +548,7 @@ impl DrawContext {
     }
 
     pub fn set_effect_mode(&mut self, mode: i32) {
+        self.flush_batch();
         unsafe {
             if let Some(loc) = self.u_effect_mode {
                 self.gl.uniform_1_i32(Some(&loc), mode);
@@ -123,9 +557,32 @@ impl DrawContext {
     }
 
     pub fn set_progress(&mut self, progress: f32) {
+        self.flush_batch();
+        unsafe {
+            if let Some(loc) = self.u_progress {
+                self.gl.uniform_1_f32(Some(&loc), progress);
+            }
+        }
+    }
+
+    /// Multiplies the plasma effect's output; reset to white by `begin`
+    pub fn set_tint(&mut self, tint: Color) {
+        self.flush_batch();
         unsafe {
-            let loc = self.gl.get_uniform_location(self.program, "uProgress");
-            self.gl.uniform_1_f32(loc.as_ref(), progress);
+            if let Some(loc) = self.u_tint {
+                self.gl.uniform_4_f32(Some(&loc), tint.r, tint.g, tint.b, tint.a);
+            }
+        }
+    }
+
+    /// Multiplies the alpha of everything drawn after it, effects included;
+    /// reset to opaque by `begin`
+    pub fn set_opacity(&mut self, alpha: f32) {
+        self.flush_batch();
+        unsafe {
+            if let Some(loc) = self.u_global_alpha {
+                self.gl.uniform_1_f32(Some(&loc), alpha.clamp(0.0, 1.0));
+            }
         }
     }
 
@@ -137,47 +594,24 @@ impl DrawContext {
         unsafe {
             self.gl.bind_buffer(glow::ARRAY_BUFFER, Some(self.vbo));
 
-            let data = bytemuck::cast_slice(&self.vertices);
-            self.gl.buffer_data_u8_slice(
-                glow::ARRAY_BUFFER,
-                data,
-                glow::DYNAMIC_DRAW,
-            );
-
-            if let Some(vao) = self.vao {
-                self.gl.bind_vertex_array(Some(vao));
-            }
-
-            let a_pos = self.gl.get_attrib_location(self.program, "aPos");
-            let a_uv = self.gl.get_attrib_location(self.program, "aUV");
-
-            if let Some(a_pos) = a_pos {
-                self.gl.enable_vertex_attrib_array(a_pos);
-                self.gl.vertex_attrib_pointer_f32(
-                    a_pos,
-                    2,
-                    glow::FLOAT,
-                    false,
-                    std::mem::size_of::<Vertex>() as i32,
-                    0,
-                );
+            // Outgrowing the store orphans it for one twice the size; the
+            // driver frees the old one once draws still reading it finish
+            let data: &[u8] = bytemuck::cast_slice(&self.vertices);
+            if data.len() > self.vbo_capacity {
+                self.vbo_capacity = data.len().next_power_of_two();
+                self.gl.buffer_data_size(glow::ARRAY_BUFFER, self.vbo_capacity as i32, glow::DYNAMIC_DRAW);
             }
+            self.gl.buffer_sub_data_u8_slice(glow::ARRAY_BUFFER, 0, data);
 
-            if let Some(a_uv) = a_uv {
-                self.gl.enable_vertex_attrib_array(a_uv);
-                self.gl.vertex_attrib_pointer_f32(
-                    a_uv,
-                    2,
-                    glow::FLOAT,
-                    false,
-                    std::mem::size_of::<Vertex>() as i32,
-                    8,
-                );
+            // A VAO already captured the attribute pointers for our VBO
+            match self.vao {
+                Some(vao) => self.gl.bind_vertex_array(Some(vao)),
+                None => self.attribs.enable(&self.gl),
             }
 
             self.gl.draw_arrays(glow::TRIANGLES, 0, self.vertices.len() as i32);
 
-            if let Some(_vao) = self.vao {
+            if self.vao.is_some() {
                 self.gl.bind_vertex_array(None);
             }
         }
@@ -188,15 +622,197 @@ impl DrawContext {
     pub fn flush(&mut self) {
         self.flush_batch();
     }
+
+    /// RGBA bytes of the bottom-left `width`x`height` of the framebuffer,
+    /// top row first
+    pub fn read_pixels(&self, width: u32, height: u32) -> Vec<u8> {
+        let row = width as usize * 4;
+        let mut pixels = vec![0u8; row * height as usize];
+        unsafe {
+            self.gl.read_pixels(
+                0, 0, width as i32, height as i32,
+                glow::RGBA, glow::UNSIGNED_BYTE,
+                glow::PixelPackData::Slice(&mut pixels),
+            );
+        }
+        // GL rows run bottom-up
+        let mut flipped = Vec::with_capacity(pixels.len());
+        for line in pixels.chunks_exact(row).rev() {
+            flipped.extend_from_slice(line);
+        }
+        flipped
+    }
+}
+
+/// How much of slice `index`'s `extent` across the stack its end feathers
+/// leave out at the leading and trailing side. Where neighbours overlap only
+/// the wider one, nearer the middle of the stack, feathers, so no band of
+/// the ends is blended twice
+fn feather_trim(index: usize, count: usize, extent: f32) -> (f32, f32) {
+    let overlap = SLICE_OVERLAP.min(extent);
+    let middle = count / 2;
+    if index < middle {
+        (0.0, overlap)
+    } else if index > middle {
+        (overlap, 0.0)
+    } else {
+        (0.0, 0.0)
+    }
+}
+
+/// Corners of a `thickness` wide quad along the segment, in winding order:
+/// start and end on one side, then end and start on the other. `None` for
+/// a zero-length segment, which has no direction to thicken across.
+fn line_quad(x0: f32, y0: f32, x1: f32, y1: f32, thickness: f32) -> Option<[[f32; 2]; 4]> {
+    let (dx, dy) = (x1 - x0, y1 - y0);
+    let len = (dx * dx + dy * dy).sqrt();
+    if len <= f32::EPSILON {
+        return None;
+    }
+    // Unit normal scaled to half the thickness
+    let half = thickness * 0.5;
+    let (nx, ny) = (-dy / len * half, dx / len * half);
+    Some([
+        [x0 + nx, y0 + ny],
+        [x1 + nx, y1 + ny],
+        [x1 - nx, y1 - ny],
+        [x0 - nx, y0 - ny],
+    ])
+}
+
+/// Triangle corners for `rect` with corners rounded to `radius`, which has to
+/// fit: a cross of two rects plus a quarter fan in each corner
+fn round_rect_triangles(rect: Rect, radius: f32) -> Vec<[f32; 2]> {
+    use std::f32::consts::FRAC_PI_2;
+
+    let Rect { x, y, width, height } = rect;
+    let (x2, y2) = (x + width, y + height);
+    let quad = |x0: f32, y0: f32, x1: f32, y1: f32| [[x0, y0], [x1, y0], [x1, y1], [x0, y0], [x1, y1], [x0, y1]];
+    let mut triangles = Vec::new();
+    triangles.extend(quad(x + radius, y, x2 - radius, y2));
+    triangles.extend(quad(x, y + radius, x + radius, y2 - radius));
+    triangles.extend(quad(x2 - radius, y + radius, x2, y2 - radius));
+
+    // Roughly one slice per 2px of arc, like `arc`
+    let segments = ((radius * FRAC_PI_2 / 2.0).ceil() as usize).max(2);
+    let corners = [
+        (x2 - radius, y2 - radius, 0.0),
+        (x + radius, y2 - radius, FRAC_PI_2),
+        (x + radius, y + radius, FRAC_PI_2 * 2.0),
+        (x2 - radius, y + radius, FRAC_PI_2 * 3.0),
+    ];
+    for (cx, cy, start) in corners {
+        let rim = |i: usize| {
+            let angle = start + FRAC_PI_2 * i as f32 / segments as f32;
+            [cx + angle.cos() * radius, cy + angle.sin() * radius]
+        };
+        triangles.extend((0..segments).flat_map(|i| [[cx, cy], rim(i), rim(i + 1)]));
+    }
+    triangles
+}
+
+/// Triangle corners for a filled circle: centre, then two neighbouring rim
+/// points, for each of `segments` slices. Empty below three segments or
+/// without a positive radius.
+fn circle_fan(cx: f32, cy: f32, radius: f32, segments: usize) -> Vec<[f32; 2]> {
+    if segments < 3 || radius <= 0.0 {
+        return Vec::new();
+    }
+    let rim = |i: usize| {
+        let angle = std::f32::consts::TAU * (i % segments) as f32 / segments as f32;
+        [cx + angle.cos() * radius, cy + angle.sin() * radius]
+    };
+    (0..segments).flat_map(|i| [[cx, cy], rim(i), rim(i + 1)]).collect()
+}
+
+/// Two triangles covering `rect`, with `uv` spread over it and colors shaded
+/// from `top` to `bottom`
+fn quad(rect: Rect, uv: Rect, top: Color, bottom: Color) -> [Vertex; 6] {
+    let (x, y, x2, y2) = (rect.x, rect.y, rect.x + rect.width, rect.y + rect.height);
+    let (u, v, u2, v2) = (uv.x, uv.y, uv.x + uv.width, uv.y + uv.height);
+    let (top, bottom) = (top.to_array(), bottom.to_array());
+    [
+        Vertex { pos: [x, y], uv: [u, v], color: top },
+        Vertex { pos: [x2, y], uv: [u2, v], color: top },
+        Vertex { pos: [x2, y2], uv: [u2, v2], color: bottom },
+
+        Vertex { pos: [x, y], uv: [u, v], color: top },
+        Vertex { pos: [x2, y2], uv: [u2, v2], color: bottom },
+        Vertex { pos: [x, y2], uv: [u, v2], color: bottom },
+    ]
 }
 
 impl Drop for DrawContext {
     fn drop(&mut self) {
         unsafe {
+            for &texture in &self.textures {
+                self.gl.delete_texture(texture);
+            }
             self.gl.delete_buffer(self.vbo);
             if let Some(vao) = self.vao {
                 self.gl.delete_vertex_array(vao);
             }
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn horizontal_line_spans_its_thickness() {
+        let corners = line_quad(10.0, 20.0, 50.0, 20.0, 4.0).unwrap();
+        assert_eq!(corners, [[10.0, 22.0], [50.0, 22.0], [50.0, 18.0], [10.0, 18.0]]);
+    }
+
+    #[test]
+    fn zero_length_line_is_skipped() {
+        assert!(line_quad(5.0, 5.0, 5.0, 5.0, 2.0).is_none());
+    }
+
+    #[test]
+    fn quad_spreads_uv_over_its_corners() {
+        let white = Color::new(1.0, 1.0, 1.0, 1.0);
+        let vertices = quad(Rect::new(10.0, 20.0, 8.0, 16.0), Rect::new(0.25, 0.5, 0.25, 0.5), white, white);
+        assert_eq!((vertices[0].pos, vertices[0].uv), ([10.0, 20.0], [0.25, 0.5]));
+        assert_eq!((vertices[2].pos, vertices[2].uv), ([18.0, 36.0], [0.5, 1.0]));
+        assert_eq!((vertices[5].pos, vertices[5].uv), ([10.0, 36.0], [0.25, 1.0]));
+    }
+
+    #[test]
+    fn round_rect_corners_stay_inside_the_rect() {
+        let rect = Rect::new(10.0, 20.0, 40.0, 16.0);
+        let triangles = round_rect_triangles(rect, 8.0);
+        assert_eq!(triangles.len() % 3, 0);
+        assert!(triangles.iter().all(|&[x, y]| {
+            (10.0 - 1e-3..=50.0 + 1e-3).contains(&x) && (20.0 - 1e-3..=36.0 + 1e-3).contains(&y)
+        }));
+        // The corner itself is cut away
+        assert!(!triangles.contains(&[10.0, 20.0]));
+    }
+
+    #[test]
+    fn slice_feathers_cover_each_overlap_once() {
+        // Slices stacked like the digit segments: a stride apart, each
+        // SLICE_OVERLAP longer than the stride
+        for count in [19, 20] {
+            let stride = 0.6;
+            let extent = stride + SLICE_OVERLAP;
+            let bands: Vec<(f32, f32)> = (0..count).map(|i| {
+                let (lead, trail) = feather_trim(i, count, extent);
+                let start = i as f32 * stride;
+                (start + lead, start + extent - trail)
+            }).collect();
+            for pair in bands.windows(2) {
+                let (end, next_start) = (pair[0].1, pair[1].0);
+                assert!((end - next_start).abs() < 1e-4, "count {count}: {pair:?} leave a gap or overlap");
+            }
+        }
+    }
+
+    #[test]
+    fn circle_fan_has_three_vertices_per_segment() {
+        assert_eq!(circle_fan(0.0, 0.0, 4.0, 16).len(), 16 * 3);
+        assert!(circle_fan(0.0, 0.0, 4.0, 2).is_empty());
+    }
+}
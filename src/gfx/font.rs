@@ -0,0 +1,184 @@
+use super::math::Rect;
+
+/// Size of a glyph in font pixels
+pub const GLYPH_WIDTH: u32 = 5;
+pub const GLYPH_HEIGHT: u32 = 7;
+
+/// Horizontal distance from one glyph to the next, a pixel of gap included
+const ADVANCE: u32 = GLYPH_WIDTH + 1;
+
+/// Printable ASCII from ' ' to '~', one byte per column, least significant
+/// bit at the top
+const GLYPHS: [[u8; 5]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00], // space
+    [0x00, 0x00, 0x5f, 0x00, 0x00], // !
+    [0x00, 0x07, 0x00, 0x07, 0x00], // "
+    [0x14, 0x7f, 0x14, 0x7f, 0x14], // #
+    [0x24, 0x2a, 0x7f, 0x2a, 0x12], // $
+    [0x23, 0x13, 0x08, 0x64, 0x62], // %
+    [0x36, 0x49, 0x55, 0x22, 0x50], // &
+    [0x00, 0x05, 0x03, 0x00, 0x00], // '
+    [0x00, 0x1c, 0x22, 0x41, 0x00], // (
+    [0x00, 0x41, 0x22, 0x1c, 0x00], // )
+    [0x08, 0x2a, 0x1c, 0x2a, 0x08], // *
+    [0x08, 0x08, 0x3e, 0x08, 0x08], // +
+    [0x00, 0x50, 0x30, 0x00, 0x00], // ,
+    [0x08, 0x08, 0x08, 0x08, 0x08], // -
+    [0x00, 0x60, 0x60, 0x00, 0x00], // .
+    [0x20, 0x10, 0x08, 0x04, 0x02], // /
+    [0x3e, 0x51, 0x49, 0x45, 0x3e], // 0
+    [0x00, 0x42, 0x7f, 0x40, 0x00], // 1
+    [0x42, 0x61, 0x51, 0x49, 0x46], // 2
+    [0x21, 0x41, 0x45, 0x4b, 0x31], // 3
+    [0x18, 0x14, 0x12, 0x7f, 0x10], // 4
+    [0x27, 0x45, 0x45, 0x45, 0x39], // 5
+    [0x3c, 0x4a, 0x49, 0x49, 0x30], // 6
+    [0x01, 0x71, 0x09, 0x05, 0x03], // 7
+    [0x36, 0x49, 0x49, 0x49, 0x36], // 8
+    [0x06, 0x49, 0x49, 0x29, 0x1e], // 9
+    [0x00, 0x36, 0x36, 0x00, 0x00], // :
+    [0x00, 0x56, 0x36, 0x00, 0x00], // ;
+    [0x00, 0x08, 0x14, 0x22, 0x41], // <
+    [0x14, 0x14, 0x14, 0x14, 0x14], // =
+    [0x41, 0x22, 0x14, 0x08, 0x00], // >
+    [0x02, 0x01, 0x51, 0x09, 0x06], // ?
+    [0x32, 0x49, 0x79, 0x41, 0x3e], // @
+    [0x7e, 0x11, 0x11, 0x11, 0x7e], // A
+    [0x7f, 0x49, 0x49, 0x49, 0x36], // B
+    [0x3e, 0x41, 0x41, 0x41, 0x22], // C
+    [0x7f, 0x41, 0x41, 0x22, 0x1c], // D
+    [0x7f, 0x49, 0x49, 0x49, 0x41], // E
+    [0x7f, 0x09, 0x09, 0x01, 0x01], // F
+    [0x3e, 0x41, 0x41, 0x51, 0x32], // G
+    [0x7f, 0x08, 0x08, 0x08, 0x7f], // H
+    [0x00, 0x41, 0x7f, 0x41, 0x00], // I
+    [0x20, 0x40, 0x41, 0x3f, 0x01], // J
+    [0x7f, 0x08, 0x14, 0x22, 0x41], // K
+    [0x7f, 0x40, 0x40, 0x40, 0x40], // L
+    [0x7f, 0x02, 0x04, 0x02, 0x7f], // M
+    [0x7f, 0x04, 0x08, 0x10, 0x7f], // N
+    [0x3e, 0x41, 0x41, 0x41, 0x3e], // O
+    [0x7f, 0x09, 0x09, 0x09, 0x06], // P
+    [0x3e, 0x41, 0x51, 0x21, 0x5e], // Q
+    [0x7f, 0x09, 0x19, 0x29, 0x46], // R
+    [0x46, 0x49, 0x49, 0x49, 0x31], // S
+    [0x01, 0x01, 0x7f, 0x01, 0x01], // T
+    [0x3f, 0x40, 0x40, 0x40, 0x3f], // U
+    [0x1f, 0x20, 0x40, 0x20, 0x1f], // V
+    [0x7f, 0x20, 0x18, 0x20, 0x7f], // W
+    [0x63, 0x14, 0x08, 0x14, 0x63], // X
+    [0x03, 0x04, 0x78, 0x04, 0x03], // Y
+    [0x61, 0x51, 0x49, 0x45, 0x43], // Z
+    [0x00, 0x00, 0x7f, 0x41, 0x41], // [
+    [0x02, 0x04, 0x08, 0x10, 0x20], // \
+    [0x41, 0x41, 0x7f, 0x00, 0x00], // ]
+    [0x04, 0x02, 0x01, 0x02, 0x04], // ^
+    [0x40, 0x40, 0x40, 0x40, 0x40], // _
+    [0x00, 0x01, 0x02, 0x04, 0x00], // `
+    [0x20, 0x54, 0x54, 0x54, 0x78], // a
+    [0x7f, 0x48, 0x44, 0x44, 0x38], // b
+    [0x38, 0x44, 0x44, 0x44, 0x20], // c
+    [0x38, 0x44, 0x44, 0x48, 0x7f], // d
+    [0x38, 0x54, 0x54, 0x54, 0x18], // e
+    [0x08, 0x7e, 0x09, 0x01, 0x02], // f
+    [0x08, 0x14, 0x54, 0x54, 0x3c], // g
+    [0x7f, 0x08, 0x04, 0x04, 0x78], // h
+    [0x00, 0x44, 0x7d, 0x40, 0x00], // i
+    [0x20, 0x40, 0x44, 0x3d, 0x00], // j
+    [0x00, 0x7f, 0x10, 0x28, 0x44], // k
+    [0x00, 0x41, 0x7f, 0x40, 0x00], // l
+    [0x7c, 0x04, 0x18, 0x04, 0x78], // m
+    [0x7c, 0x08, 0x04, 0x04, 0x78], // n
+    [0x38, 0x44, 0x44, 0x44, 0x38], // o
+    [0x7c, 0x14, 0x14, 0x14, 0x08], // p
+    [0x08, 0x14, 0x14, 0x18, 0x7c], // q
+    [0x7c, 0x08, 0x04, 0x04, 0x08], // r
+    [0x48, 0x54, 0x54, 0x54, 0x20], // s
+    [0x04, 0x3f, 0x44, 0x40, 0x20], // t
+    [0x3c, 0x40, 0x40, 0x20, 0x7c], // u
+    [0x1c, 0x20, 0x40, 0x20, 0x1c], // v
+    [0x3c, 0x40, 0x30, 0x40, 0x3c], // w
+    [0x44, 0x28, 0x10, 0x28, 0x44], // x
+    [0x0c, 0x50, 0x50, 0x50, 0x3c], // y
+    [0x44, 0x64, 0x54, 0x4c, 0x44], // z
+    [0x00, 0x08, 0x36, 0x41, 0x00], // {
+    [0x00, 0x00, 0x7f, 0x00, 0x00], // |
+    [0x00, 0x41, 0x36, 0x08, 0x00], // }
+    [0x02, 0x01, 0x02, 0x04, 0x02], // ~
+
+];
+
+/// Index into `GLYPHS`; anything outside printable ASCII draws as '?'
+fn glyph_index(c: char) -> usize {
+    match c {
+        ' '..='~' => c as usize - ' ' as usize,
+        _ => '?' as usize - ' ' as usize,
+    }
+}
+
+/// RGBA pixels of every glyph side by side in `ADVANCE`-wide cells, white
+/// where lit and transparent elsewhere, with the atlas width and height
+pub fn atlas() -> (Vec<u8>, u32, u32) {
+    let (width, height) = (ADVANCE * GLYPHS.len() as u32, GLYPH_HEIGHT);
+    let mut rgba = vec![0u8; (width * height * 4) as usize];
+    for (index, columns) in GLYPHS.iter().enumerate() {
+        for (column, bits) in columns.iter().enumerate() {
+            for row in 0..GLYPH_HEIGHT {
+                if bits & (1 << row) != 0 {
+                    let x = index as u32 * ADVANCE + column as u32;
+                    let offset = ((row * width + x) * 4) as usize;
+                    rgba[offset..offset + 4].copy_from_slice(&[255; 4]);
+                }
+            }
+        }
+    }
+    (rgba, width, height)
+}
+
+/// Where `c` sits in the `atlas`, in 0-1 texture space
+pub fn glyph_uv(c: char) -> Rect {
+    let width = (ADVANCE * GLYPHS.len() as u32) as f32;
+    let x = (glyph_index(c) as u32 * ADVANCE) as f32;
+    Rect::new(x / width, 0.0, GLYPH_WIDTH as f32 / width, 1.0)
+}
+
+/// Logical width of `text` drawn with font pixels `scale` units square,
+/// without a gap after the last glyph
+pub fn text_width(text: &str, scale: f32) -> f32 {
+    let glyphs = text.chars().count() as u32;
+    (glyphs * ADVANCE).saturating_sub(1) as f32 * scale
+}
+
+/// Offset of each glyph of `text` from its start, in font pixels
+pub fn glyph_offsets(text: &str) -> impl Iterator<Item = (char, f32)> + '_ {
+    text.chars().enumerate().map(|(i, c)| (c, (i as u32 * ADVANCE) as f32))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lit(rgba: &[u8], width: u32, x: u32, y: u32) -> bool {
+        rgba[((y * width + x) * 4 + 3) as usize] == 255
+    }
+
+    #[test]
+    fn atlas_lights_the_glyph_bits() {
+        let (rgba, width, height) = atlas();
+        assert_eq!((width, height), (95 * ADVANCE, GLYPH_HEIGHT));
+
+        // 'I' is a full-height bar in its middle column with serifs beside it
+        let x = glyph_index('I') as u32 * ADVANCE;
+        assert!((0..GLYPH_HEIGHT).all(|y| lit(&rgba, width, x + 2, y)));
+        assert!(lit(&rgba, width, x + 1, 0) && !lit(&rgba, width, x + 1, 3));
+        // The gap column stays dark
+        assert!((0..GLYPH_HEIGHT).all(|y| !lit(&rgba, width, x + GLYPH_WIDTH, y)));
+    }
+
+    #[test]
+    fn unknown_characters_draw_as_question_marks() {
+        assert_eq!(glyph_uv('\u{e9}').x, glyph_uv('?').x);
+        assert_eq!(text_width("ab", 2.0), 22.0);
+        assert_eq!(text_width("", 2.0), 0.0);
+    }
+}
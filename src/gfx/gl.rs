@@ -1,5 +1,9 @@
-use anyhow::Result;
+use crate::error::{CornaError, Result};
 use glow::HasContext;
+use std::path::PathBuf;
+
+const UI_VERT_SRC: &str = include_str!("../../assets/shaders/ui.vert.glsl");
+const UI_FRAG_SRC: &str = include_str!("../../assets/shaders/ui.frag.glsl");
 
 pub fn compile_shader(
     gl: &glow::Context,
@@ -8,14 +12,20 @@ pub fn compile_shader(
 ) -> Result<glow::Shader> {
     unsafe {
         let shader = gl.create_shader(shader_type)
-            .map_err(|e| anyhow::anyhow!("Failed to create shader: {}", e))?;
-        gl.shader_source(shader, source);
+            .map_err(|e| CornaError::Shader(format!("failed to create shader: {}", e)))?;
+        // Desktop GL reads unversioned shaders as GLSL 1.10, which rejects
+        // precision qualifiers; ask for GLSL ES 1.00 (ARB_ES2_compatibility)
+        if gl.version().is_embedded || source.trim_start().starts_with("#version") {
+            gl.shader_source(shader, source);
+        } else {
+            gl.shader_source(shader, &format!("#version 100\n{}", source));
+        }
         gl.compile_shader(shader);
 
         if !gl.get_shader_compile_status(shader) {
             let info = gl.get_shader_info_log(shader);
             gl.delete_shader(shader);
-            anyhow::bail!("Shader compilation failed: {}", info);
+            return Err(CornaError::Shader(format!("compilation failed: {}", info)));
         }
 
         Ok(shader)
@@ -29,7 +39,7 @@ pub fn link_program(
 ) -> Result<glow::Program> {
     unsafe {
         let program = gl.create_program()
-            .map_err(|e| anyhow::anyhow!("Failed to create program: {}", e))?;
+            .map_err(|e| CornaError::Shader(format!("failed to create program: {}", e)))?;
         gl.attach_shader(program, vertex_shader);
         gl.attach_shader(program, fragment_shader);
         gl.link_program(program);
@@ -37,7 +47,7 @@ pub fn link_program(
         if !gl.get_program_link_status(program) {
             let info = gl.get_program_info_log(program);
             gl.delete_program(program);
-            anyhow::bail!("Program linking failed: {}", info);
+            return Err(CornaError::Shader(format!("program linking failed: {}", info)));
         }
 
         gl.detach_shader(program, vertex_shader);
@@ -58,4 +68,22 @@ pub fn load_shader_program(gl: &glow::Context, vert_src: &str, frag_src: &str) -
     }
 
     Ok(program)
+}
+
+/// Vertex and fragment sources for the UI program. They are compiled into the
+/// binary; setting `CORNA_SHADER_DIR` loads them from that directory instead,
+/// which is handy while iterating on the shaders.
+pub fn ui_shader_sources() -> Result<(String, String)> {
+    let Some(dir) = std::env::var_os("CORNA_SHADER_DIR") else {
+        return Ok((UI_VERT_SRC.to_string(), UI_FRAG_SRC.to_string()));
+    };
+
+    let dir = PathBuf::from(dir);
+    let read = |name: &str| {
+        let path = dir.join(name);
+        std::fs::read_to_string(&path)
+            .map_err(|source| CornaError::Io { path, source })
+    };
+    log::info!("Loading shaders from {}", dir.display());
+    Ok((read("ui.vert.glsl")?, read("ui.frag.glsl")?))
 }
\ No newline at end of file
@@ -1,3 +1,5 @@
+use anyhow::Result;
+
 #[derive(Clone, Copy, Debug)]
 pub struct Vec2 {
     pub x: f32,
@@ -23,12 +25,25 @@ impl Rect {
         Self { x, y, width, height }
     }
 
+    /// Edges count as inside
     pub fn contains(&self, point: Vec2) -> bool {
         point.x >= self.x
             && point.x <= self.x + self.width
             && point.y >= self.y
             && point.y <= self.y + self.height
     }
+
+    pub fn center(&self) -> Vec2 {
+        Vec2::new(self.x + self.width * 0.5, self.y + self.height * 0.5)
+    }
+
+    /// Shrunk by `amount` on every side, or grown for a negative amount;
+    /// never narrower than zero
+    pub fn inset(&self, amount: f32) -> Rect {
+        let width = (self.width - amount * 2.0).max(0.0);
+        let height = (self.height - amount * 2.0).max(0.0);
+        Rect::new(self.x + (self.width - width) * 0.5, self.y + (self.height - height) * 0.5, width, height)
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -52,4 +67,77 @@ impl Color {
             a: a as f32 / 255.0,
         }
     }
-}
\ No newline at end of file
+
+    /// Blend from `a` to `b` in linear light, so midpoints don't dip dark the
+    /// way blending the sRGB values directly does. Alpha blends as is.
+    pub fn lerp(a: Color, b: Color, t: f32) -> Color {
+        let mix = |x: f32, y: f32| {
+            let (x, y) = (srgb_to_linear(x), srgb_to_linear(y));
+            linear_to_srgb(x + (y - x) * t)
+        };
+        Color::new(mix(a.r, b.r), mix(a.g, b.g), mix(a.b, b.b), a.a + (b.a - a.a) * t)
+    }
+
+    pub fn with_alpha(self, a: f32) -> Self {
+        Self { a, ..self }
+    }
+
+    pub fn to_array(self) -> [f32; 4] {
+        [self.r, self.g, self.b, self.a]
+    }
+
+    /// Parse `#rrggbb` or `#rrggbbaa` (leading `#` optional)
+    pub fn from_hex(hex: &str) -> Result<Self> {
+        let digits = hex.trim().trim_start_matches('#');
+        if !matches!(digits.len(), 6 | 8) || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            anyhow::bail!("Invalid hex color '{}', expected #rrggbb or #rrggbbaa", hex);
+        }
+
+        let channel = |i: usize| u8::from_str_radix(&digits[i..i + 2], 16);
+        let alpha = if digits.len() == 8 { channel(6)? } else { 255 };
+        Ok(Self::rgba(channel(0)?, channel(2)?, channel(4)?, alpha))
+    }
+}
+
+fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn contains_includes_edges() {
+        let rect = Rect::new(10.0, 20.0, 30.0, 40.0);
+        assert!(rect.contains(Vec2::new(10.0, 20.0)));
+        assert!(rect.contains(Vec2::new(40.0, 60.0)));
+        assert!(rect.contains(Vec2::new(25.0, 60.0)));
+        assert!(!rect.contains(Vec2::new(9.9, 30.0)));
+        assert!(!rect.contains(Vec2::new(25.0, 60.1)));
+    }
+
+    #[test]
+    fn inset_keeps_the_center() {
+        let rect = Rect::new(0.0, 0.0, 10.0, 4.0);
+        let inner = rect.inset(1.0);
+        assert_eq!((inner.x, inner.y, inner.width, inner.height), (1.0, 1.0, 8.0, 2.0));
+        assert_eq!((inner.center().x, inner.center().y), (5.0, 2.0));
+
+        // Collapses to the center instead of turning inside out
+        let empty = rect.inset(3.0);
+        assert_eq!((empty.x, empty.y, empty.width, empty.height), (3.0, 2.0, 4.0, 0.0));
+    }
+}
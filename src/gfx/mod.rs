@@ -1,4 +1,5 @@
 pub mod anim;
 pub mod draw;
+pub mod font;
 pub mod gl;
 pub mod math;
\ No newline at end of file
@@ -0,0 +1,278 @@
+use crate::app::App;
+use crate::config::Config;
+use crate::features::clock::Clock;
+use crate::gfx::{draw::DrawContext, gl::{load_shader_program, ui_shader_sources}, math::Rect};
+use crate::state::State;
+use crate::stats::Stats;
+use crate::wayland::egl::EglContext;
+use crate::wayland::window_manager::WindowId;
+use anyhow::{Context, Result};
+use glow::HasContext;
+use log::info;
+use std::path::Path;
+use time::{Date, Month};
+
+/// Render one clock frame at a fixed time to a PNG, without a compositor
+pub fn render_frame(config: Config, out: &Path) -> Result<()> {
+    let mut scene = Scene::new(config)?;
+    scene.render();
+
+    let [width, height] = scene.size;
+    let pixels = scene.draw.read_pixels(width, height);
+    std::fs::write(out, encode_png(width, height, &pixels))
+        .with_context(|| format!("writing {}", out.display()))?;
+    info!("Rendered {}x{} frame to {}", width, height, out.display());
+    Ok(())
+}
+
+/// Render the `render_frame` scene `frames` times and print the frame rate
+#[cfg(feature = "bench")]
+pub fn bench(config: Config, frames: u32) -> Result<()> {
+    let mut scene = Scene::new(config)?;
+    // One frame outside the timing for shader and font atlas warm-up
+    scene.render();
+    scene.draw.read_pixels(1, 1);
+
+    let start = std::time::Instant::now();
+    for _ in 0..frames {
+        scene.render();
+    }
+    // Reading back waits for the GPU to finish the queued frames
+    scene.draw.read_pixels(1, 1);
+    let elapsed = start.elapsed().as_secs_f64();
+    println!(
+        "{} frames of {}x{} in {:.3}s: {:.1} fps",
+        frames, scene.size[0], scene.size[1], elapsed, frames as f64 / elapsed
+    );
+    Ok(())
+}
+
+/// The clock fixed at 10:08:42 on a pbuffer of its size
+struct Scene {
+    app: App,
+    draw: DrawContext,
+    size: [u32; 2],
+    // Dropped last, the GL context goes with it
+    _egl: EglContext,
+}
+
+impl Scene {
+    fn new(config: Config) -> Result<Self> {
+        let mut app = App::new(config, State::default(), Stats::default());
+        app.set_scale(1.0);
+
+        // 10:08:42 reads every segment position and keeps snapshots stable
+        let time = Date::from_calendar_date(2024, Month::January, 1)?
+            .with_hms(10, 8, 42)?
+            .assume_utc();
+        if let Some(clock) = app.feature_mut::<Clock>() {
+            clock.set_fixed_time(time);
+        }
+        // Second update lets the digit flip settle
+        app.update(0.0);
+        app.update(1.0);
+
+        let size = app.get_current_size();
+        let egl = EglContext::new_pbuffer(size[0] as i32, size[1] as i32)?;
+        let gl = unsafe {
+            glow::Context::from_loader_function(|s| egl.get_proc_address(s))
+        };
+        bind_offscreen_target(&gl, size)?;
+        let (vert_src, frag_src) = ui_shader_sources()?;
+        let program = load_shader_program(&gl, &vert_src, &frag_src)?;
+        let mut draw = DrawContext::new(gl, program)?;
+        draw.set_antialias(app.config.antialias);
+        draw.set_clear_color(app.config.theme.surface_color());
+        Ok(Self { app, draw, size, _egl: egl })
+    }
+
+    fn render(&mut self) {
+        let size = [self.size[0] as f32, self.size[1] as f32];
+        self.draw.begin(size, 1.0);
+        self.draw.set_time(self.app.time);
+        self.app.render(WindowId::Clock, &mut self.draw, Rect::new(0.0, 0.0, size[0], size[1]));
+        self.draw.flush();
+    }
+}
+
+/// Point drawing at a texture-backed RGBA8 framebuffer of `size`, so what
+/// gets read back doesn't depend on the format of the pbuffer's config. It
+/// stays bound; the pbuffer only keeps the context current
+fn bind_offscreen_target(gl: &glow::Context, size: [u32; 2]) -> Result<()> {
+    unsafe {
+        let texture = gl.create_texture().map_err(|e| anyhow::anyhow!("Failed to create texture: {}", e))?;
+        gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+        gl.tex_image_2d(
+            glow::TEXTURE_2D, 0, glow::RGBA as i32, size[0] as i32, size[1] as i32, 0,
+            glow::RGBA, glow::UNSIGNED_BYTE, None,
+        );
+        gl.bind_texture(glow::TEXTURE_2D, None);
+
+        let framebuffer = gl.create_framebuffer().map_err(|e| anyhow::anyhow!("Failed to create framebuffer: {}", e))?;
+        gl.bind_framebuffer(glow::FRAMEBUFFER, Some(framebuffer));
+        gl.framebuffer_texture_2d(glow::FRAMEBUFFER, glow::COLOR_ATTACHMENT0, glow::TEXTURE_2D, Some(texture), 0);
+        let status = gl.check_framebuffer_status(glow::FRAMEBUFFER);
+        if status != glow::FRAMEBUFFER_COMPLETE {
+            anyhow::bail!("Offscreen framebuffer incomplete (status {:#x})", status);
+        }
+    }
+    Ok(())
+}
+
+/// Minimal RGBA8 PNG encoder using stored (uncompressed) deflate blocks;
+/// snapshots are small enough that compression isn't worth a dependency
+fn encode_png(width: u32, height: u32, rgba: &[u8]) -> Vec<u8> {
+    // Each scanline is prefixed with filter type 0 (none)
+    let row = width as usize * 4;
+    let mut raw = Vec::with_capacity((row + 1) * height as usize);
+    for line in rgba.chunks_exact(row) {
+        raw.push(0);
+        raw.extend_from_slice(line);
+    }
+
+    let mut zlib = vec![0x78, 0x01];
+    let mut blocks = raw.chunks(0xFFFF).peekable();
+    if blocks.peek().is_none() {
+        zlib.extend_from_slice(&[1, 0, 0, 0xFF, 0xFF]);
+    }
+    while let Some(block) = blocks.next() {
+        let len = block.len() as u16;
+        zlib.push(blocks.peek().is_none() as u8);
+        zlib.extend_from_slice(&len.to_le_bytes());
+        zlib.extend_from_slice(&(!len).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+    zlib.extend_from_slice(&adler32(&raw).to_be_bytes());
+
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&width.to_be_bytes());
+    ihdr.extend_from_slice(&height.to_be_bytes());
+    // 8-bit RGBA, deflate, adaptive filtering, no interlace
+    ihdr.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    write_chunk(&mut png, b"IHDR", &ihdr);
+    write_chunk(&mut png, b"IDAT", &zlib);
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in bytes {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reference frame for `snapshot_matches_reference`; regenerate it with
+    /// `CORNA_UPDATE_SNAPSHOTS=1 cargo test` after an intended visual change
+    const SNAPSHOT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/snapshots/clock.png");
+
+    #[test]
+    fn checksums_match_known_vectors() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b"IEND"), 0xAE42_6082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+        assert_eq!(adler32(b""), 1);
+    }
+
+    #[test]
+    fn png_round_trips_through_stored_blocks() {
+        // Over 64 KiB of scanlines spans several stored deflate blocks
+        let (width, height) = (130, 130);
+        let rgba: Vec<u8> = (0..width * height * 4).map(|i| (i * 7 % 251) as u8).collect();
+        let png = encode_png(width, height, &rgba);
+
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(&png[png.len() - 12..], b"\0\0\0\0IEND\xAE\x42\x60\x82");
+        assert_eq!(decode_png(&png), (width, height, rgba));
+    }
+
+    #[test]
+    fn snapshot_matches_reference() {
+        let mut scene = match Scene::new(Config::default()) {
+            Ok(scene) => scene,
+            Err(e) => {
+                eprintln!("skipping snapshot test, no headless EGL: {:#}", e);
+                return;
+            }
+        };
+        scene.render();
+        let [width, height] = scene.size;
+        let pixels = scene.draw.read_pixels(width, height);
+
+        if std::env::var_os("CORNA_UPDATE_SNAPSHOTS").is_some() {
+            std::fs::write(SNAPSHOT, encode_png(width, height, &pixels)).unwrap();
+            return;
+        }
+        let reference = std::fs::read(SNAPSHOT).expect("missing snapshot, run with CORNA_UPDATE_SNAPSHOTS=1");
+        let (ref_width, ref_height, ref_pixels) = decode_png(&reference);
+        assert_eq!([width, height], [ref_width, ref_height], "clock size changed");
+
+        // Drivers rasterize edges a little differently; only count pixels
+        // that are clearly off
+        let off = pixels.chunks_exact(4).zip(ref_pixels.chunks_exact(4))
+            .filter(|(a, b)| a.iter().zip(*b).any(|(a, b)| a.abs_diff(*b) > 16))
+            .count();
+        let allowed = (width * height) as usize / 200;
+        assert!(off <= allowed, "{} of {} pixels differ from {}", off, width * height, SNAPSHOT);
+    }
+
+    /// Reads back what `encode_png` writes (stored blocks, no filtering)
+    fn decode_png(png: &[u8]) -> (u32, u32, Vec<u8>) {
+        let be32 = |b: &[u8]| u32::from_be_bytes(b[..4].try_into().unwrap());
+        let (mut size, mut zlib) = ([0, 0], Vec::new());
+        let mut rest = &png[8..];
+        while !rest.is_empty() {
+            let len = be32(rest) as usize;
+            let data = &rest[8..8 + len];
+            match &rest[4..8] {
+                b"IHDR" => size = [be32(data), be32(&data[4..])],
+                b"IDAT" => zlib.extend_from_slice(data),
+                _ => {}
+            }
+            assert_eq!(be32(&rest[8 + len..]), crc32(&rest[4..8 + len]));
+            rest = &rest[12 + len..];
+        }
+
+        let mut raw = Vec::new();
+        let mut blocks = &zlib[2..];
+        loop {
+            let len = u16::from_le_bytes([blocks[1], blocks[2]]) as usize;
+            raw.extend_from_slice(&blocks[5..5 + len]);
+            if blocks[0] & 1 == 1 {
+                break;
+            }
+            blocks = &blocks[5 + len..];
+        }
+        let row = size[0] as usize * 4;
+        let pixels = raw.chunks_exact(row + 1).flat_map(|line| line[1..].to_vec()).collect();
+        (size[0], size[1], pixels)
+    }
+}
@@ -0,0 +1,131 @@
+use log::warn;
+use std::collections::{BTreeMap, HashMap};
+use xkbcommon::xkb::{self, keysyms};
+
+/// Modifier state that bindings can require; lock keys are ignored
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    pub logo: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    ToggleSeconds,
+    /// Starts a pomodoro, or stops the one that's running
+    StartPomodoro,
+    Pause,
+    CycleColor,
+    /// Switch between the digital and analog clock
+    ToggleFace,
+    /// Start a count-up stopwatch, or stop the running one
+    ToggleStopwatch,
+    /// Select the next longer/shorter pomodoro duration
+    LongerDuration,
+    ShorterDuration,
+    /// Hide the clock while a pomodoro runs, see `pomodoro.hide_clock_while_counting`
+    ToggleFocus,
+    Quit,
+}
+
+impl Action {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "toggle_seconds" => Some(Action::ToggleSeconds),
+            "start_pomodoro" => Some(Action::StartPomodoro),
+            "pause" => Some(Action::Pause),
+            "cycle_color" => Some(Action::CycleColor),
+            "toggle_face" => Some(Action::ToggleFace),
+            "toggle_stopwatch" => Some(Action::ToggleStopwatch),
+            "longer_duration" => Some(Action::LongerDuration),
+            "shorter_duration" => Some(Action::ShorterDuration),
+            "toggle_focus" => Some(Action::ToggleFocus),
+            "quit" => Some(Action::Quit),
+            _ => None,
+        }
+    }
+}
+
+pub fn default_keybindings() -> BTreeMap<String, String> {
+    [
+        ("toggle_seconds", "space"),
+        ("start_pomodoro", "Return"),
+        ("pause", "p"),
+        ("cycle_color", "c"),
+        ("toggle_face", "a"),
+        ("toggle_stopwatch", "s"),
+        ("longer_duration", "Up"),
+        ("shorter_duration", "Down"),
+        ("toggle_focus", "f"),
+        ("quit", "ctrl+q"),
+    ]
+    .into_iter()
+    .map(|(action, key)| (action.to_string(), key.to_string()))
+    .collect()
+}
+
+/// Key combos resolved to actions, built once from the `[keybindings]` table
+#[derive(Debug, Default)]
+pub struct Keybindings {
+    map: HashMap<(Modifiers, u32), Action>,
+}
+
+impl Keybindings {
+    /// Actions missing from `config` keep their default key
+    pub fn from_config(config: &BTreeMap<String, String>) -> Self {
+        let mut merged = default_keybindings();
+        merged.extend(config.iter().map(|(action, key)| (action.clone(), key.clone())));
+
+        let mut map = HashMap::new();
+        for (name, key) in &merged {
+            let Some(action) = Action::from_name(name) else {
+                warn!("Unknown keybinding action '{}'", name);
+                continue;
+            };
+            let Some(combo) = parse_combo(key) else {
+                warn!("Unknown key '{}' for keybinding '{}'", key, name);
+                continue;
+            };
+            if let Some(previous) = map.insert(combo, action) {
+                warn!("Key '{}' is bound to both {:?} and {:?}; using {:?}", key, previous, action, action);
+            }
+        }
+        Self { map }
+    }
+
+    pub fn lookup(&self, modifiers: Modifiers, keysym: u32) -> Option<Action> {
+        self.map.get(&(modifiers, normalize_keysym(keysym))).copied()
+    }
+}
+
+/// Parse `"ctrl+shift+p"`-style strings into modifiers and a keysym
+fn parse_combo(combo: &str) -> Option<(Modifiers, u32)> {
+    let mut modifiers = Modifiers::default();
+    let mut parts: Vec<&str> = combo.split('+').map(str::trim).collect();
+    let key = parts.pop().filter(|k| !k.is_empty())?;
+    for part in parts {
+        match part.to_ascii_lowercase().as_str() {
+            "ctrl" | "control" => modifiers.ctrl = true,
+            "alt" => modifiers.alt = true,
+            "shift" => modifiers.shift = true,
+            "super" | "logo" => modifiers.logo = true,
+            _ => return None,
+        }
+    }
+
+    let keysym = xkb::keysym_from_name(key, xkb::KEYSYM_CASE_INSENSITIVE).raw();
+    if keysym == 0 {
+        return None;
+    }
+    Some((modifiers, normalize_keysym(keysym)))
+}
+
+/// Letters match regardless of shift or caps lock
+fn normalize_keysym(keysym: u32) -> u32 {
+    match keysym {
+        keysyms::KEY_A..=keysyms::KEY_Z => keysym - keysyms::KEY_A + keysyms::KEY_a,
+        _ => keysym,
+    }
+}
@@ -0,0 +1,57 @@
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+
+/// An flock on `$XDG_RUNTIME_DIR/corna-<hash>.lock`, held for as long as
+/// this lives, so a second corna on the same config can tell one is running.
+/// The kernel drops it when the process exits, however that happens.
+pub struct InstanceLock {
+    file: File,
+}
+
+impl InstanceLock {
+    /// Fails when another instance holds the lock for `config`, or for the
+    /// default config when that's None. Each config file gets a lock of its
+    /// own, so one instance per monitor still works.
+    pub fn acquire(config: Option<&Path>) -> Result<Self> {
+        let path = lock_path(config)?;
+        let file = File::options().create(true).truncate(false).write(true).open(&path)
+            .with_context(|| format!("opening {}", path.display()))?;
+        if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } != 0 {
+            let error = std::io::Error::last_os_error();
+            if error.kind() == std::io::ErrorKind::WouldBlock {
+                anyhow::bail!(
+                    "corna is already running with this config ({} is locked); pass --allow-multiple to start another",
+                    path.display()
+                );
+            }
+            return Err(error).with_context(|| format!("locking {}", path.display()));
+        }
+        Ok(Self { file })
+    }
+}
+
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        unsafe { libc::flock(self.file.as_raw_fd(), libc::LOCK_UN) };
+    }
+}
+
+fn lock_path(config: Option<&Path>) -> Result<PathBuf> {
+    let config = match config {
+        Some(config) => config.to_path_buf(),
+        None => crate::config::default_config_path()?,
+    };
+    // The default config may not exist yet; its path still tells instances apart
+    let config = config.canonicalize().unwrap_or(config);
+    let dir = dirs::runtime_dir().unwrap_or_else(std::env::temp_dir);
+    Ok(dir.join(format!("corna-{:016x}.lock", fnv1a(config.as_os_str().as_bytes()))))
+}
+
+/// 64-bit FNV-1a; unlike `DefaultHasher` it stays the same across Rust
+/// releases, so an upgraded binary still finds a running instance's lock
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3))
+}
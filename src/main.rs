@@ -1,21 +1,32 @@
 mod app;
 mod config;
+mod error;
 mod features;
 mod gfx;
+mod headless;
+mod keybindings;
+mod lock;
+mod state;
+mod stats;
 mod wayland;
 
 use anyhow::Result;
 use app::{App, UiEvent, UiMode};
-use config::Config;
-use features::{clock::Clock, pomodoro::Pomodoro};
-use gfx::{draw::DrawContext, gl::load_shader_program, math::{Rect, Vec2}};
-use log::info;
+use config::{Anchor, CompletionStyle, Config, PlasmaRegion, TimerSide};
+use features::pomodoro::{Pomodoro, TIMER_WINDOW_SIZE};
+use gfx::{anim::{Easing, Timeline}, draw::DrawContext, gl::{load_shader_program, ui_shader_sources}, math::{Rect, Vec2}};
+use log::{debug, error, info, warn};
+use std::os::unix::io::AsRawFd;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::time::Instant;
 use wayland::egl::EglContext;
 use wayland::WaylandState;
-use wayland_client::{Connection, Dispatch, QueueHandle, Proxy};
+use wayland::window_manager::{PositionConfig, RelativePosition, WindowConfig, WindowId, WindowManager};
+use wayland_client::{Connection, Dispatch, EventQueue, QueueHandle, Proxy};
 use wayland_protocols_wlr::layer_shell::v1::client::{
-    zwlr_layer_shell_v1, zwlr_layer_surface_v1,
+    zwlr_layer_shell_v1,
 };
 use wayland_client::protocol::{
     wl_compositor, wl_keyboard, wl_output, wl_pointer, wl_registry, wl_seat,
@@ -23,13 +34,307 @@ use wayland_client::protocol::{
 };
 use xkbcommon::xkb::{self, Context, Keymap, State as XkbState, CONTEXT_NO_FLAGS as FFI_CONTEXT_NO_FLAGS, KEYMAP_COMPILE_NO_FLAGS as FFI_KEYMAP_COMPILE_NO_FLAGS, keysyms};
 
+const WORLD_CLOCK_GAP: i32 = 4;
+const WORLD_CLOCK_HEIGHT: u32 = 44;
+const WINDOW_FADE_SECONDS: f32 = 0.25;
+
+/// The world clock stacks under the clock, or above it at the bottom of the screen
+fn world_clock_position(anchor: Anchor) -> RelativePosition {
+    if anchor.is_top() {
+        RelativePosition::Below { gap: WORLD_CLOCK_GAP }
+    } else {
+        RelativePosition::Above { gap: WORLD_CLOCK_GAP }
+    }
+}
+
+/// Where the timer sits relative to the window before it, `gap` pixels
+/// away; `Auto` picks the side facing away from the anchored screen edge
+fn timer_position(anchor: Anchor, side: TimerSide, gap: u32) -> RelativePosition {
+    let gap = gap as i32;
+    match side {
+        TimerSide::Left => RelativePosition::LeftOf { gap },
+        TimerSide::Right => RelativePosition::RightOf { gap },
+        TimerSide::Below if anchor.is_top() => RelativePosition::Below { gap },
+        TimerSide::Below => RelativePosition::Above { gap },
+        TimerSide::Auto if anchor.is_left() => RelativePosition::RightOf { gap },
+        TimerSide::Auto => RelativePosition::LeftOf { gap },
+    }
+}
+
+/// A GL context of its own for a configured `surface` of `buffer` pixels,
+/// shared with the clock's. Leaves that context current.
+fn open_surface_gl(
+    display_ptr: *mut std::ffi::c_void,
+    surface: &wl_surface::WlSurface,
+    buffer: [i32; 2],
+    (vert_src, frag_src): (&str, &str),
+) -> Result<(EglContext, DrawContext)> {
+    let mut egl = EglContext::new_shared(display_ptr)?;
+    egl.create_surface(surface, buffer[0], buffer[1])?;
+    egl.make_current()?;
+    // Only the clock's swap throttles the loop
+    egl.set_swap_interval(0)?;
+    let gl = unsafe {
+        glow::Context::from_loader_function(|s| egl.get_proc_address(s))
+    };
+    let program = load_shader_program(&gl, vert_src, frag_src)?;
+    let draw = DrawContext::new(gl, program)?;
+    Ok((egl, draw))
+}
+
+/// A window that stays up for the whole session, drawn by whichever
+/// features target its id
+struct FeatureWindow {
+    id: WindowId,
+    size: [u32; 2],
+    surface: wl_surface::WlSurface,
+    // Dropped before the EGL context it renders with
+    draw: DrawContext,
+    egl: EglContext,
+}
+
+impl FeatureWindow {
+    /// Create the layer surface, wait for its configure and give it a GL
+    /// context of its own. Leaves that context current.
+    fn open(
+        config: WindowConfig,
+        state: &mut WaylandState,
+        event_queue: &mut EventQueue<WaylandState>,
+        windows: &mut WindowManager,
+        display_ptr: *mut std::ffi::c_void,
+        scale: f32,
+        shaders: (&str, &str),
+    ) -> Result<Option<Self>> {
+        let qh = event_queue.handle();
+        let (Some(compositor), Some(layer_shell)) = (&state.compositor, &state.layer_shell) else {
+            return Ok(None);
+        };
+        let surface = compositor.create_surface(&qh, ());
+        surface.set_buffer_scale(state.buffer_scale());
+        let (id, size) = (config.id, config.size);
+        windows.create_window(config, surface.clone(), layer_shell, &qh);
+        state.set_surface_size(&surface, size, &qh);
+
+        event_queue.roundtrip(state)?;
+
+        let buffer = size.map(|x| (x as f32 * scale) as i32);
+        let (egl, draw) = open_surface_gl(display_ptr, &surface, buffer, shaders)?;
+
+        Ok(Some(Self { id, size, surface, draw, egl }))
+    }
+
+    fn resize(&mut self, size: [u32; 2], scale: f32) -> Result<()> {
+        self.size = size;
+        self.egl.resize((size[0] as f32 * scale) as i32, (size[1] as f32 * scale) as i32)?;
+        Ok(())
+    }
+
+    /// Leaves this window's context current
+    fn render(&mut self, app: &App) -> Result<()> {
+        self.egl.make_current()?;
+        let size = self.size.map(|x| x as f32);
+        self.draw.begin(size, app.scale);
+        self.draw.set_time(app.time);
+        app.render(self.id, &mut self.draw, Rect::new(0.0, 0.0, size[0], size[1]));
+        self.draw.flush();
+        self.egl.swap_buffers()?;
+        self.surface.commit();
+        Ok(())
+    }
+}
+
+/// Swap freshly compiled shaders into `draw`, whose context must be current.
+/// A shader that fails to compile leaves the old program running.
+fn reload_shaders(draw: &mut DrawContext, (vert_src, frag_src): (&str, &str)) {
+    if let Err(e) = draw.reload_program(vert_src, frag_src) {
+        error!("Keeping the old shaders: {:#}", e);
+    }
+}
+
+/// Block until Wayland events are readable or `timeout` passes
+fn wait_for_events(event_queue: &mut EventQueue<WaylandState>, timeout: std::time::Duration) -> Result<()> {
+    event_queue.flush()?;
+    // Events may already be queued, in which case there's nothing to wait for
+    let Some(guard) = event_queue.prepare_read() else { return Ok(()) };
+    let mut fd = libc::pollfd {
+        fd: guard.connection_fd().as_raw_fd(),
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    let ready = unsafe { libc::poll(&mut fd, 1, timeout.as_millis() as i32) };
+    if ready > 0 {
+        guard.read()?;
+    }
+    Ok(())
+}
+
+/// Turn a linear fade around mid-flight so the opacity carries on from
+/// where it is instead of jumping
+fn reverse_fade(fade: &mut Timeline, now: f32) {
+    let progress = fade.progress();
+    fade.start(now - fade.duration * (1.0 - progress));
+    fade.update(now);
+}
+
+/// Time left until the wall clock's next whole second, or half second
+/// while the colons blink
+fn until_next_tick(blink_colon: bool) -> std::time::Duration {
+    let since_epoch = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    let period: u64 = if blink_colon { 500_000_000 } else { 1_000_000_000 };
+    std::time::Duration::from_nanos(period - since_epoch.subsec_nanos() as u64 % period)
+}
+
+const USAGE: &str = "\
+Usage: corna [OPTIONS]
+
+Options:
+  -c, --config <PATH>  Load config from PATH instead of ~/.config/corna/config.toml
+      --render-frame <OUT.png>
+                       Render one clock frame at a fixed time to a PNG and exit
+                       (no compositor needed)
+      --bench <FRAMES> Render that frame FRAMES times and print the frame rate
+                       (builds with the `bench` feature only)
+      --list-outputs   Print the compositor's outputs (names for `output`) and exit
+      --allow-multiple Start even if corna is already running with this config
+  -h, --help           Print this help
+  -V, --version        Print the version
+
+Environment:
+  CORNA_CONFIG_DIR     Directory holding config.toml, instead of ~/.config/corna";
+
+struct Args {
+    config: Option<PathBuf>,
+    render_frame: Option<PathBuf>,
+    #[cfg(feature = "bench")]
+    bench_frames: Option<u32>,
+    list_outputs: bool,
+    allow_multiple: bool,
+}
+
+/// Returns None when the invocation was fully handled (--help, --version)
+fn parse_args() -> Result<Option<Args>> {
+    let mut args = Args {
+        config: None,
+        render_frame: None,
+        #[cfg(feature = "bench")]
+        bench_frames: None,
+        list_outputs: false,
+        allow_multiple: false,
+    };
+    let mut argv = std::env::args().skip(1);
+    while let Some(arg) = argv.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(None);
+            }
+            "-V" | "--version" => {
+                println!("corna {}", env!("CARGO_PKG_VERSION"));
+                return Ok(None);
+            }
+            "-c" | "--config" => {
+                let path = argv.next().ok_or_else(|| anyhow::anyhow!("{} needs a path\n\n{}", arg, USAGE))?;
+                args.config = Some(PathBuf::from(path));
+            }
+            "--render-frame" => {
+                let path = argv.next().ok_or_else(|| anyhow::anyhow!("{} needs a path\n\n{}", arg, USAGE))?;
+                args.render_frame = Some(PathBuf::from(path));
+            }
+            #[cfg(feature = "bench")]
+            "--bench" => {
+                let frames = argv.next().and_then(|n| n.parse().ok())
+                    .ok_or_else(|| anyhow::anyhow!("{} needs a frame count\n\n{}", arg, USAGE))?;
+                args.bench_frames = Some(frames);
+            }
+            "--list-outputs" => args.list_outputs = true,
+            "--allow-multiple" => args.allow_multiple = true,
+            _ => match arg.strip_prefix("--config=") {
+                Some(path) => args.config = Some(PathBuf::from(path)),
+                None => anyhow::bail!("Unknown argument '{}'\n\n{}", arg, USAGE),
+            },
+        }
+    }
+    Ok(Some(args))
+}
+
+/// Print every output with its connector name, mode, scale and position
+fn list_outputs() -> Result<()> {
+    let conn = Connection::connect_to_env()?;
+    let mut event_queue = conn.new_event_queue();
+    let qh = event_queue.handle();
+    let _registry = conn.display().get_registry(&qh, ());
+
+    // Globals first, then the outputs' own events
+    let mut state = WaylandState::new(&qh);
+    event_queue.roundtrip(&mut state)?;
+    event_queue.roundtrip(&mut state)?;
+
+    if state.xdg_output_manager.is_none() {
+        eprintln!("Compositor lacks zxdg_output_manager_v1, output names are unavailable");
+    }
+    for info in &state.outputs {
+        let size = info.size.map_or("unknown".to_string(), |[w, h]| format!("{}x{}", w, h));
+        println!(
+            "{}  {}  scale {}  at {},{}",
+            info.name.as_deref().unwrap_or("(unnamed)"),
+            size,
+            info.scale,
+            info.position[0],
+            info.position[1],
+        );
+    }
+    Ok(())
+}
+
 fn main() -> Result<()> {
+    let Some(args) = parse_args()? else { return Ok(()) };
+
     env_logger::init();
+
+    if args.list_outputs {
+        return list_outputs();
+    }
+
+    // Load config; an explicit path has to exist and parse
+    let config = match &args.config {
+        Some(path) => Config::load_from(path)?,
+        None => Config::load().unwrap_or_else(|e| {
+            error!("{}, using the default config", e);
+            Config::default()
+        }),
+    };
+    if let Some(out) = &args.render_frame {
+        return headless::render_frame(config, out);
+    }
+    #[cfg(feature = "bench")]
+    if let Some(frames) = args.bench_frames {
+        return headless::bench(config, frames);
+    }
+
+    // One clock per config: a second launch, say from an autostart race,
+    // would stack its surfaces on top of the first's
+    let instance_lock = if args.allow_multiple {
+        None
+    } else {
+        Some(lock::InstanceLock::acquire(args.config.as_deref())?)
+    };
+
     println!("Starting corna...");
 
-    // Load config
-    let config = Config::load().unwrap_or_default();
-    let mut app = App::new(config);
+    // Pick up edits to the config file while running
+    let config_path = config.file_path().ok();
+    let config_reloads = config_path.clone().map(config::watch);
+    let saved = state::State::load().unwrap_or_else(|e| {
+        warn!("Failed to load state: {}", e);
+        state::State::default()
+    });
+    let stats = stats::Stats::load().unwrap_or_else(|e| {
+        warn!("Failed to load pomodoro stats: {}", e);
+        stats::Stats::default()
+    });
+    let mut app = App::new(config, saved, stats);
 
     // Connect to Wayland
     println!("Connecting to Wayland...");
@@ -42,44 +347,86 @@ fn main() -> Result<()> {
     let _registry = display.get_registry(&qh, ());
 
     let mut state = WaylandState::new(&qh);
+    state.scroll_step = app.config.scroll.step.max(f32::EPSILON);
 
-    // Initial roundtrip to get globals
+    // Initial roundtrip to get globals, then a second one so the outputs we
+    // just bound report their modes and names
     println!("Getting Wayland globals...");
     event_queue.roundtrip(&mut state)?;
-
-    if let Some(size) = state.output_size {
-        app.set_screen_size(size);
+    event_queue.roundtrip(&mut state)?;
+    state.select_output(app.config.output.as_deref());
+    app.set_scale(state.scale());
+
+    // GNOME and other non-wlroots compositors don't offer layer-shell; without it
+    // the clock would never be configured and we'd hang waiting for it
+    if state.layer_shell.is_none() {
+        if !app.config.allow_xdg_fallback {
+            anyhow::bail!(
+                "compositor does not support zwlr_layer_shell_v1; \
+                 set allow_xdg_fallback = true in the config to open corna as a regular window"
+            );
+        }
+        if state.xdg_wm_base.is_none() {
+            anyhow::bail!("compositor supports neither zwlr_layer_shell_v1 nor xdg_wm_base");
+        }
+        info!("No layer-shell support, falling back to an xdg toplevel (timer and effects disabled)");
     }
 
+    // Relative window placement works in logical coordinates
+    let logical_screen_size = |state: &WaylandState| {
+        state.output_size
+            .unwrap_or([1920, 1080])
+            .map(|x| (x as f32 / state.scale().max(1.0)).round() as u32)
+    };
+    let mut screen_size = logical_screen_size(&state);
+    app.set_screen_size(screen_size);
+    app.config.check_margins(screen_size);
+    let mut windows = WindowManager::new(screen_size, state.output.clone());
+
     // Create surface
     println!("Creating surface...");
-    if let Some(compositor) = &state.compositor {
+    if let (Some(compositor), Some(layer_shell)) = (&state.compositor, &state.layer_shell) {
         let surface = compositor.create_surface(&qh, ());
+        surface.set_buffer_scale(state.buffer_scale());
         state.surface = Some(surface.clone());
 
-        // Create layer surface
-        if let Some(layer_shell) = &state.layer_shell {
-            let layer_surface = layer_shell.get_layer_surface(
-                &surface,
-                None,
-                zwlr_layer_shell_v1::Layer::Overlay,
-                "corna".to_string(),
-                &qh,
-                (),
-            );
-
-            // Configure layer surface for top-right corner
-            layer_surface.set_anchor(
-                zwlr_layer_surface_v1::Anchor::Top | zwlr_layer_surface_v1::Anchor::Right,
-            );
-            layer_surface.set_exclusive_zone(0);
-            layer_surface.set_margin(0, 0, 0, 0);
-            layer_surface.set_size(150, 60);  // Match the default collapsed size
-
-            surface.commit();
+        let anchor = app.config.position.anchor;
+        windows.create_window(
+            WindowConfig {
+                id: WindowId::Clock,
+                size: app.get_current_size(),
+                position: PositionConfig::Anchored {
+                    anchor: anchor.into(),
+                    margin: app.config.margins.for_anchor(anchor),
+                },
+                layer: zwlr_layer_shell_v1::Layer::Overlay,
+                name: "corna".to_string(),
+                exclusive_zone: app.config.position.exclusive_zone,
+                keyboard: true,
+            },
+            surface,
+            layer_shell,
+            &qh,
+        );
+    } else if let (Some(compositor), Some(wm_base)) = (&state.compositor, &state.xdg_wm_base) {
+        let surface = compositor.create_surface(&qh, ());
+        surface.set_buffer_scale(state.buffer_scale());
+        state.surface = Some(surface.clone());
 
-            state.layer_surface = Some(layer_surface);
-        }
+        let xdg_surface = wm_base.get_xdg_surface(&surface, &qh, ());
+        let toplevel = xdg_surface.get_toplevel(&qh, ());
+        toplevel.set_title("corna".to_string());
+        toplevel.set_app_id("corna".to_string());
+        let [width, height] = app.get_current_size();
+        toplevel.set_min_size(width as i32, height as i32);
+        toplevel.set_max_size(width as i32, height as i32);
+        surface.commit();
+
+        state.xdg_surface = Some(xdg_surface);
+        state.xdg_toplevel = Some(toplevel);
+    }
+    if let Some(surface) = state.surface.clone() {
+        state.set_surface_size(&surface, app.get_current_size(), &qh);
     }
 
 
@@ -97,9 +444,10 @@ fn main() -> Result<()> {
 
     println!("Creating EGL surface...");
     if let Some(surface) = &state.surface {
-        let size = app.get_current_size();
-        egl.create_surface(surface, size[0] as i32, size[1] as i32)?;
+        let size = app.get_current_size().map(|x| (x as f32 * app.scale) as i32);
+        egl.create_surface(surface, size[0], size[1])?;
         egl.make_current()?;
+        egl.set_swap_interval(if app.config.vsync { 1 } else { 0 })?;
     }
 
     // Create GL context
@@ -110,31 +458,119 @@ fn main() -> Result<()> {
 
     // Load shaders
     println!("Loading shaders...");
-    let vert_src = std::fs::read_to_string("assets/shaders/ui.vert.glsl")?;
-    let frag_src = std::fs::read_to_string("assets/shaders/ui.frag.glsl")?;
+    let (mut vert_src, mut frag_src) = ui_shader_sources()?;
     let program = load_shader_program(&gl, &vert_src, &frag_src)?;
 
+    // Shaders read from disk can be edited live; SIGUSR1 recompiles them
+    let shader_reload = Arc::new(AtomicBool::new(false));
+    if std::env::var_os("CORNA_SHADER_DIR").is_some() {
+        signal_hook::flag::register(signal_hook::consts::SIGUSR1, Arc::clone(&shader_reload))?;
+    }
+
     // Create draw context
     let mut draw_context = DrawContext::new(gl, program)?;
+    draw_context.set_antialias(app.config.antialias);
+    draw_context.set_clear_color(app.config.theme.surface_color());
+    if log::log_enabled!(log::Level::Debug) {
+        let info = draw_context.gl_info();
+        debug!(
+            "GL renderer {}, {}, max texture {}px, vertex arrays {}, derivatives {}",
+            info.renderer, info.version, info.max_texture_size, info.vertex_arrays, info.derivatives
+        );
+    }
 
-    let mut clock = Clock::new();
+    // Optional world clock window, as wide as the clock so their faces line up
+    let world_size = |app: &App| [app.clock_size()[0], WORLD_CLOCK_HEIGHT];
+    let mut feature_windows: Vec<FeatureWindow> = Vec::new();
+    if app.wants_window(WindowId::WorldClock) {
+        let config = WindowConfig {
+            id: WindowId::WorldClock,
+            size: world_size(&app),
+            position: PositionConfig::RelativeTo {
+                window: WindowId::Clock,
+                position: world_clock_position(app.config.position.anchor),
+            },
+            layer: zwlr_layer_shell_v1::Layer::Overlay,
+            name: "corna-world-clock".to_string(),
+            exclusive_zone: 0,
+            keyboard: false,
+        };
+        feature_windows.extend(FeatureWindow::open(config, &mut state, &mut event_queue, &mut windows, display_ptr, app.scale, (&vert_src, &frag_src))?);
+    }
+
+    // Battery, system monitor and countdown line up beside the clock, away
+    // from the anchored edge; `timer_side` only moves the timer
+    let anchor = app.config.position.anchor;
+    let mut beside_window = WindowId::Clock;
+    for (id, name) in [
+        (WindowId::Battery, "corna-battery"),
+        (WindowId::SysMon, "corna-sysmon"),
+        (WindowId::Countdown, "corna-countdown"),
+    ] {
+        let Some(size) = app.window_size(id) else { continue };
+        let config = WindowConfig {
+            id,
+            size,
+            position: PositionConfig::RelativeTo {
+                window: beside_window,
+                position: timer_position(anchor, TimerSide::Auto, app.config.pomodoro.timer_gap),
+            },
+            layer: zwlr_layer_shell_v1::Layer::Top,
+            name: name.to_string(),
+            exclusive_zone: 0,
+            keyboard: false,
+        };
+        if let Some(window) = FeatureWindow::open(config, &mut state, &mut event_queue, &mut windows, display_ptr, app.scale, (&vert_src, &frag_src))? {
+            feature_windows.push(window);
+            beside_window = id;
+        }
+    }
+    // The timer continues that row when it's on the same side, and otherwise
+    // sits against the clock or under the clock stack
+    let auto_side = if anchor.is_left() { TimerSide::Right } else { TimerSide::Left };
+    let timer_side = app.config.pomodoro.timer_side;
+    let timer_anchor_window = match timer_side {
+        TimerSide::Auto => beside_window,
+        side if side == auto_side => beside_window,
+        TimerSide::Below if feature_windows.iter().any(|w| w.id == WindowId::WorldClock) => WindowId::WorldClock,
+        _ => WindowId::Clock,
+    };
+    for window in &mut feature_windows {
+        window.draw.set_antialias(app.config.antialias);
+        window.draw.set_clear_color(app.config.theme.surface_color());
+    }
+    egl.make_current()?;
 
     // Timer window variables
     let mut timer_egl: Option<EglContext> = None;
     let mut timer_draw_context: Option<DrawContext> = None;
     let mut timer_window_active = false;
+    // Fades the timer in on creation and out before its surface goes away
+    let mut timer_fade = Timeline::with_easing(WINDOW_FADE_SECONDS, Easing::Linear);
+    let mut timer_closing = false;
 
     // Plasma window variables
     let mut plasma_egl: Option<EglContext> = None;
     let mut plasma_draw_context: Option<DrawContext> = None;
     let mut plasma_window_active = false;
+    // Physical size of the plasma's buffer, the output or a band across it
+    let mut plasma_buffer = [0i32; 2];
+
+    let mut timer_size = app.window_size(WindowId::Timer).unwrap_or(TIMER_WINDOW_SIZE);
 
-    let mut last_frame = Instant::now();
+    let mut idle_inhibit_missing_logged = false;
+
+    // SIGTERM and Ctrl+C end the loop so everything is torn down in order;
+    // a second one exits on the spot in case the loop is stuck
+    let terminate = Arc::new(AtomicBool::new(false));
+    for signal in [signal_hook::consts::SIGTERM, signal_hook::consts::SIGINT] {
+        signal_hook::flag::register_conditional_shutdown(signal, 1, Arc::clone(&terminate))?;
+        signal_hook::flag::register(signal, Arc::clone(&terminate))?;
+    }
 
     // Main loop
     println!("Starting main loop...");
     let mut previous_size = [100u32, 40u32];
-    let mut previous_clock_width = app.get_current_size()[0];
 
     while state.running {
         event_queue.dispatch_pending(&mut state)?;
@@ -143,80 +579,198 @@ fn main() -> Result<()> {
         for ev in state.pending_events.drain(..) {
             app.handle_event(ev);
         }
+        if app.quit_requested {
+            state.running = false;
+        }
+        if terminate.load(Ordering::Relaxed) {
+            info!("Received a termination signal, shutting down");
+            state.running = false;
+        }
+
+        // A closed layer surface means the compositor wants us gone, unless its
+        // output was unplugged; a roundtrip makes sure a removal has arrived
+        if std::mem::take(&mut state.surface_closed) {
+            event_queue.roundtrip(&mut state)?;
+            if !state.output_lost && state.output.is_some() {
+                info!("Layer surface closed by the compositor, exiting");
+                state.running = false;
+            }
+        }
+
+        // Move every window to another output when ours goes away or the
+        // configured one comes back
+        let lost = std::mem::take(&mut state.output_lost);
+        if let Some(layer_shell) = state.layer_shell.clone() {
+            if lost || state.should_switch_output(app.config.output.as_deref()) {
+                state.select_output(app.config.output.as_deref());
+                if state.output.is_some() {
+                    screen_size = logical_screen_size(&state);
+                    app.set_screen_size(screen_size);
+                    windows.move_to_output(state.output.clone(), screen_size, &layer_shell, &qh);
+                    event_queue.roundtrip(&mut state)?;
+                    // The old surface may never deliver its last frame callback,
+                    // which a vsynced swap would wait on forever
+                    if let Some(surface) = &state.surface {
+                        let size = app.get_current_size().map(|x| (x as f32 * app.scale) as i32);
+                        egl.create_surface(surface, size[0], size[1])?;
+                        egl.set_swap_interval(if app.config.vsync { 1 } else { 0 })?;
+                    }
+                } else {
+                    info!("No outputs left, waiting for one to appear");
+                }
+            }
+            if state.output.is_none() {
+                // Wakes up now and then to notice a termination signal
+                wait_for_events(&mut event_queue, std::time::Duration::from_secs(1))?;
+                continue;
+            }
+        }
+
+        let frame_start = Instant::now();
+
+        // Follow scale changes of the output we're on
+        let scale_changed = state.scale() != app.scale;
+        if scale_changed {
+            info!("Output scale changed: {} -> {}", app.scale, state.scale());
+            app.set_scale(state.scale());
+            for surface in [&state.surface, &state.timer_surface, &state.plasma_surface].into_iter().flatten() {
+                surface.set_buffer_scale(state.buffer_scale());
+            }
+            if let Some(timer_egl_ctx) = &mut timer_egl {
+                let timer_buffer = timer_size.map(|x| (x as f32 * app.scale) as i32);
+                timer_egl_ctx.resize(timer_buffer[0], timer_buffer[1])?;
+            }
+            for window in &mut feature_windows {
+                window.surface.set_buffer_scale(state.buffer_scale());
+                window.resize(window.size, app.scale)?;
+            }
+        }
+
+        if logical_screen_size(&state) != screen_size {
+            screen_size = logical_screen_size(&state);
+            app.set_screen_size(screen_size);
+            windows.update_screen_size(screen_size);
+        }
 
-        let now = Instant::now();
-        let dt = now.duration_since(last_frame).as_secs_f32();
-        last_frame = now;
+        if let (Some(path), Some(reloads)) = (&config_path, &config_reloads) {
+            if reloads.try_iter().count() > 0 {
+                // A bad edit keeps the last good config running
+                match Config::load_from(path) {
+                    Ok(config) => {
+                        let surface = config.theme.surface_color();
+                        draw_context.set_clear_color(surface);
+                        if let Some(timer_draw) = &mut timer_draw_context {
+                            timer_draw.set_clear_color(surface);
+                        }
+                        for window in &mut feature_windows {
+                            window.draw.set_clear_color(surface);
+                        }
+                        app.reload_config(config);
+                    }
+                    Err(e) => error!("Ignoring config reload: {:#}", e),
+                }
+            }
+        }
+
+        if shader_reload.swap(false, Ordering::Relaxed) {
+            match ui_shader_sources() {
+                Ok(sources) => {
+                    (vert_src, frag_src) = sources;
+                    let sources = (vert_src.as_str(), frag_src.as_str());
+                    egl.make_current()?;
+                    reload_shaders(&mut draw_context, sources);
+                    for window in &mut feature_windows {
+                        window.egl.make_current()?;
+                        reload_shaders(&mut window.draw, sources);
+                    }
+                    if let (Some(timer_egl_ctx), Some(timer_draw)) = (&timer_egl, &mut timer_draw_context) {
+                        timer_egl_ctx.make_current()?;
+                        reload_shaders(timer_draw, sources);
+                    }
+                    if let (Some(plasma_egl_ctx), Some(plasma_draw)) = (&plasma_egl, &mut plasma_draw_context) {
+                        plasma_egl_ctx.make_current()?;
+                        reload_shaders(plasma_draw, sources);
+                    }
+                    egl.make_current()?;
+                    info!("Reloaded shaders");
+                }
+                Err(e) => error!("Ignoring shader reload: {:#}", e),
+            }
+        }
+
+        app.update_to_now();
 
-        app.update(dt);
-        clock.update(dt, app.time);
-        app.pomodoro.update(app.time);
+        // Follow the clock while it's being dragged or the config moved it
+        if app.take_margins_changed() {
+            windows.set_margin(WindowId::Clock, app.config.margins.for_anchor(app.config.position.anchor));
+        }
+
+        // Keep the screen awake while counting down
+        if app.config.inhibit_idle_during_pomodoro {
+            let counting = matches!(app.pomodoro().mode, crate::features::pomodoro::PomodoroMode::Counting { .. });
+            if !state.set_idle_inhibited(counting, &qh) && counting && !idle_inhibit_missing_logged {
+                info!("Compositor lacks zwp_idle_inhibit_manager_v1, the screen may still idle during pomodoros");
+                idle_inhibit_missing_logged = true;
+            }
+        }
 
         // Create/destroy timer window based on pomodoro state
-        let should_show_timer = matches!(app.pomodoro.mode, crate::features::pomodoro::PomodoroMode::Counting { .. });
+        // An inline timer is drawn by the clock's surface instead
+        let should_show_timer = !app.config.pomodoro.inline && app.wants_window(WindowId::Timer);
+        timer_fade.update(app.time);
 
         if should_show_timer && !timer_window_active {
+            timer_size = app.window_size(WindowId::Timer).unwrap_or(TIMER_WINDOW_SIZE);
             // Create timer surface
             if let (Some(compositor), Some(layer_shell)) = (&state.compositor, &state.layer_shell) {
                 let timer_surface = compositor.create_surface(&event_queue.handle(), ());
-                let timer_layer = layer_shell.get_layer_surface(
-                    &timer_surface,
-                    state.output.as_ref(),
-                    zwlr_layer_shell_v1::Layer::Top,
-                    "corna-timer".to_string(),
+                timer_surface.set_buffer_scale(state.buffer_scale());
+                state.timer_surface = Some(timer_surface.clone());
+
+                windows.create_window(
+                    WindowConfig {
+                        id: WindowId::Timer,
+                        size: timer_size,
+                        position: PositionConfig::RelativeTo {
+                            window: timer_anchor_window,
+                            position: timer_position(app.config.position.anchor, timer_side, app.config.pomodoro.timer_gap),
+                        },
+                        layer: zwlr_layer_shell_v1::Layer::Top,
+                        name: "corna-timer".to_string(),
+                        exclusive_zone: 0,
+                        keyboard: false,
+                    },
+                    timer_surface.clone(),
+                    layer_shell,
                     &event_queue.handle(),
-                    (),
                 );
-
-                // Position timer window properly to the left of clock
-                // Use actual clock size from app.get_current_size()
-                let clock_size = app.get_current_size();
-                const TIMER_WIDTH: u32 = 80;
-                const TIMER_HEIGHT: u32 = 30;
-                const GAP: u32 = 10;
-
-                if let Some(screen_size) = state.output_size {
-                    // Clock is at top-right, timer should be to its left
-                    let timer_x_margin = screen_size[0] as i32 - clock_size[0] as i32 - TIMER_WIDTH as i32 - GAP as i32;
-                    timer_layer.set_anchor(
-                        zwlr_layer_surface_v1::Anchor::Top | zwlr_layer_surface_v1::Anchor::Left,
-                    );
-                    timer_layer.set_margin(0, 0, 0, timer_x_margin);
-                } else {
-                    // Fallback positioning
-                    timer_layer.set_anchor(
-                        zwlr_layer_surface_v1::Anchor::Top | zwlr_layer_surface_v1::Anchor::Right,
-                    );
-                    timer_layer.set_margin(0, clock_size[0] as i32 + GAP as i32, 0, 0);
-                }
-
-                timer_layer.set_exclusive_zone(0);
-                timer_layer.set_size(TIMER_WIDTH, TIMER_HEIGHT);
-
-                timer_surface.commit();
-                state.timer_surface = Some(timer_surface);
-                state.timer_layer_surface = Some(timer_layer);
+                state.set_surface_size(&timer_surface, timer_size, &qh);
 
                 // Wait for timer surface to be configured
                 event_queue.roundtrip(&mut state)?;
 
                 timer_window_active = true;
+                timer_closing = false;
+                timer_fade.start(app.time);
 
                 // Create EGL context for timer after configuration
                 if let Some(timer_surf) = &state.timer_surface {
-                    let mut timer_egl_ctx = EglContext::new_shared(display_ptr)?;
-                    timer_egl_ctx.create_surface(timer_surf, 80, 30)?;
-                    timer_egl_ctx.make_current()?;
-
-                    let timer_gl = unsafe {
-                        glow::Context::from_loader_function(|s| timer_egl_ctx.get_proc_address(s))
-                    };
-                    let timer_program = load_shader_program(&timer_gl, &vert_src, &frag_src)?;
-                    timer_draw_context = Some(DrawContext::new(timer_gl, timer_program)?);
+                    let timer_buffer = timer_size.map(|x| (x as f32 * app.scale) as i32);
+                    let (timer_egl_ctx, mut timer_draw) = open_surface_gl(display_ptr, timer_surf, timer_buffer, (&vert_src, &frag_src))?;
+                    timer_draw.set_antialias(app.config.antialias);
+                    timer_draw.set_clear_color(app.config.theme.surface_color());
+                    timer_draw_context = Some(timer_draw);
                     timer_egl = Some(timer_egl_ctx);
                 }
             }
-        } else if !should_show_timer && timer_window_active {
+        } else if !should_show_timer && timer_window_active && !timer_closing {
+            // Keep the surface around until it has faded out
+            timer_closing = true;
+            reverse_fade(&mut timer_fade, app.time);
+        } else if should_show_timer && timer_closing {
+            timer_closing = false;
+            reverse_fade(&mut timer_fade, app.time);
+        } else if !should_show_timer && timer_closing && timer_fade.is_complete() {
             info!("Destroying timer window...");
 
             // Switch back to main context before destroying timer
@@ -232,46 +786,59 @@ fn main() -> Result<()> {
 
             // Then destroy timer surfaces
             info!("Destroying timer surfaces...");
-            if let Some(layer) = state.timer_layer_surface.take() {
-                layer.destroy();
-            }
-            if let Some(surf) = state.timer_surface.take() {
-                surf.destroy();
+            if let Some(surface) = state.timer_surface.take() {
+                state.forget_surface(&surface);
             }
+            windows.destroy_window(WindowId::Timer);
             info!("Timer surfaces destroyed");
 
             timer_window_active = false;
+            timer_closing = false;
         }
 
         // Create/destroy plasma window for completion effect
-        let should_show_plasma = matches!(app.pomodoro.mode, crate::features::pomodoro::PomodoroMode::Completion { .. });
+        // Only the Fullscreen completion style gets the plasma overlay
+        let should_show_plasma = app.config.pomodoro.completion_style == CompletionStyle::Fullscreen
+            && app.wants_window(WindowId::Plasma);
 
         if should_show_plasma && !plasma_window_active {
-            info!("Creating fullscreen plasma window!");
+            let region = app.config.pomodoro.plasma_region;
+            info!("Creating {:?} plasma window!", region);
             if let (Some(compositor), Some(layer_shell)) = (&state.compositor, &state.layer_shell) {
                 let plasma_surface = compositor.create_surface(&event_queue.handle(), ());
-                let plasma_layer = layer_shell.get_layer_surface(
-                    &plasma_surface,
-                    state.output.as_ref(),
-                    zwlr_layer_shell_v1::Layer::Overlay, // Highest layer
-                    "corna-plasma".to_string(),
-                    &event_queue.handle(),
-                    (),
-                );
+                plasma_surface.set_buffer_scale(state.buffer_scale());
+                state.plasma_surface = Some(plasma_surface.clone());
+
+                // Fullscreen covers everything; a band only spans its edge
+                // and stays clear of panels there
+                let output_size = state.output_size.unwrap_or([1920, 1080]).map(|x| x as i32);
+                let band = app.config.pomodoro.plasma_band_height.clamp(1, screen_size[1].max(1));
+                let (position, size, exclusive_zone) = match region {
+                    PlasmaRegion::Fullscreen => (PositionConfig::Fill, [0, 0], -1),
+                    PlasmaRegion::TopBand => (PositionConfig::Band { top: true }, [0, band], 0),
+                    PlasmaRegion::BottomBand => (PositionConfig::Band { top: false }, [0, band], 0),
+                };
+                plasma_buffer = match region {
+                    PlasmaRegion::Fullscreen => output_size,
+                    _ => [output_size[0], (band as f32 * app.scale) as i32],
+                };
 
-                // Make it fullscreen
-                plasma_layer.set_anchor(
-                    zwlr_layer_surface_v1::Anchor::Top |
-                    zwlr_layer_surface_v1::Anchor::Bottom |
-                    zwlr_layer_surface_v1::Anchor::Left |
-                    zwlr_layer_surface_v1::Anchor::Right
+                windows.create_window(
+                    WindowConfig {
+                        id: WindowId::Plasma,
+                        size,
+                        position,
+                        layer: zwlr_layer_shell_v1::Layer::Overlay, // Highest layer
+                        name: "corna-plasma".to_string(),
+                        exclusive_zone,
+                        keyboard: false,
+                    },
+                    plasma_surface.clone(),
+                    layer_shell,
+                    &event_queue.handle(),
                 );
-                plasma_layer.set_exclusive_zone(-1); // Cover everything
-                plasma_layer.set_size(0, 0); // Fill entire screen
-
-                plasma_surface.commit();
-                state.plasma_surface = Some(plasma_surface);
-                state.plasma_layer_surface = Some(plasma_layer);
+                let logical = if size[1] == 0 { screen_size } else { [screen_size[0], size[1]] };
+                state.set_surface_size(&plasma_surface, logical, &qh);
 
                 // Wait for configuration
                 event_queue.roundtrip(&mut state)?;
@@ -280,16 +847,8 @@ fn main() -> Result<()> {
 
                 // Create EGL context for plasma
                 if let Some(plasma_surf) = &state.plasma_surface {
-                    let screen_size = state.output_size.unwrap_or([1920, 1080]);
-                    let mut plasma_egl_ctx = EglContext::new_shared(display_ptr)?;
-                    plasma_egl_ctx.create_surface(plasma_surf, screen_size[0] as i32, screen_size[1] as i32)?;
-                    plasma_egl_ctx.make_current()?;
-
-                    let plasma_gl = unsafe {
-                        glow::Context::from_loader_function(|s| plasma_egl_ctx.get_proc_address(s))
-                    };
-                    let plasma_program = load_shader_program(&plasma_gl, &vert_src, &frag_src)?;
-                    plasma_draw_context = Some(DrawContext::new(plasma_gl, plasma_program)?);
+                    let (plasma_egl_ctx, plasma_draw) = open_surface_gl(display_ptr, plasma_surf, plasma_buffer, (&vert_src, &frag_src))?;
+                    plasma_draw_context = Some(plasma_draw);
                     plasma_egl = Some(plasma_egl_ctx);
                 }
             }
@@ -304,28 +863,55 @@ fn main() -> Result<()> {
             plasma_egl = None;
 
             // Destroy plasma surfaces
-            if let Some(layer) = state.plasma_layer_surface.take() {
-                layer.destroy();
-            }
-            if let Some(surf) = state.plasma_surface.take() {
-                surf.destroy();
+            if let Some(surface) = state.plasma_surface.take() {
+                state.forget_surface(&surface);
             }
+            windows.destroy_window(WindowId::Plasma);
 
             plasma_window_active = false;
         }
 
+        // The timer widens when its display needs an hours field
+        if let (true, Some(size)) = (timer_window_active, app.window_size(WindowId::Timer)) {
+            if size != timer_size {
+                timer_size = size;
+                windows.resize_window(WindowId::Timer, size);
+                if let Some(timer_surf) = state.timer_surface.clone() {
+                    state.set_surface_size(&timer_surf, size, &qh);
+                    timer_surf.commit();
+                }
+                if let Some(timer_egl_ctx) = &mut timer_egl {
+                    let timer_buffer = size.map(|x| (x as f32 * app.scale) as i32);
+                    timer_egl_ctx.resize(timer_buffer[0], timer_buffer[1])?;
+                }
+            }
+        }
+
         // Handle normal resize for main window
         let current_size = app.get_current_size();
         let buffer_size = [
             (current_size[0] as f32 * app.scale) as u32,
             (current_size[1] as f32 * app.scale) as u32,
         ];
-        if buffer_size != app.buffer_size || current_size != previous_size {
+        let resized = scale_changed || buffer_size != app.buffer_size || current_size != previous_size;
+        if resized {
             app.buffer_size = buffer_size;
             egl.resize(app.buffer_size[0] as i32, app.buffer_size[1] as i32)?;
 
-            if let Some(ref layer_surface) = state.layer_surface {
-                layer_surface.set_size(current_size[0], current_size[1]);
+            // Reflows the timer along with the clock's new width
+            windows.resize_window(WindowId::Clock, current_size);
+            if let Some(surface) = state.surface.clone() {
+                state.set_surface_size(&surface, current_size, &qh);
+            }
+            if let Some(world) = feature_windows.iter_mut().find(|w| w.id == WindowId::WorldClock) {
+                let size = world_size(&app);
+                windows.resize_window(WindowId::WorldClock, size);
+                state.set_surface_size(&world.surface, size, &qh);
+                world.resize(size, app.scale)?;
+            }
+            if let Some(toplevel) = &state.xdg_toplevel {
+                toplevel.set_min_size(current_size[0] as i32, current_size[1] as i32);
+                toplevel.set_max_size(current_size[0] as i32, current_size[1] as i32);
             }
             if let Some(ref surface) = state.surface {
                 surface.commit();
@@ -333,42 +919,54 @@ fn main() -> Result<()> {
             previous_size = current_size;
         }
 
+        // Low-power mode: with nothing animating, sleep until input arrives
+        // or the clock's next second instead of redrawing an identical frame
+        let fading = timer_window_active && (timer_closing || !timer_fade.is_complete());
+        if !app.take_redraw() && !resized && !fading {
+            wait_for_events(&mut event_queue, until_next_tick(app.blink_colon))?;
+            continue;
+        }
+
         // Render
         egl.make_current()?;
 
-        let size = app.buffer_size.map(|x| x as f32);
-        draw_context.begin(size);
+        // Layout happens in logical units; the buffer is allocated at physical pixels
+        let size = current_size.map(|x| x as f32);
+        draw_context.begin(size, app.scale);
         draw_context.set_time(app.time);
 
         let viewport = Rect::new(0.0, 0.0, size[0], size[1]);
-        // Pass show_seconds flag, color_mode and time to clock
-        clock.render(&mut draw_context, viewport, app.show_seconds, app.color_mode, app.time);
+        app.render(WindowId::Clock, &mut draw_context, viewport);
 
         draw_context.flush();
 
         // Swap buffers for main window
-        egl.swap_buffers()?;
+        if let Err(e) = egl.swap_buffers() {
+            if e.is_context_lost() {
+                error!("GL context lost (GPU reset?), exiting");
+            }
+            return Err(e.into());
+        }
+
+        if !feature_windows.is_empty() {
+            for window in &mut feature_windows {
+                window.render(&app)?;
+            }
+            egl.make_current()?;
+        }
 
-        // Render plasma window if active (FULLSCREEN)
+        // Render plasma window if active
         if plasma_window_active {
             if let (Some(ref mut plasma_egl_ctx), Some(ref mut plasma_draw)) = (&mut plasma_egl, &mut plasma_draw_context) {
                 plasma_egl_ctx.make_current()?;
-                let screen_size = state.output_size.unwrap_or([1920, 1080]);
-                let plasma_viewport = Rect::new(0.0, 0.0, screen_size[0] as f32, screen_size[1] as f32);
+                let plasma_size = plasma_buffer.map(|x| x as f32 / app.scale);
+                let plasma_viewport = Rect::new(0.0, 0.0, plasma_size[0], plasma_size[1]);
 
-                // Pass completion progress to shader for fade in/out BEFORE begin
-                let progress = if let crate::features::pomodoro::PomodoroMode::Completion { tl, .. } = &app.pomodoro.mode {
-                    tl.progress()
-                } else {
-                    1.0
-                };
-
-                plasma_draw.begin([screen_size[0] as f32, screen_size[1] as f32]);
+                plasma_draw.begin(plasma_size, app.scale);
                 plasma_draw.set_time(app.time);
-                plasma_draw.set_progress(progress);
 
-                // Render the FULLSCREEN plasma effect
-                app.pomodoro.render(plasma_draw, plasma_viewport, app.time);
+                // Render the plasma effect
+                app.render(WindowId::Plasma, plasma_draw, plasma_viewport);
 
                 plasma_draw.flush();
                 plasma_egl_ctx.swap_buffers()?;
@@ -382,35 +980,24 @@ fn main() -> Result<()> {
             }
         }
 
-        // Update timer position if clock width changed
-        if timer_window_active {
-            let current_clock_width = app.get_current_size()[0];
-            if current_clock_width != previous_clock_width {
-                // Clock width changed, update timer position
-                if let (Some(ref timer_layer), Some(screen_size)) = (&state.timer_layer_surface, state.output_size) {
-                    const TIMER_WIDTH: u32 = 80;
-                    const GAP: u32 = 10;
-                    let timer_x_margin = screen_size[0] as i32 - current_clock_width as i32 - TIMER_WIDTH as i32 - GAP as i32;
-                    timer_layer.set_margin(0, 0, 0, timer_x_margin);
-                    if let Some(timer_surf) = &state.timer_surface {
-                        timer_surf.commit();
-                    }
-                    info!("Updated timer position due to clock width change: {} -> {}", previous_clock_width, current_clock_width);
-                }
-                previous_clock_width = current_clock_width;
-            }
-        }
-
         // Render timer window if active
         if timer_window_active {
             if let (Some(ref mut timer_egl_ctx), Some(ref mut timer_draw)) = (&mut timer_egl, &mut timer_draw_context) {
                 timer_egl_ctx.make_current()?;
-                let timer_viewport = Rect::new(0.0, 0.0, 80.0, 30.0);
-                timer_draw.begin([80.0, 30.0]);
+                let timer_size = timer_size.map(|x| x as f32);
+                let timer_viewport = Rect::new(0.0, 0.0, timer_size[0], timer_size[1]);
+                timer_draw.begin(timer_size, app.scale);
                 timer_draw.set_time(app.time);
+                let fade = timer_fade.eased_progress();
+                timer_draw.set_opacity(if timer_closing { 1.0 - fade } else { fade });
 
-                // Render just the timer display
-                app.pomodoro.render(timer_draw, timer_viewport, app.time);
+                // Render just the timer display; once the pomodoro has let the
+                // window go, keep drawing its display while it fades out
+                if timer_closing {
+                    app.pomodoro().render_closing(timer_draw, timer_viewport);
+                } else {
+                    app.render(WindowId::Timer, timer_draw, timer_viewport);
+                }
 
                 timer_draw.flush();
                 timer_egl_ctx.swap_buffers()?;
@@ -429,9 +1016,33 @@ fn main() -> Result<()> {
             surface.commit();
         }
 
-        // Sleep briefly to cap framerate
-        std::thread::sleep(std::time::Duration::from_millis(16));
+        // With vsync the clock's buffer swap paces the loop; otherwise sleep
+        // off the rest of the frame budget
+        if !app.config.vsync {
+            let budget = std::time::Duration::from_secs_f32(1.0 / app.config.fps_cap.clamp(1, config::MAX_FPS_CAP) as f32);
+            if let Some(remaining) = budget.checked_sub(frame_start.elapsed()) {
+                std::thread::sleep(remaining);
+            }
+        }
     }
 
+    // Don't lose a change still waiting out the save delay
+    app.save_state();
+
+    // GL state goes before the EGL contexts, the shared contexts before the
+    // main one that owns the display, and EGL before the surfaces it drew to
+    drop(timer_draw_context);
+    drop(timer_egl);
+    drop(plasma_draw_context);
+    drop(plasma_egl);
+    drop(feature_windows);
+    drop(draw_context);
+    drop(egl);
+    windows.destroy_all();
+    event_queue.flush()?;
+    // Only now can a new instance take over without overlapping this one
+    drop(instance_lock);
+    info!("Shut down cleanly");
+
     Ok(())
 }
\ No newline at end of file
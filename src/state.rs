@@ -0,0 +1,63 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::config::ClockFace;
+
+/// Choices made at runtime that should survive a restart without touching
+/// the user's config file, persisted to `~/.local/state/corna/state.json`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct State {
+    /// Scrolled-to color mode, by name; falls back to `clock.color_mode`
+    #[serde(default)]
+    pub color_mode: Option<String>,
+
+    #[serde(default)]
+    pub show_seconds: Option<bool>,
+
+    /// Shift+scrolled brightness; falls back to `clock.brightness`
+    #[serde(default)]
+    pub brightness: Option<f32>,
+
+    /// Focus mode as toggled with its key, when that differs from
+    /// `pomodoro.hide_clock_while_counting`
+    #[serde(default)]
+    pub focus: Option<bool>,
+
+    /// Clock face as toggled with its key, when that differs from `clock.face`
+    #[serde(default)]
+    pub face: Option<ClockFace>,
+}
+
+impl State {
+    fn path() -> Result<PathBuf> {
+        let state_dir = dirs::state_dir()
+            .or_else(dirs::data_dir)
+            .ok_or_else(|| anyhow::anyhow!("Could not find state directory"))?;
+        Ok(state_dir.join("corna").join("state.json"))
+    }
+
+    pub fn load() -> Result<Self> {
+        let path = Self::path()?;
+
+        if path.exists() {
+            let contents = std::fs::read_to_string(&path)?;
+            Ok(serde_json::from_str(&contents)?)
+        } else {
+            Ok(State::default())
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path()?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        let tmp_path = path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp_path, &path)?;
+
+        Ok(())
+    }
+}
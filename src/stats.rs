@@ -0,0 +1,105 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use time::{Date, OffsetDateTime};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DayStats {
+    pub completed: u32,
+    pub focused_minutes: f32,
+}
+
+/// Completed pomodoros, persisted to `~/.local/share/corna/stats.json`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Stats {
+    /// Keyed by local date (YYYY-MM-DD)
+    #[serde(default)]
+    pub days: BTreeMap<String, DayStats>,
+
+    #[serde(default)]
+    pub total_focused_minutes: f32,
+}
+
+impl Stats {
+    fn path() -> Result<PathBuf> {
+        let data_dir = dirs::data_dir()
+            .ok_or_else(|| anyhow::anyhow!("Could not find data directory"))?;
+        Ok(data_dir.join("corna").join("stats.json"))
+    }
+
+    pub fn load() -> Result<Self> {
+        let path = Self::path()?;
+
+        if path.exists() {
+            let contents = std::fs::read_to_string(&path)?;
+            Ok(serde_json::from_str(&contents)?)
+        } else {
+            Ok(Stats::default())
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path()?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        // Write a sibling temp file and rename it over the old one so a crash
+        // mid-write never leaves a truncated stats file behind
+        let tmp_path = path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp_path, &path)?;
+
+        Ok(())
+    }
+
+    pub fn record_pomodoro(&mut self, date: Date, minutes: f32) {
+        let day = self.days.entry(date.to_string()).or_default();
+        day.completed += 1;
+        day.focused_minutes += minutes;
+        self.total_focused_minutes += minutes;
+    }
+
+    pub fn count_for(&self, date: Date) -> u32 {
+        self.days.get(&date.to_string()).map_or(0, |day| day.completed)
+    }
+}
+
+pub fn today() -> Date {
+    OffsetDateTime::now_local()
+        .unwrap_or_else(|_| OffsetDateTime::now_utc())
+        .date()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::Month;
+
+    fn date(year: i32, month: Month, day: u8) -> Date {
+        Date::from_calendar_date(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn records_are_keyed_by_date() {
+        let mut stats = Stats::default();
+        stats.record_pomodoro(date(2024, Month::March, 9), 25.0);
+        stats.record_pomodoro(date(2024, Month::March, 9), 25.0);
+        stats.record_pomodoro(date(2024, Month::March, 10), 15.0);
+
+        assert_eq!(stats.count_for(date(2024, Month::March, 9)), 2);
+        assert_eq!(stats.count_for(date(2024, Month::March, 10)), 1);
+        assert_eq!(stats.count_for(date(2024, Month::March, 11)), 0);
+        assert!(stats.days.contains_key("2024-03-09"));
+        assert_eq!(stats.days["2024-03-09"].focused_minutes, 50.0);
+    }
+
+    #[test]
+    fn total_minutes_span_every_day() {
+        let mut stats = Stats::default();
+        stats.record_pomodoro(date(2024, Month::December, 31), 25.0);
+        stats.record_pomodoro(date(2025, Month::January, 1), 30.0);
+        assert_eq!(stats.total_focused_minutes, 55.0);
+    }
+}
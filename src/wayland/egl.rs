@@ -1,10 +1,104 @@
-use anyhow::{anyhow, Result};
+use crate::error::{CornaError, Result};
 use khronos_egl as egl;
 use std::ffi::c_void;
 use std::ptr;
 use wayland_client::protocol::wl_surface::WlSurface;
 use wayland_client::Proxy;
 
+/// EGL_PLATFORM_SURFACELESS_MESA, from EGL_MESA_platform_surfaceless
+const PLATFORM_SURFACELESS_MESA: egl::Enum = 0x31DD;
+
+/// Config requests tried in order until the driver offers a match
+const CONFIG_ATTEMPTS: [ConfigAttempt; 4] = [
+    ConfigAttempt { desktop_gl: false, alpha: true },
+    ConfigAttempt { desktop_gl: false, alpha: false },
+    ConfigAttempt { desktop_gl: true, alpha: true },
+    ConfigAttempt { desktop_gl: true, alpha: false },
+];
+
+/// An RGB888 config for OpenGL ES 2 or, on drivers without it, desktop GL
+/// (whose ES2 compatibility runs our shaders as `#version 100`). Without alpha
+/// the clock loses its transparency but still shows.
+#[derive(Debug, Clone, Copy)]
+struct ConfigAttempt {
+    desktop_gl: bool,
+    alpha: bool,
+}
+
+impl ConfigAttempt {
+    fn api(self) -> egl::Enum {
+        if self.desktop_gl { egl::OPENGL_API } else { egl::OPENGL_ES_API }
+    }
+
+    fn attribs(self, surface_type: egl::Int) -> Vec<egl::Int> {
+        let renderable = if self.desktop_gl { egl::OPENGL_BIT } else { egl::OPENGL_ES2_BIT };
+        let mut attribs = vec![
+            egl::SURFACE_TYPE, surface_type,
+            egl::RED_SIZE, 8,
+            egl::GREEN_SIZE, 8,
+            egl::BLUE_SIZE, 8,
+            egl::RENDERABLE_TYPE, renderable,
+        ];
+        if self.alpha {
+            attribs.extend([egl::ALPHA_SIZE, 8]);
+        }
+        attribs.push(egl::NONE);
+        attribs
+    }
+
+    /// Desktop GL takes no client version; it hands out a compatible context
+    fn context_attribs(self) -> Vec<egl::Int> {
+        if self.desktop_gl {
+            vec![egl::NONE]
+        } else {
+            vec![egl::CONTEXT_CLIENT_VERSION, 2, egl::NONE]
+        }
+    }
+
+    fn describe(self) -> String {
+        let api = if self.desktop_gl { "OpenGL" } else { "OpenGL ES 2" };
+        let alpha = if self.alpha { "RGBA8888" } else { "RGB888" };
+        format!("{} {}", api, alpha)
+    }
+}
+
+/// The first of `CONFIG_ATTEMPTS` the driver can satisfy for `surface_type`,
+/// with a context for it and its API bound
+fn create_context(
+    instance: &egl::Instance<egl::Static>,
+    display: egl::Display,
+    surface_type: egl::Int,
+) -> Result<(egl::Config, egl::Context)> {
+    let mut failures = Vec::new();
+    for attempt in CONFIG_ATTEMPTS {
+        let config = match instance.choose_first_config(display, &attempt.attribs(surface_type)) {
+            Ok(Some(config)) => config,
+            Ok(None) => {
+                failures.push(format!("{} (no matching config)", attempt.describe()));
+                continue;
+            }
+            Err(e) => {
+                failures.push(format!("{} ({})", attempt.describe(), e));
+                continue;
+            }
+        };
+        let context = instance.bind_api(attempt.api())
+            .and_then(|()| instance.create_context(display, config, None, &attempt.context_attribs()));
+        match context {
+            Ok(context) => {
+                let id = instance.get_config_attrib(display, config, egl::CONFIG_ID).unwrap_or(-1);
+                log::info!("EGL config {}: {}", id, attempt.describe());
+                if !attempt.alpha {
+                    log::warn!("No EGL config with alpha, the clock will be drawn opaque");
+                }
+                return Ok((config, context));
+            }
+            Err(e) => failures.push(format!("{} (context: {})", attempt.describe(), e)),
+        }
+    }
+    Err(CornaError::EglConfig(failures.join(", ")))
+}
+
 pub struct EglContext {
     _egl: egl::Instance<egl::Static>,
     display: egl::Display,
@@ -22,33 +116,52 @@ impl EglContext {
         // Initialize EGL
         let display = unsafe {
             egl_instance.get_display(wl_display as egl::NativeDisplayType)
-                .ok_or_else(|| anyhow!("Failed to get EGL display"))?
+                .ok_or(egl::Error::BadDisplay)?
         };
 
         let (major, minor) = egl_instance.initialize(display)?;
         log::info!("EGL version: {}.{}", major, minor);
 
-        let config_attribs = [
-            egl::SURFACE_TYPE, egl::WINDOW_BIT,
-            egl::RED_SIZE, 8,
-            egl::GREEN_SIZE, 8,
-            egl::BLUE_SIZE, 8,
-            egl::ALPHA_SIZE, 8,
-            egl::RENDERABLE_TYPE, egl::OPENGL_ES2_BIT,
-            egl::NONE,
-        ];
+        let (config, context) = create_context(&egl_instance, display, egl::WINDOW_BIT)?;
 
-        let config = egl_instance.choose_first_config(display, &config_attribs)?
-            .ok_or_else(|| anyhow!("No EGL config found"))?;
+        Ok(Self {
+            _egl: egl_instance,
+            display,
+            context,
+            config,
+            wl_egl_window: None,
+            surface: None,
+            owns_display: true,  // First context owns the display
+        })
+    }
+
+    /// Offscreen context on the default display with a `width`x`height`
+    /// pbuffer, for rendering without a compositor. Left current.
+    pub fn new_pbuffer(width: i32, height: i32) -> Result<Self> {
+        let egl_instance = egl::Instance::new(egl::Static);
+
+        // The default display needs a Wayland or X server around; in CI or
+        // on a tty Mesa's surfaceless platform renders without one
+        let default = egl_instance.get_display(egl::DEFAULT_DISPLAY)
+            .and_then(|display| Some((display, egl_instance.initialize(display).ok()?)));
+        let (display, (major, minor)) = match default {
+            Some(initialized) => initialized,
+            None => {
+                let display = egl_instance.get_platform_display(PLATFORM_SURFACELESS_MESA, egl::DEFAULT_DISPLAY, &[egl::ATTRIB_NONE])?;
+                (display, egl_instance.initialize(display)?)
+            }
+        };
+        log::info!("EGL version: {}.{} (headless)", major, minor);
 
-        egl_instance.bind_api(egl::OPENGL_ES_API)?;
+        let (config, context) = create_context(&egl_instance, display, egl::PBUFFER_BIT)?;
 
-        let context_attribs = [
-            egl::CONTEXT_CLIENT_VERSION, 2,
+        let surface_attribs = [
+            egl::WIDTH, width,
+            egl::HEIGHT, height,
             egl::NONE,
         ];
-
-        let context = egl_instance.create_context(display, config, None, &context_attribs)?;
+        let surface = egl_instance.create_pbuffer_surface(display, config, &surface_attribs)?;
+        egl_instance.make_current(display, Some(surface), Some(surface), Some(context))?;
 
         Ok(Self {
             _egl: egl_instance,
@@ -56,8 +169,8 @@ impl EglContext {
             context,
             config,
             wl_egl_window: None,
-            surface: None,
-            owns_display: true,  // First context owns the display
+            surface: Some(surface),
+            owns_display: true,
         })
     }
 
@@ -137,6 +250,12 @@ impl EglContext {
         Ok(())
     }
 
+    /// Frames to wait between buffer swaps for the current surface (0 disables vsync)
+    pub fn set_swap_interval(&self, interval: i32) -> Result<()> {
+        self._egl.swap_interval(self.display, interval)?;
+        Ok(())
+    }
+
     pub fn get_proc_address(&self, name: &str) -> *const c_void {
         self._egl.get_proc_address(name)
             .map(|f| f as *const c_void)
@@ -151,32 +270,12 @@ impl EglContext {
         // Get the same display (won't be initialized again)
         let display = unsafe {
             egl_instance.get_display(wl_display as egl::NativeDisplayType)
-                .ok_or_else(|| anyhow!("Failed to get EGL display"))?
+                .ok_or(egl::Error::BadDisplay)?
         };
 
         // Note: display is already initialized by the first context
 
-        let config_attribs = [
-            egl::SURFACE_TYPE, egl::WINDOW_BIT,
-            egl::RED_SIZE, 8,
-            egl::GREEN_SIZE, 8,
-            egl::BLUE_SIZE, 8,
-            egl::ALPHA_SIZE, 8,
-            egl::RENDERABLE_TYPE, egl::OPENGL_ES2_BIT,
-            egl::NONE,
-        ];
-
-        let config = egl_instance.choose_first_config(display, &config_attribs)?
-            .ok_or_else(|| anyhow!("No EGL config found"))?;
-
-        egl_instance.bind_api(egl::OPENGL_ES_API)?;
-
-        let context_attribs = [
-            egl::CONTEXT_CLIENT_VERSION, 2,
-            egl::NONE,
-        ];
-
-        let context = egl_instance.create_context(display, config, None, &context_attribs)?;
+        let (config, context) = create_context(&egl_instance, display, egl::WINDOW_BIT)?;
 
         Ok(Self {
             _egl: egl_instance,
@@ -2,16 +2,21 @@ pub mod egl;
 pub mod window_manager;
 
 use wayland_client::{
-    protocol::{wl_compositor, wl_keyboard, wl_output, wl_pointer, wl_registry, wl_seat, wl_surface},
+    protocol::{wl_compositor, wl_keyboard, wl_output, wl_pointer, wl_registry, wl_seat, wl_surface, wl_touch},
     Connection, Dispatch, QueueHandle,
 };
+use wayland_protocols::wp::fractional_scale::v1::client::{wp_fractional_scale_manager_v1, wp_fractional_scale_v1};
+use wayland_protocols::wp::idle_inhibit::zv1::client::{zwp_idle_inhibit_manager_v1, zwp_idle_inhibitor_v1};
+use wayland_protocols::wp::viewporter::client::{wp_viewport, wp_viewporter};
+use wayland_protocols::xdg::shell::client::{xdg_surface, xdg_toplevel, xdg_wm_base};
+use wayland_protocols::xdg::xdg_output::zv1::client::{zxdg_output_manager_v1, zxdg_output_v1};
 use wayland_protocols_wlr::layer_shell::v1::client::{
     zwlr_layer_shell_v1, zwlr_layer_surface_v1,
 };
 use crate::app::UiEvent;
+use crate::keybindings::Modifiers;
 use crate::gfx::math::Vec2;
 use xkbcommon::xkb::{self, Context, Keymap, State as XkbState, CONTEXT_NO_FLAGS as FFI_CONTEXT_NO_FLAGS, KEYMAP_COMPILE_NO_FLAGS as FFI_KEYMAP_COMPILE_NO_FLAGS};
-use xkbcommon::xkb::keysyms;
 use std::os::unix::io::{RawFd, AsRawFd};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -21,23 +26,72 @@ pub enum ActiveSurface {
     Plasma,
 }
 
+/// A bound `wl_output` and what the compositor has told us about it
+pub struct OutputInfo {
+    /// Registry global name, also used as the proxies' user data
+    pub global_name: u32,
+    pub output: wl_output::WlOutput,
+    pub xdg_output: Option<zxdg_output_v1::ZxdgOutputV1>,
+    /// Connector name such as "DP-1", from xdg_output
+    pub name: Option<String>,
+    /// Current mode in physical pixels
+    pub size: Option<[u32; 2]>,
+    pub position: [i32; 2],
+    /// Integer buffer scale advertised by the output
+    pub scale: i32,
+}
+
+/// Per-surface objects for fractional scaling: the compositor reports the
+/// preferred scale through `fractional`, and `viewport` maps the
+/// scale-sized buffer back onto the logical surface
+struct SurfaceScaler {
+    surface: wl_surface::WlSurface,
+    viewport: wp_viewport::WpViewport,
+    fractional: wp_fractional_scale_v1::WpFractionalScaleV1,
+}
+
 pub struct WaylandState {
     pub running: bool,
     pub configured: bool,
     pub compositor: Option<wl_compositor::WlCompositor>,
     pub layer_shell: Option<zwlr_layer_shell_v1::ZwlrLayerShellV1>,
+    /// Only used for the toplevel fallback when layer-shell is unavailable
+    pub xdg_wm_base: Option<xdg_wm_base::XdgWmBase>,
+    pub xdg_surface: Option<xdg_surface::XdgSurface>,
+    pub xdg_toplevel: Option<xdg_toplevel::XdgToplevel>,
+    pub idle_inhibit_manager: Option<zwp_idle_inhibit_manager_v1::ZwpIdleInhibitManagerV1>,
+    idle_inhibitor: Option<zwp_idle_inhibitor_v1::ZwpIdleInhibitorV1>,
     pub surface: Option<wl_surface::WlSurface>,
-    pub layer_surface: Option<zwlr_layer_surface_v1::ZwlrLayerSurfaceV1>,
     pub timer_surface: Option<wl_surface::WlSurface>,
-    pub timer_layer_surface: Option<zwlr_layer_surface_v1::ZwlrLayerSurfaceV1>,
     pub plasma_surface: Option<wl_surface::WlSurface>,
-    pub plasma_layer_surface: Option<zwlr_layer_surface_v1::ZwlrLayerSurfaceV1>,
     pub seat: Option<wl_seat::WlSeat>,
+    pub xdg_output_manager: Option<zxdg_output_manager_v1::ZxdgOutputManagerV1>,
+    pub outputs: Vec<OutputInfo>,
+    /// The output corna lives on, chosen by `select_output`
     pub output: Option<wl_output::WlOutput>,
     pub output_size: Option<[u32; 2]>,
+    pub output_scale: i32,
+    /// Set when the output we're on is unplugged; main moves the windows
+    pub output_lost: bool,
+    /// Set when the compositor closes one of our layer surfaces
+    pub surface_closed: bool,
+    pub fractional_scale_manager: Option<wp_fractional_scale_manager_v1::WpFractionalScaleManagerV1>,
+    pub viewporter: Option<wp_viewporter::WpViewporter>,
+    /// Preferred scale from wp_fractional_scale_v1, e.g. 1.5
+    pub fractional_scale: Option<f32>,
+    scalers: Vec<SurfaceScaler>,
     pub pointer: Option<wl_pointer::WlPointer>,
     pub keyboard: Option<wl_keyboard::WlKeyboard>,
+    pub touch: Option<wl_touch::WlTouch>,
+    /// The touch point being followed; later fingers are ignored until it lifts
+    pub touch_id: Option<i32>,
     pub surface_pos: Vec2,
+    /// Vertical scroll distance not yet turned into a step, and the distance
+    /// per step (`scroll.step`)
+    scroll_accum: f32,
+    pub scroll_step: f32,
+    /// A wheel notch already produced this frame's step
+    scroll_discrete: bool,
     pub pending_events: Vec<UiEvent>,
     pub xkb_context: Context,
     pub xkb_keymap: Option<Keymap>,
@@ -52,17 +106,33 @@ impl WaylandState {
             configured: false,
             compositor: None,
             layer_shell: None,
+            xdg_wm_base: None,
+            xdg_surface: None,
+            xdg_toplevel: None,
+            idle_inhibit_manager: None,
+            idle_inhibitor: None,
             surface: None,
-            layer_surface: None,
             timer_surface: None,
-            timer_layer_surface: None,
             plasma_surface: None,
-            plasma_layer_surface: None,
             seat: None,
+            xdg_output_manager: None,
+            outputs: Vec::new(),
             output: None,
             output_size: None,
+            output_scale: 1,
+            output_lost: false,
+            surface_closed: false,
+            fractional_scale_manager: None,
+            viewporter: None,
+            fractional_scale: None,
+            scalers: Vec::new(),
             pointer: None,
             keyboard: None,
+            touch: None,
+            touch_id: None,
+            scroll_accum: 0.0,
+            scroll_step: 10.0,
+            scroll_discrete: false,
             surface_pos: Vec2 { x: 0.0, y: 0.0 },
             pending_events: Vec::new(),
             xkb_context: Context::new(FFI_CONTEXT_NO_FLAGS),
@@ -71,6 +141,145 @@ impl WaylandState {
             active_surface: None,
         }
     }
+
+    /// Pick the output to place surfaces on: the one whose connector name
+    /// matches `name`, otherwise the first output the compositor announced
+    pub fn select_output(&mut self, name: Option<&str>) {
+        let by_name = name.and_then(|wanted| {
+            let found = self.outputs.iter().find(|o| o.name.as_deref() == Some(wanted));
+            if found.is_none() {
+                let known: Vec<_> = self.outputs.iter().filter_map(|o| o.name.as_deref()).collect();
+                log::warn!("Output '{}' not found (available: {:?}), using the first output", wanted, known);
+            }
+            found
+        });
+
+        if let Some(info) = by_name.or_else(|| self.outputs.first()) {
+            log::info!("Using output {:?} ({:?})", info.name, info.size);
+            self.output = Some(info.output.clone());
+            self.output_size = info.size;
+            self.output_scale = info.scale;
+        }
+    }
+
+    /// Whether to move to another output: we have none left and one has
+    /// appeared, or the configured one is back (e.g. after redocking)
+    pub fn should_switch_output(&self, wanted: Option<&str>) -> bool {
+        let ready = |o: &&OutputInfo| o.size.is_some();
+        let Some(current) = &self.output else {
+            return self.outputs.iter().any(|o| ready(&o));
+        };
+        let Some(wanted) = wanted else { return false };
+        let current_name = self.outputs.iter().find(|o| &o.output == current).and_then(|o| o.name.as_deref());
+        current_name != Some(wanted)
+            && self.outputs.iter().filter(ready).any(|o| o.name.as_deref() == Some(wanted))
+    }
+
+    /// Keep the screen awake while the clock surface exists. Returns false if
+    /// the compositor has no idle-inhibit support.
+    pub fn set_idle_inhibited(&mut self, inhibit: bool, qh: &QueueHandle<Self>) -> bool {
+        let Some(manager) = &self.idle_inhibit_manager else { return false };
+        match (inhibit, self.idle_inhibitor.take()) {
+            (true, None) => {
+                if let Some(surface) = &self.surface {
+                    self.idle_inhibitor = Some(manager.create_inhibitor(surface, qh, ()));
+                }
+            }
+            (true, Some(inhibitor)) => self.idle_inhibitor = Some(inhibitor),
+            (false, Some(inhibitor)) => inhibitor.destroy(),
+            (false, None) => {}
+        }
+        true
+    }
+
+    fn uses_fractional_scale(&self) -> bool {
+        self.fractional_scale_manager.is_some() && self.viewporter.is_some()
+    }
+
+    /// Rendering scale: the compositor's fractional preference when it has
+    /// one, otherwise the integer output scale
+    pub fn scale(&self) -> f32 {
+        match self.fractional_scale {
+            Some(scale) if self.uses_fractional_scale() => scale,
+            _ => self.output_scale as f32,
+        }
+    }
+
+    /// Value for `wl_surface.set_buffer_scale`; fractional scaling goes
+    /// through the viewport instead and keeps this at 1
+    pub fn buffer_scale(&self) -> i32 {
+        if self.uses_fractional_scale() { 1 } else { self.output_scale }
+    }
+
+    /// Which of our surfaces `surface` is, for routing input
+    fn surface_kind(&self, surface: &wl_surface::WlSurface) -> Option<ActiveSurface> {
+        if self.surface.as_ref() == Some(surface) {
+            Some(ActiveSurface::Clock)
+        } else if self.timer_surface.as_ref() == Some(surface) {
+            Some(ActiveSurface::Timer)
+        } else if self.plasma_surface.as_ref() == Some(surface) {
+            Some(ActiveSurface::Plasma)
+        } else {
+            None
+        }
+    }
+
+    /// Tell the compositor `surface` is `size` logical pixels regardless of
+    /// its buffer size. Creates the surface's fractional-scale objects on
+    /// first use; does nothing without fractional scaling support.
+    pub fn set_surface_size(&mut self, surface: &wl_surface::WlSurface, size: [u32; 2], qh: &QueueHandle<Self>) {
+        let (Some(manager), Some(viewporter)) = (&self.fractional_scale_manager, &self.viewporter) else { return };
+        if !self.scalers.iter().any(|s| s.surface == *surface) {
+            self.scalers.push(SurfaceScaler {
+                surface: surface.clone(),
+                viewport: viewporter.get_viewport(surface, qh, ()),
+                fractional: manager.get_fractional_scale(surface, qh, ()),
+            });
+        }
+        if let Some(scaler) = self.scalers.iter().find(|s| s.surface == *surface) {
+            // A zero size would be a protocol error
+            if size[0] > 0 && size[1] > 0 {
+                scaler.viewport.set_destination(size[0] as i32, size[1] as i32);
+            }
+        }
+    }
+
+    /// Queue one scroll step for an axis `value`: negative is up, which
+    /// `UiEvent::Scroll` reports as a positive delta
+    fn push_scroll(&mut self, value: f32) {
+        let delta = if value < 0.0 { 1.0 } else { -1.0 };
+        // Include which surface the scroll happened on
+        self.pending_events.push(UiEvent::Scroll {
+            delta,
+            surface: self.active_surface,
+            modifiers: self.modifiers(),
+        });
+    }
+
+    /// Modifiers currently held, as far as the keyboard focus lets us know
+    fn modifiers(&self) -> Modifiers {
+        let Some(xkb_state) = &self.xkb_state else { return Modifiers::default() };
+        let active = |name| xkb_state.mod_name_is_active(name, xkb::STATE_MODS_EFFECTIVE);
+        Modifiers {
+            ctrl: active(xkb::MOD_NAME_CTRL),
+            alt: active(xkb::MOD_NAME_ALT),
+            shift: active(xkb::MOD_NAME_SHIFT),
+            logo: active(xkb::MOD_NAME_LOGO),
+        }
+    }
+
+    /// Release the fractional-scale objects of a surface about to be destroyed
+    pub fn forget_surface(&mut self, surface: &wl_surface::WlSurface) {
+        if let Some(index) = self.scalers.iter().position(|s| s.surface == *surface) {
+            let scaler = self.scalers.remove(index);
+            scaler.fractional.destroy();
+            scaler.viewport.destroy();
+        }
+    }
+
+    fn output_info_mut(&mut self, global_name: u32) -> Option<&mut OutputInfo> {
+        self.outputs.iter_mut().find(|o| o.global_name == global_name)
+    }
 }
 
 impl Dispatch<wl_registry::WlRegistry, ()> for WaylandState {
@@ -82,6 +291,20 @@ impl Dispatch<wl_registry::WlRegistry, ()> for WaylandState {
         _: &Connection,
         qh: &QueueHandle<Self>,
     ) {
+        if let wl_registry::Event::GlobalRemove { name } = event {
+            // Only outputs are expected to come and go
+            let Some(index) = state.outputs.iter().position(|o| o.global_name == name) else { return };
+            let info = state.outputs.remove(index);
+            log::info!("Output {:?} removed", info.name);
+            if let Some(xdg_output) = info.xdg_output {
+                xdg_output.destroy();
+            }
+            if state.output.as_ref() == Some(&info.output) {
+                state.output = None;
+                state.output_lost = true;
+            }
+            return;
+        }
         if let wl_registry::Event::Global {
             name,
             interface,
@@ -99,14 +322,51 @@ impl Dispatch<wl_registry::WlRegistry, ()> for WaylandState {
                     state.compositor = Some(compositor);
                 }
                 "zwlr_layer_shell_v1" => {
+                    // v4 is needed for on-demand keyboard focus
                     let layer_shell = registry.bind::<zwlr_layer_shell_v1::ZwlrLayerShellV1, _, _>(
                         name,
-                        version.min(1),
+                        version.min(4),
                         qh,
                         (),
                     );
                     state.layer_shell = Some(layer_shell);
                 }
+                "zwp_idle_inhibit_manager_v1" => {
+                    let manager = registry.bind::<zwp_idle_inhibit_manager_v1::ZwpIdleInhibitManagerV1, _, _>(
+                        name,
+                        1,
+                        qh,
+                        (),
+                    );
+                    state.idle_inhibit_manager = Some(manager);
+                }
+                "wp_fractional_scale_manager_v1" => {
+                    let manager = registry.bind::<wp_fractional_scale_manager_v1::WpFractionalScaleManagerV1, _, _>(
+                        name,
+                        1,
+                        qh,
+                        (),
+                    );
+                    state.fractional_scale_manager = Some(manager);
+                }
+                "wp_viewporter" => {
+                    let viewporter = registry.bind::<wp_viewporter::WpViewporter, _, _>(
+                        name,
+                        1,
+                        qh,
+                        (),
+                    );
+                    state.viewporter = Some(viewporter);
+                }
+                "xdg_wm_base" => {
+                    let wm_base = registry.bind::<xdg_wm_base::XdgWmBase, _, _>(
+                        name,
+                        version.min(2),
+                        qh,
+                        (),
+                    );
+                    state.xdg_wm_base = Some(wm_base);
+                }
                 "wl_seat" => {
                     let seat = registry.bind::<wl_seat::WlSeat, _, _>(
                         name,
@@ -121,9 +381,33 @@ impl Dispatch<wl_registry::WlRegistry, ()> for WaylandState {
                         name,
                         version.min(2),
                         qh,
+                        name,
+                    );
+                    let xdg_output = state.xdg_output_manager.as_ref()
+                        .map(|manager| manager.get_xdg_output(&output, qh, name));
+                    state.outputs.push(OutputInfo {
+                        global_name: name,
+                        output,
+                        xdg_output,
+                        name: None,
+                        size: None,
+                        position: [0, 0],
+                        scale: 1,
+                    });
+                }
+                "zxdg_output_manager_v1" => {
+                    // v2 adds the connector name event
+                    let manager = registry.bind::<zxdg_output_manager_v1::ZxdgOutputManagerV1, _, _>(
+                        name,
+                        version.min(3),
+                        qh,
                         (),
                     );
-                    state.output = Some(output);
+                    // Outputs announced before the manager still need their xdg_output
+                    for info in state.outputs.iter_mut().filter(|o| o.xdg_output.is_none()) {
+                        info.xdg_output = Some(manager.get_xdg_output(&info.output, qh, info.global_name));
+                    }
+                    state.xdg_output_manager = Some(manager);
                 }
                 _ => {}
             }
@@ -154,21 +438,7 @@ impl Dispatch<wl_pointer::WlPointer, ()> for WaylandState {
                 state.surface_pos.y = surface_y as f32;
 
                 // Determine which surface the pointer entered
-                if let Some(ref main_surface) = state.surface {
-                    if surface == *main_surface {
-                        state.active_surface = Some(ActiveSurface::Clock);
-                    }
-                }
-                if let Some(ref timer_surf) = state.timer_surface {
-                    if surface == *timer_surf {
-                        state.active_surface = Some(ActiveSurface::Timer);
-                    }
-                }
-                if let Some(ref plasma_surf) = state.plasma_surface {
-                    if surface == *plasma_surf {
-                        state.active_surface = Some(ActiveSurface::Plasma);
-                    }
-                }
+                state.active_surface = state.surface_kind(&surface);
 
                 state.pending_events.push(UiEvent::PointerEnter { pos: state.surface_pos });
             }
@@ -183,27 +453,74 @@ impl Dispatch<wl_pointer::WlPointer, ()> for WaylandState {
                 state.pending_events.push(UiEvent::PointerMove { pos: state.surface_pos });
             }
             wl_pointer::Event::Button { button, state: btn_state, .. } => {
-                if button == 0x110 || button == 0x111 {  // BTN_LEFT or BTN_RIGHT
+                if (0x110..=0x112).contains(&button) {  // BTN_LEFT, BTN_RIGHT or BTN_MIDDLE
                     let ev = match btn_state {
-                        wayland_client::WEnum::Value(wl_pointer::ButtonState::Pressed) => UiEvent::PointerDown { pos: state.surface_pos, button },
+                        wayland_client::WEnum::Value(wl_pointer::ButtonState::Pressed) => UiEvent::PointerDown { pos: state.surface_pos, button, surface: state.active_surface },
                         wayland_client::WEnum::Value(wl_pointer::ButtonState::Released) => UiEvent::PointerUp,
                         _ => return,
                     };
                     state.pending_events.push(ev);
                 }
             }
-            wl_pointer::Event::Axis { axis, value, .. } => {
-                // Handle scroll wheel events
-                if let wayland_client::WEnum::Value(wl_pointer::Axis::VerticalScroll) = axis {
-                    // Negative value = scroll up, positive = scroll down
-                    let delta = if value < 0.0 { 1.0 } else { -1.0 };
-                    // Include which surface the scroll happened on
-                    state.pending_events.push(UiEvent::Scroll {
-                        delta,
-                        surface: state.active_surface,
-                    });
+            // Wheel notches step once each; the continuous axis event that
+            // follows in the same frame is then skipped
+            wl_pointer::Event::AxisDiscrete { axis: wayland_client::WEnum::Value(wl_pointer::Axis::VerticalScroll), discrete } => {
+                state.scroll_discrete = true;
+                state.scroll_accum = 0.0;
+                if discrete != 0 {
+                    state.push_scroll(discrete as f32);
                 }
             }
+            wl_pointer::Event::Axis { axis: wayland_client::WEnum::Value(wl_pointer::Axis::VerticalScroll), value, .. } => {
+                if std::mem::take(&mut state.scroll_discrete) {
+                    return;
+                }
+                // Touchpads send many small values; step once enough has built up
+                state.scroll_accum += value as f32;
+                if state.scroll_accum.abs() >= state.scroll_step {
+                    let value = state.scroll_accum;
+                    state.scroll_accum = 0.0;
+                    state.push_scroll(value);
+                }
+            }
+            wl_pointer::Event::AxisStop { axis: wayland_client::WEnum::Value(wl_pointer::Axis::VerticalScroll), .. } => {
+                state.scroll_accum = 0.0;
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<wl_touch::WlTouch, ()> for WaylandState {
+    fn event(
+        state: &mut Self,
+        _: &wl_touch::WlTouch,
+        event: wl_touch::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        // Only the first touch point drives input, as a pointer on the
+        // surface it went down on
+        match event {
+            wl_touch::Event::Down { surface, id, x, y, .. } if state.touch_id.is_none() => {
+                state.touch_id = Some(id);
+                state.surface_pos = Vec2::new(x as f32, y as f32);
+                state.active_surface = state.surface_kind(&surface);
+                state.pending_events.push(UiEvent::TouchDown { pos: state.surface_pos, surface: state.active_surface });
+            }
+            wl_touch::Event::Motion { id, x, y, .. } if state.touch_id == Some(id) => {
+                state.surface_pos = Vec2::new(x as f32, y as f32);
+                state.pending_events.push(UiEvent::PointerMove { pos: state.surface_pos });
+            }
+            wl_touch::Event::Up { id, .. } if state.touch_id == Some(id) => {
+                state.touch_id = None;
+                state.pending_events.push(UiEvent::PointerUp);
+            }
+            wl_touch::Event::Cancel if state.touch_id.is_some() => {
+                state.touch_id = None;
+                state.pending_events.push(UiEvent::TouchCancel);
+            }
             _ => {}
         }
     }
@@ -227,6 +544,9 @@ impl Dispatch<wl_seat::WlSeat, ()> for WaylandState {
                     if caps.contains(wl_seat::Capability::Keyboard) {
                         state.keyboard = Some(seat.get_keyboard(qh, ()));
                     }
+                    if caps.contains(wl_seat::Capability::Touch) && state.touch.is_none() {
+                        state.touch = Some(seat.get_touch(qh, ()));
+                    }
                 }
             }
             _ => {}
@@ -243,25 +563,197 @@ impl Dispatch<wl_keyboard::WlKeyboard, ()> for WaylandState {
         _: &Connection,
         _: &QueueHandle<Self>,
     ) {
-        // Temporarily disable keyboard handling to avoid crashes
-        // TODO: Fix keymap parsing issue
         match event {
+            wl_keyboard::Event::Keymap { format, fd, size } => {
+                // Only xkb v1 keymaps can be compiled; compositors may also send
+                // `NoKeymap`, and feeding that to xkbcommon is what used to crash
+                if format != wayland_client::WEnum::Value(wl_keyboard::KeymapFormat::XkbV1) {
+                    log::warn!("Ignoring unsupported keymap format: {:?}", format);
+                    return;
+                }
+
+                let keymap = unsafe {
+                    Keymap::new_from_fd(
+                        &state.xkb_context,
+                        fd,
+                        size as usize,
+                        xkb::KEYMAP_FORMAT_TEXT_V1,
+                        FFI_KEYMAP_COMPILE_NO_FLAGS,
+                    )
+                };
+                match keymap {
+                    Ok(Some(keymap)) => {
+                        state.xkb_state = Some(XkbState::new(&keymap));
+                        state.xkb_keymap = Some(keymap);
+                    }
+                    Ok(None) => log::warn!("Failed to compile keymap"),
+                    Err(e) => log::warn!("Failed to map keymap: {}", e),
+                }
+            }
+            wl_keyboard::Event::Modifiers { mods_depressed, mods_latched, mods_locked, group, .. } => {
+                if let Some(xkb_state) = state.xkb_state.as_mut() {
+                    xkb_state.update_mask(mods_depressed, mods_latched, mods_locked, 0, 0, group);
+                }
+            }
+            wl_keyboard::Event::Key { key, state: key_state, .. } => {
+                if key_state != wayland_client::WEnum::Value(wl_keyboard::KeyState::Pressed) {
+                    return;
+                }
+                if let Some(xkb_state) = &state.xkb_state {
+                    // Wayland sends evdev keycodes; xkb keycodes are offset by 8
+                    let keysym = xkb_state.key_get_one_sym(xkb::Keycode::new(key + 8));
+                    let modifiers = state.modifiers();
+                    state.pending_events.push(UiEvent::Key { keysym: keysym.raw(), modifiers });
+                }
+            }
             _ => {}
         }
     }
 }
 
-impl Dispatch<wl_output::WlOutput, ()> for WaylandState {
+impl Dispatch<wl_output::WlOutput, u32> for WaylandState {
     fn event(
         state: &mut Self,
-        _: &wl_output::WlOutput,
+        output: &wl_output::WlOutput,
         event: wl_output::Event,
+        global_name: &u32,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        // Mode and scale of the output we're on are followed live so windows
+        // re-layout and re-render crisply when they change
+        if state.output.as_ref() == Some(output) {
+            match event {
+                wl_output::Event::Mode { flags: wayland_client::WEnum::Value(flags), width, height, .. }
+                    if flags.contains(wl_output::Mode::Current) =>
+                {
+                    state.output_size = Some([width as u32, height as u32]);
+                }
+                wl_output::Event::Scale { factor } => state.output_scale = factor,
+                _ => {}
+            }
+        }
+        let Some(info) = state.output_info_mut(*global_name) else { return };
+        match event {
+            // Outputs list every supported mode; only the current one matters
+            wl_output::Event::Mode { flags: wayland_client::WEnum::Value(flags), width, height, .. }
+                if flags.contains(wl_output::Mode::Current) =>
+            {
+                info.size = Some([width as u32, height as u32]);
+            }
+            wl_output::Event::Geometry { x, y, .. } => {
+                info.position = [x, y];
+            }
+            wl_output::Event::Scale { factor } => {
+                info.scale = factor;
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<zxdg_output_manager_v1::ZxdgOutputManagerV1, ()> for WaylandState {
+    fn event(_: &mut Self, _: &zxdg_output_manager_v1::ZxdgOutputManagerV1, _: zxdg_output_manager_v1::Event, _: &(), _: &Connection, _: &QueueHandle<Self>) {}
+}
+
+impl Dispatch<zxdg_output_v1::ZxdgOutputV1, u32> for WaylandState {
+    fn event(
+        state: &mut Self,
+        _: &zxdg_output_v1::ZxdgOutputV1,
+        event: zxdg_output_v1::Event,
+        global_name: &u32,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let Some(info) = state.output_info_mut(*global_name) else { return };
+        match event {
+            zxdg_output_v1::Event::Name { name } => info.name = Some(name),
+            zxdg_output_v1::Event::LogicalPosition { x, y } => info.position = [x, y],
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<zwp_idle_inhibit_manager_v1::ZwpIdleInhibitManagerV1, ()> for WaylandState {
+    fn event(_: &mut Self, _: &zwp_idle_inhibit_manager_v1::ZwpIdleInhibitManagerV1, _: zwp_idle_inhibit_manager_v1::Event, _: &(), _: &Connection, _: &QueueHandle<Self>) {}
+}
+
+impl Dispatch<zwp_idle_inhibitor_v1::ZwpIdleInhibitorV1, ()> for WaylandState {
+    fn event(_: &mut Self, _: &zwp_idle_inhibitor_v1::ZwpIdleInhibitorV1, _: zwp_idle_inhibitor_v1::Event, _: &(), _: &Connection, _: &QueueHandle<Self>) {}
+}
+
+impl Dispatch<wp_fractional_scale_manager_v1::WpFractionalScaleManagerV1, ()> for WaylandState {
+    fn event(_: &mut Self, _: &wp_fractional_scale_manager_v1::WpFractionalScaleManagerV1, _: wp_fractional_scale_manager_v1::Event, _: &(), _: &Connection, _: &QueueHandle<Self>) {}
+}
+
+impl Dispatch<wp_fractional_scale_v1::WpFractionalScaleV1, ()> for WaylandState {
+    fn event(
+        state: &mut Self,
+        _: &wp_fractional_scale_v1::WpFractionalScaleV1,
+        event: wp_fractional_scale_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        // All our surfaces live on one output, so any of them reporting is enough
+        if let wp_fractional_scale_v1::Event::PreferredScale { scale } = event {
+            // Sent as a multiple of 1/120
+            state.fractional_scale = Some(scale as f32 / 120.0);
+        }
+    }
+}
+
+impl Dispatch<wp_viewporter::WpViewporter, ()> for WaylandState {
+    fn event(_: &mut Self, _: &wp_viewporter::WpViewporter, _: wp_viewporter::Event, _: &(), _: &Connection, _: &QueueHandle<Self>) {}
+}
+
+impl Dispatch<wp_viewport::WpViewport, ()> for WaylandState {
+    fn event(_: &mut Self, _: &wp_viewport::WpViewport, _: wp_viewport::Event, _: &(), _: &Connection, _: &QueueHandle<Self>) {}
+}
+
+impl Dispatch<xdg_wm_base::XdgWmBase, ()> for WaylandState {
+    fn event(
+        _: &mut Self,
+        wm_base: &xdg_wm_base::XdgWmBase,
+        event: xdg_wm_base::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let xdg_wm_base::Event::Ping { serial } = event {
+            wm_base.pong(serial);
+        }
+    }
+}
+
+impl Dispatch<xdg_surface::XdgSurface, ()> for WaylandState {
+    fn event(
+        state: &mut Self,
+        surface: &xdg_surface::XdgSurface,
+        event: xdg_surface::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let xdg_surface::Event::Configure { serial } = event {
+            surface.ack_configure(serial);
+            state.configured = true;
+        }
+    }
+}
+
+impl Dispatch<xdg_toplevel::XdgToplevel, ()> for WaylandState {
+    fn event(
+        state: &mut Self,
+        _: &xdg_toplevel::XdgToplevel,
+        event: xdg_toplevel::Event,
         _: &(),
         _: &Connection,
         _: &QueueHandle<Self>,
     ) {
-        if let wl_output::Event::Mode { width, height, .. } = event {
-            state.output_size = Some([width as u32, height as u32]);
+        // The toplevel keeps the size we ask for, so configure sizes are ignored
+        if let xdg_toplevel::Event::Close = event {
+            state.running = false;
         }
     }
 }
@@ -294,7 +786,8 @@ impl Dispatch<zwlr_layer_surface_v1::ZwlrLayerSurfaceV1, ()> for WaylandState {
                 state.configured = true;
             }
             zwlr_layer_surface_v1::Event::Closed => {
-                state.running = false;
+                // Main decides whether this was an unplug or a request to go away
+                state.surface_closed = true;
             }
             _ => {}
         }
@@ -1,14 +1,20 @@
-use wayland_client::{protocol::wl_surface::WlSurface, QueueHandle};
+use wayland_client::{protocol::{wl_output::WlOutput, wl_surface::WlSurface}, Proxy, QueueHandle};
 use wayland_protocols_wlr::layer_shell::v1::client::{
     zwlr_layer_shell_v1::{self, ZwlrLayerShellV1},
     zwlr_layer_surface_v1::{self, ZwlrLayerSurfaceV1},
 };
 use std::collections::HashMap;
+use crate::config::Anchor;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum WindowId {
     Clock,
     Timer,
+    Plasma,
+    WorldClock,
+    Battery,
+    SysMon,
+    Countdown,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -17,9 +23,25 @@ pub enum AnchorPoint {
     TopRight,
     BottomLeft,
     BottomRight,
+    TopCenter,
+    BottomCenter,
     Center,
 }
 
+impl From<Anchor> for AnchorPoint {
+    fn from(anchor: Anchor) -> Self {
+        match anchor {
+            Anchor::TopLeft => AnchorPoint::TopLeft,
+            Anchor::TopRight => AnchorPoint::TopRight,
+            Anchor::BottomLeft => AnchorPoint::BottomLeft,
+            Anchor::BottomRight => AnchorPoint::BottomRight,
+            Anchor::TopCenter => AnchorPoint::TopCenter,
+            Anchor::BottomCenter => AnchorPoint::BottomCenter,
+            Anchor::Center => AnchorPoint::Center,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum RelativePosition {
     LeftOf { gap: i32 },
@@ -35,6 +57,9 @@ pub struct WindowConfig {
     pub position: PositionConfig,
     pub layer: zwlr_layer_shell_v1::Layer,
     pub name: String,
+    pub exclusive_zone: i32,
+    /// Ask for keyboard focus on click (needs layer-shell v4)
+    pub keyboard: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -51,6 +76,11 @@ pub enum PositionConfig {
         x: i32,
         y: i32,
     },
+    /// Stretch over the whole output
+    Fill,
+    /// Stretch across the output's top or bottom edge, as tall as the
+    /// window's size
+    Band { top: bool },
 }
 
 pub struct ManagedWindow {
@@ -62,14 +92,17 @@ pub struct ManagedWindow {
 
 pub struct WindowManager {
     windows: HashMap<WindowId, ManagedWindow>,
+    /// Logical size of the output the windows live on
     screen_size: [u32; 2],
+    output: Option<WlOutput>,
 }
 
 impl WindowManager {
-    pub fn new(screen_size: [u32; 2]) -> Self {
+    pub fn new(screen_size: [u32; 2], output: Option<WlOutput>) -> Self {
         Self {
             windows: HashMap::new(),
             screen_size,
+            output,
         }
     }
 
@@ -86,7 +119,7 @@ impl WindowManager {
         // Create layer surface
         let layer_surface = layer_shell.get_layer_surface(
             &surface,
-            None,
+            self.output.as_ref(),
             config.layer,
             config.name.clone(),
             qh,
@@ -109,6 +142,9 @@ impl WindowManager {
                     AnchorPoint::BottomRight => {
                         zwlr_layer_surface_v1::Anchor::Bottom | zwlr_layer_surface_v1::Anchor::Right
                     }
+                    // A single edge centers the surface along it
+                    AnchorPoint::TopCenter => zwlr_layer_surface_v1::Anchor::Top,
+                    AnchorPoint::BottomCenter => zwlr_layer_surface_v1::Anchor::Bottom,
                     AnchorPoint::Center => zwlr_layer_surface_v1::Anchor::empty(),
                 };
 
@@ -122,11 +158,30 @@ impl WindowManager {
                 );
                 layer_surface.set_margin(actual_position[1], 0, 0, actual_position[0]);
             }
+            PositionConfig::Fill => {
+                layer_surface.set_anchor(zwlr_layer_surface_v1::Anchor::all());
+            }
+            PositionConfig::Band { top } => {
+                let edge = if *top { zwlr_layer_surface_v1::Anchor::Top } else { zwlr_layer_surface_v1::Anchor::Bottom };
+                layer_surface.set_anchor(edge | zwlr_layer_surface_v1::Anchor::Left | zwlr_layer_surface_v1::Anchor::Right);
+            }
         }
 
-        layer_surface.set_exclusive_zone(0);
+        layer_surface.set_exclusive_zone(config.exclusive_zone);
         layer_surface.set_size(config.size[0], config.size[1]);
 
+        // Older layer-shell versions only offer an exclusive grab, which would
+        // steal the keyboard outright
+        if config.keyboard {
+            if layer_shell.version() >= 4 {
+                layer_surface.set_keyboard_interactivity(
+                    zwlr_layer_surface_v1::KeyboardInteractivity::OnDemand,
+                );
+            } else {
+                log::info!("Layer shell v{} lacks on-demand keyboard focus, shortcuts disabled", layer_shell.version());
+            }
+        }
+
         surface.commit();
 
         let window = ManagedWindow {
@@ -140,6 +195,29 @@ impl WindowManager {
         self.windows.get(&config.id).unwrap()
     }
 
+    /// Re-create every window's layer surface on `output`, e.g. after the
+    /// old one was unplugged. The wl_surfaces are kept; each gets a fresh
+    /// configure before it may show a buffer again.
+    pub fn move_to_output(
+        &mut self,
+        output: Option<WlOutput>,
+        screen_size: [u32; 2],
+        layer_shell: &ZwlrLayerShellV1,
+        qh: &QueueHandle<crate::wayland::WaylandState>,
+    ) {
+        self.output = output;
+        self.screen_size = screen_size;
+        // Referenced windows first so relative positions resolve
+        for id in self.layout_order() {
+            let Some(window) = self.windows.remove(&id) else { continue };
+            // A new layer surface must start out without a buffer
+            window.surface.attach(None, 0, 0);
+            window.surface.commit();
+            window.layer_surface.destroy();
+            self.create_window(window.config, window.surface, layer_shell, qh);
+        }
+    }
+
     pub fn destroy_window(&mut self, id: WindowId) {
         if let Some(window) = self.windows.remove(&id) {
             window.layer_surface.destroy();
@@ -147,6 +225,96 @@ impl WindowManager {
         }
     }
 
+    /// Destroy every window, e.g. on shutdown
+    pub fn destroy_all(&mut self) {
+        for (_, window) in self.windows.drain() {
+            window.layer_surface.destroy();
+            window.surface.destroy();
+        }
+    }
+
+    /// Move an anchored window to new margins (top, right, bottom, left)
+    pub fn set_margin(&mut self, id: WindowId, margin: [i32; 4]) {
+        let Some(window) = self.windows.get_mut(&id) else { return };
+        if let PositionConfig::Anchored { margin: current, .. } = &mut window.config.position {
+            *current = margin;
+            window.layer_surface.set_margin(margin[0], margin[1], margin[2], margin[3]);
+            self.reflow();
+        }
+    }
+
+    /// Change a window's size and move the windows positioned relative to it
+    pub fn resize_window(&mut self, id: WindowId, size: [u32; 2]) {
+        let Some(window) = self.windows.get_mut(&id) else { return };
+        window.config.size = size;
+        window.layer_surface.set_size(size[0], size[1]);
+        self.reflow();
+    }
+
+    /// Recompute every window's position, referenced windows first, and
+    /// commit the surfaces whose margins changed
+    pub fn reflow(&mut self) {
+        for id in self.layout_order() {
+            if self.apply_position(id) {
+                if let Some(window) = self.windows.get(&id) {
+                    window.surface.commit();
+                }
+            }
+        }
+    }
+
+    /// Windows sorted so every `RelativeTo` target comes before the windows
+    /// placed against it. A cyclic reference is logged and broken at the
+    /// window that closes the loop, which then keeps its last position as reference.
+    fn layout_order(&self) -> Vec<WindowId> {
+        fn visit(
+            wm: &WindowManager,
+            id: WindowId,
+            visiting: &mut Vec<WindowId>,
+            order: &mut Vec<WindowId>,
+        ) {
+            if order.contains(&id) {
+                return;
+            }
+            if visiting.contains(&id) {
+                log::warn!("Cyclic window placement through {:?}, ignoring the reference", id);
+                return;
+            }
+            visiting.push(id);
+            if let Some(PositionConfig::RelativeTo { window, .. }) = wm.windows.get(&id).map(|w| &w.config.position) {
+                if wm.windows.contains_key(window) {
+                    visit(wm, *window, visiting, order);
+                }
+            }
+            visiting.pop();
+            order.push(id);
+        }
+
+        let mut ids: Vec<WindowId> = self.windows.keys().copied().collect();
+        ids.sort();
+        let mut order = Vec::with_capacity(ids.len());
+        let mut visiting = Vec::new();
+        for id in ids {
+            visit(self, id, &mut visiting, &mut order);
+        }
+        order
+    }
+
+    /// Recalculate a window's position, updating margins for windows we place
+    /// ourselves. Returns whether the margins changed.
+    fn apply_position(&mut self, id: WindowId) -> bool {
+        let Some(config) = self.windows.get(&id).map(|w| w.config.clone()) else { return false };
+        let position = self.calculate_position(&config);
+        let Some(window) = self.windows.get_mut(&id) else { return false };
+        let moved = window.actual_position != position;
+        window.actual_position = position;
+        let self_placed = matches!(config.position, PositionConfig::RelativeTo { .. } | PositionConfig::Absolute { .. });
+        if self_placed && moved {
+            window.layer_surface.set_margin(position[1], 0, 0, position[0]);
+        }
+        self_placed && moved
+    }
+
     pub fn get_window(&self, id: WindowId) -> Option<&ManagedWindow> {
         self.windows.get(&id)
     }
@@ -174,6 +342,14 @@ impl WindowManager {
                         self.screen_size[0] as i32 - config.size[0] as i32 - margin[1],
                         self.screen_size[1] as i32 - config.size[1] as i32 - margin[2],
                     ],
+                    AnchorPoint::TopCenter => [
+                        (self.screen_size[0] as i32 - config.size[0] as i32) / 2,
+                        margin[0],
+                    ],
+                    AnchorPoint::BottomCenter => [
+                        (self.screen_size[0] as i32 - config.size[0] as i32) / 2,
+                        self.screen_size[1] as i32 - config.size[1] as i32 - margin[2],
+                    ],
                     AnchorPoint::Center => [
                         (self.screen_size[0] as i32 - config.size[0] as i32) / 2,
                         (self.screen_size[1] as i32 - config.size[1] as i32) / 2,
@@ -209,11 +385,14 @@ impl WindowManager {
                 }
             }
             PositionConfig::Absolute { x, y } => [*x, *y],
+            PositionConfig::Fill => [0, 0],
+            PositionConfig::Band { top: true } => [0, 0],
+            PositionConfig::Band { top: false } => [0, self.screen_size[1] as i32 - config.size[1] as i32],
         }
     }
 
     pub fn update_screen_size(&mut self, size: [u32; 2]) {
         self.screen_size = size;
-        // Could recalculate positions here if needed
+        self.reflow();
     }
 }
\ No newline at end of file